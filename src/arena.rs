@@ -0,0 +1,372 @@
+use std::mem;
+use std::ops;
+use crate::invariant::InvariantError;
+
+// Handle to a value in an Arena. The generation tells apart the values that lived in the same
+// slot, so an index kept after its value was removed finds nothing instead of whatever reused
+// the slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Index {
+    slot: u32,
+    generation: u32,
+}
+
+enum Entry<T> {
+    Occupied { generation: u32, val: T },
+    // generation is the one the next value put in this slot gets
+    Free { generation: u32, next_free: Option<u32> },
+}
+
+// Slab allocator: every value lives in one Vec and removed slots are reused through a free
+// list. Nodes of a structure built on it sit next to each other in memory and dropping the
+// arena frees all of them with a single deallocation.
+pub struct Arena<T> {
+    entries: Vec<Entry<T>>,
+    // the slot removed last, each free slot points at the one freed before it
+    free_head: Option<u32>,
+    len: usize,
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Arena { entries: Vec::new(), free_head: None, len: 0 }
+    }
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Arena { entries: Vec::with_capacity(capacity), free_head: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // slots that can be used without growing the Vec, free ones included
+    pub fn capacity(&self) -> usize {
+        self.entries.capacity()
+    }
+
+    pub fn insert(&mut self, val: T) -> Index {
+        self.len += 1;
+
+        if let Some(slot) = self.free_head {
+            let entry = &mut self.entries[slot as usize];
+            let Entry::Free { generation, next_free } = *entry else {
+                unreachable!("the free list only holds free slots");
+            };
+            self.free_head = next_free;
+            *entry = Entry::Occupied { generation, val };
+            return Index { slot, generation };
+        }
+
+        let slot = u32::try_from(self.entries.len()).expect("an arena can't hold more than u32::MAX slots");
+        self.entries.push(Entry::Occupied { generation: 0, val });
+        Index { slot, generation: 0 }
+    }
+
+    pub fn remove(&mut self, index: Index) -> Option<T> {
+        let entry = self.entries.get_mut(index.slot as usize)?;
+        if !matches!(entry, Entry::Occupied { generation, .. } if *generation == index.generation) {
+            return None;
+        }
+
+        // a slot that ran out of generations is retired, reusing it could make an old index
+        // match again
+        let next_generation = index.generation.checked_add(1);
+        let free = Entry::Free { generation: next_generation.unwrap_or(u32::MAX), next_free: self.free_head };
+        let Entry::Occupied { val, .. } = mem::replace(entry, free) else {
+            unreachable!("the entry was checked above");
+        };
+        if next_generation.is_some() {
+            self.free_head = Some(index.slot);
+        }
+        self.len -= 1;
+        Some(val)
+    }
+
+    pub fn get(&self, index: Index) -> Option<&T> {
+        match self.entries.get(index.slot as usize)? {
+            Entry::Occupied { generation, val } if *generation == index.generation => Some(val),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, index: Index) -> Option<&mut T> {
+        match self.entries.get_mut(index.slot as usize)? {
+            Entry::Occupied { generation, val } if *generation == index.generation => Some(val),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, index: Index) -> bool {
+        self.get(index).is_some()
+    }
+
+    // Drops every value but keeps the slots, so the memory is reused and the indices handed out
+    // before stay dead
+    pub fn clear(&mut self) {
+        for slot in 0..self.entries.len() {
+            if let Entry::Occupied { generation, .. } = self.entries[slot] {
+                self.remove(Index { slot: slot as u32, generation });
+            }
+        }
+    }
+
+    // values in slot order, which is not the order they were inserted once slots get reused
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { entries: self.entries.iter().enumerate(), remaining: self.len }
+    }
+
+    // len must count the occupied slots and the free list must hold every free slot once, the
+    // retired ones aside
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        let occupied = self.entries.iter().filter(|entry| matches!(entry, Entry::Occupied { .. })).count();
+        if occupied != self.len {
+            return Err(InvariantError::WrongLength { stored: self.len, actual: occupied });
+        }
+
+        let mut on_free_list = vec![false; self.entries.len()];
+        let mut next = self.free_head;
+        while let Some(slot) = next {
+            let slot = slot as usize;
+            match self.entries.get(slot) {
+                Some(Entry::Free { next_free, .. }) if !on_free_list[slot] => {
+                    on_free_list[slot] = true;
+                    next = *next_free;
+                },
+                _ => return Err(InvariantError::FreeList { index: slot }),
+            }
+        }
+
+        for (slot, entry) in self.entries.iter().enumerate() {
+            if let Entry::Free { generation, .. } = entry && *generation != u32::MAX && !on_free_list[slot] {
+                return Err(InvariantError::FreeList { index: slot });
+            }
+        }
+        Ok(())
+    }
+}
+
+// panics on a removed value, like indexing a Vec out of bounds
+impl<T> ops::Index<Index> for Arena<T> {
+    type Output = T;
+
+    fn index(&self, index: Index) -> &T {
+        self.get(index).expect("no value at this index, it was removed")
+    }
+}
+
+impl<T> ops::IndexMut<Index> for Arena<T> {
+    fn index_mut(&mut self, index: Index) -> &mut T {
+        self.get_mut(index).expect("no value at this index, it was removed")
+    }
+}
+
+pub struct Iter<'a, T> {
+    entries: std::iter::Enumerate<std::slice::Iter<'a, Entry<T>>>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (Index, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (slot, entry) in self.entries.by_ref() {
+            if let Entry::Occupied { generation, val } = entry {
+                self.remaining -= 1;
+                return Some((Index { slot: slot as u32, generation: *generation }, val));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a Arena<T> {
+    type Item = (Index, &'a T);
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut arena = Arena::new();
+        assert!(arena.is_empty());
+        let a = arena.insert("a");
+        let b = arena.insert("b");
+        assert_ne!(a, b);
+        assert_eq!(arena.get(a), Some(&"a"));
+        assert_eq!(arena[b], "b");
+        assert_eq!(arena.len(), 2);
+
+        arena[a] = "c";
+        *arena.get_mut(b).unwrap() = "d";
+        assert_eq!(arena.iter().map(|(_, val)| *val).collect::<Vec<_>>(), vec!["c", "d"]);
+        assert_eq!(arena.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut arena = Arena::new();
+        let a = arena.insert(String::from("a"));
+        assert_eq!(arena.remove(a), Some(String::from("a")));
+        assert_eq!(arena.remove(a), None);
+        assert_eq!(arena.get(a), None);
+        assert!(!arena.contains(a));
+        assert!(arena.is_empty());
+        assert_eq!(arena.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_reused_slot_gets_a_new_generation() {
+        let mut arena = Arena::new();
+        let old = arena.insert(1);
+        arena.remove(old);
+        let new = arena.insert(2);
+
+        // same slot, so the Vec didn't grow, but the old index doesn't see the new value
+        assert_eq!(new.slot, old.slot);
+        assert_eq!(arena.entries.len(), 1);
+        assert_eq!(arena.get(old), None);
+        assert_eq!(arena.remove(old), None);
+        assert_eq!(arena.get(new), Some(&2));
+    }
+
+    #[test]
+    fn test_free_slots_are_reused_last_freed_first() {
+        let mut arena = Arena::new();
+        let indices: Vec<Index> = (0..5).map(|val| arena.insert(val)).collect();
+        arena.remove(indices[1]);
+        arena.remove(indices[3]);
+        assert_eq!(arena.validate(), Ok(()));
+
+        assert_eq!(arena.insert(30).slot, 3);
+        assert_eq!(arena.insert(10).slot, 1);
+        assert_eq!(arena.insert(5).slot, 5);
+        assert_eq!(arena.len(), 6);
+        assert_eq!(arena.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_exhausted_slot_is_retired() {
+        let mut arena = Arena::new();
+        let index = arena.insert(1);
+        arena.entries[0] = Entry::Occupied { generation: u32::MAX, val: 1 };
+        let last = Index { slot: 0, generation: u32::MAX };
+        assert_eq!(arena.remove(last), Some(1));
+        assert_eq!(arena.validate(), Ok(()));
+
+        // the slot is never handed out again
+        let fresh = arena.insert(2);
+        assert_eq!(fresh.slot, 1);
+        assert_eq!(arena.get(index), None);
+        assert_eq!(arena.get(last), None);
+    }
+
+    #[test]
+    fn test_clear_keeps_old_indices_dead() {
+        let mut arena = Arena::with_capacity(8);
+        let indices: Vec<Index> = (0..8).map(|val| arena.insert(val)).collect();
+        arena.clear();
+        assert!(arena.is_empty());
+        assert_eq!(arena.iter().next(), None);
+
+        let reused: Vec<Index> = (0..8).map(|val| arena.insert(val)).collect();
+        assert_eq!(arena.capacity(), 8);
+        for index in indices {
+            assert_eq!(arena.get(index), None);
+        }
+        for (val, index) in reused.into_iter().enumerate() {
+            assert_eq!(arena[index], val);
+        }
+        assert_eq!(arena.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut arena = Arena::new();
+        let a = arena.insert('a');
+        let b = arena.insert('b');
+        let c = arena.insert('c');
+        arena.remove(b);
+
+        let mut iter = arena.iter();
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.next(), Some((a, &'a')));
+        assert_eq!(iter.next(), Some((c, &'c')));
+        assert_eq!(iter.next(), None);
+        assert_eq!((&arena).into_iter().count(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "it was removed")]
+    fn test_index_with_removed_value_panics() {
+        let mut arena = Arena::new();
+        let index = arena.insert(1);
+        arena.remove(index);
+        let _ = arena[index];
+    }
+
+    #[test]
+    fn test_drop_values() {
+        use std::rc::Rc;
+        let alive = Rc::new(());
+        let mut arena = Arena::new();
+        let first = arena.insert(alive.clone());
+        arena.insert(alive.clone());
+        arena.insert(alive.clone());
+        drop(arena.remove(first));
+        assert_eq!(Rc::strong_count(&alive), 3);
+        drop(arena);
+        assert_eq!(Rc::strong_count(&alive), 1);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut arena = Arena::new();
+        let indices: Vec<Index> = (0..3).map(|val| arena.insert(val)).collect();
+        arena.remove(indices[0]);
+        arena.remove(indices[2]);
+        assert_eq!(arena.validate(), Ok(()));
+
+        arena.len = 3;
+        assert_eq!(arena.validate(), Err(InvariantError::WrongLength { stored: 3, actual: 1 }));
+        arena.len = 1;
+
+        // slot 0 dropped from the free list
+        arena.entries[2] = Entry::Free { generation: 1, next_free: None };
+        assert_eq!(arena.validate(), Err(InvariantError::FreeList { index: 0 }));
+
+        // an occupied slot on the free list
+        arena.entries[2] = Entry::Free { generation: 1, next_free: Some(1) };
+        assert_eq!(arena.validate(), Err(InvariantError::FreeList { index: 1 }));
+
+        // a free list that loops
+        arena.entries[2] = Entry::Free { generation: 1, next_free: Some(2) };
+        assert_eq!(arena.validate(), Err(InvariantError::FreeList { index: 2 }));
+
+        arena.entries[2] = Entry::Free { generation: 1, next_free: Some(0) };
+        assert_eq!(arena.validate(), Ok(()));
+    }
+}
@@ -0,0 +1,687 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::ops::{Bound, RangeBounds};
+
+use crate::avl_tree::{check_nodes, detach_min, rebalance, size, Link, Node};
+use crate::invariant::InvariantError;
+
+// Ordered key/value map balanced exactly like AvlTree.
+// Every node holds a (key, value) pair and only the key is compared,
+// so the value doesn't need to implement Ord.
+
+type MapLink<K, V> = Link<(K, V)>;
+
+#[derive(Default)]
+pub struct AvlMap<K, V> {
+    root: MapLink<K, V>,
+}
+
+// struct to hold the value for the iteration, same as the AVL tree one
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<(K, V)>>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        let mut current_node = node.right.as_ref();
+        while let Some(n) = current_node {
+            self.stack.push(n);
+            current_node = n.left.as_ref();
+        }
+        Some((&node.val.0, &node.val.1))
+    }
+}
+
+// Walks only the keys inside the range.
+// The stack starts with the path to the first key inside the range, so getting there is
+// O(log n), and the iteration stops at the first key past the end.
+pub struct Range<'a, K, V, Q: ?Sized, R> {
+    stack: Vec<&'a Node<(K, V)>>,
+    range: R,
+    _bound: std::marker::PhantomData<fn(&Q)>, // ties the range to the type it is compared as
+}
+
+impl<'a, K, V, Q, R> Iterator for Range<'a, K, V, Q, R>
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+    R: RangeBounds<Q>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        if !before_end(node.val.0.borrow(), self.range.end_bound()) {
+            self.stack.clear(); // everything left is even bigger
+            return None;
+        }
+
+        let mut current_node = node.right.as_ref();
+        while let Some(n) = current_node {
+            self.stack.push(n);
+            current_node = n.left.as_ref();
+        }
+        Some((&node.val.0, &node.val.1))
+    }
+}
+
+fn after_start<Q: Ord + ?Sized>(key: &Q, start: Bound<&Q>) -> bool {
+    match start {
+        Bound::Included(start) => key >= start,
+        Bound::Excluded(start) => key > start,
+        Bound::Unbounded => true,
+    }
+}
+
+fn before_end<Q: Ord + ?Sized>(key: &Q, end: Bound<&Q>) -> bool {
+    match end {
+        Bound::Included(end) => key <= end,
+        Bound::Excluded(end) => key < end,
+        Bound::Unbounded => true,
+    }
+}
+
+impl<K, V> AvlMap<K, V> {
+    pub fn new() -> Self {
+        Self { root: None }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iterator = Iter { stack: vec![] };
+        let mut current_node = self.root.as_ref();
+
+        while let Some(node) = current_node {
+            iterator.stack.push(node);
+            current_node = node.left.as_ref();
+        }
+        iterator
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_ref()?;
+        while let Some(left) = &node.left {
+            node = left;
+        }
+        Some((&node.val.0, &node.val.1))
+    }
+
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_ref()?;
+        while let Some(right) = &node.right {
+            node = right;
+        }
+        Some((&node.val.0, &node.val.1))
+    }
+}
+
+impl<K: Ord, V> AvlMap<K, V> {
+    // Checks every invariant of the tree: strictly increasing keys, the stored heights and sizes,
+    // and balance factors between -1 and 1.
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        check_nodes(&self.root, &mut 0)?;
+        let mut keys = self.keys().enumerate();
+        if let Some((_, mut previous)) = keys.next() {
+            for (index, key) in keys {
+                if previous >= key {
+                    return Err(InvariantError::OutOfOrder { index });
+                }
+                previous = key;
+            }
+        }
+        Ok(())
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<&Node<(K, V)>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut current_node = self.root.as_ref();
+        while let Some(node) = current_node {
+            match key.cmp(node.val.0.borrow()) {
+                Ordering::Equal => return Some(node),
+                Ordering::Less => current_node = node.left.as_ref(),
+                Ordering::Greater => current_node = node.right.as_ref(),
+            }
+        }
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|node| &node.val.1)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|node| (&node.val.0, &node.val.1))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut current_node = self.root.as_mut();
+        while let Some(node) = current_node {
+            match key.cmp(node.val.0.borrow()) {
+                Ordering::Equal => return Some(&mut node.val.1),
+                Ordering::Less => current_node = node.left.as_mut(),
+                Ordering::Greater => current_node = node.right.as_mut(),
+            }
+        }
+        None
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    // returns the old value if the key was already present, the key itself is kept
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let root = self.root.take();
+        let (root, old_value) = Self::insert_recursive(root, key, value);
+        self.root = Some(root);
+        old_value
+    }
+
+    fn insert_recursive(link: MapLink<K, V>, key: K, value: V) -> (Box<Node<(K, V)>>, Option<V>) {
+        let Some(mut node) = link else {
+            return (Node::leaf((key, value)), None);
+        };
+
+        let old_value = match key.cmp(&node.val.0) {
+            Ordering::Equal => {
+                let old_value = std::mem::replace(&mut node.val.1, value);
+                return (node, Some(old_value));
+            },
+            Ordering::Less => {
+                let (left, old_value) = Self::insert_recursive(node.left.take(), key, value);
+                node.left = Some(left);
+                old_value
+            },
+            Ordering::Greater => {
+                let (right, old_value) = Self::insert_recursive(node.right.take(), key, value);
+                node.right = Some(right);
+                old_value
+            },
+        };
+        node.update();
+        (rebalance(node), old_value)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let root = self.root.take();
+        let (root, removed) = Self::remove_recursive(root, key);
+        self.root = root;
+        removed
+    }
+
+    // same as AvlTree::delete_recursive, comparing keys only
+    fn remove_recursive<Q>(link: MapLink<K, V>, key: &Q) -> (MapLink<K, V>, Option<(K, V)>)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let Some(mut node) = link else {
+            return (None, None);
+        };
+
+        let removed = match key.cmp(node.val.0.borrow()) {
+            Ordering::Less => {
+                let (left, removed) = Self::remove_recursive(node.left.take(), key);
+                node.left = left;
+                removed
+            },
+            Ordering::Greater => {
+                let (right, removed) = Self::remove_recursive(node.right.take(), key);
+                node.right = right;
+                removed
+            },
+            Ordering::Equal => match (node.left.take(), node.right.take()) {
+                (None, None) => return (None, Some(node.val)),
+                (Some(child), None) | (None, Some(child)) => return (Some(child), Some(node.val)),
+                (Some(left), Some(right)) => {
+                    let (right, successor) = detach_min(right);
+                    node.left = Some(left);
+                    node.right = right;
+                    Some(std::mem::replace(&mut node.val, successor.val))
+                },
+            },
+        };
+
+        node.update();
+        (Some(rebalance(node)), removed)
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        match self.position(&key) {
+            Ok(rank) => Entry::Occupied(OccupiedEntry { map: self, key, rank }),
+            Err(rank) => Entry::Vacant(VacantEntry { map: self, key, rank }),
+        }
+    }
+
+    // How many keys are smaller than key: Ok if key is in the map, Err if not
+    // (then it's also where key would be inserted).
+    // Rotations never change the order, so the position stays good until a key is added or removed.
+    fn position(&self, key: &K) -> Result<usize, usize> {
+        let mut rank = 0;
+        let mut current_node = self.root.as_ref();
+        while let Some(node) = current_node {
+            match key.cmp(&node.val.0) {
+                Ordering::Less => current_node = node.left.as_ref(),
+                Ordering::Equal => return Ok(rank + size(&node.left)),
+                Ordering::Greater => {
+                    rank += size(&node.left) + 1;
+                    current_node = node.right.as_ref();
+                },
+            }
+        }
+        Err(rank)
+    }
+
+    // the value at a position, walking down with the subtree sizes
+    fn value_at(&self, mut rank: usize) -> Option<&V> {
+        let mut current_node = self.root.as_ref();
+        while let Some(node) = current_node {
+            let left_size = size(&node.left);
+            match rank.cmp(&left_size) {
+                Ordering::Less => current_node = node.left.as_ref(),
+                Ordering::Equal => return Some(&node.val.1),
+                Ordering::Greater => {
+                    rank -= left_size + 1;
+                    current_node = node.right.as_ref();
+                },
+            }
+        }
+        None
+    }
+
+    fn value_at_mut(&mut self, mut rank: usize) -> Option<&mut V> {
+        let mut current_node = self.root.as_mut();
+        while let Some(node) = current_node {
+            let left_size = size(&node.left);
+            match rank.cmp(&left_size) {
+                Ordering::Less => current_node = node.left.as_mut(),
+                Ordering::Equal => return Some(&mut node.val.1),
+                Ordering::Greater => {
+                    rank -= left_size + 1;
+                    current_node = node.right.as_mut();
+                },
+            }
+        }
+        None
+    }
+
+    // pairs whose key is inside range, in order. map.range(3..7), map.range("a".."c")
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V, Q, R>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        // stack the path to the first key inside the range:
+        // a node before the start is skipped together with its left subtree
+        let mut stack = Vec::new();
+        let mut current_node = self.root.as_ref();
+        while let Some(node) = current_node {
+            if after_start(node.val.0.borrow(), range.start_bound()) {
+                stack.push(&**node);
+                current_node = node.left.as_ref();
+            } else {
+                current_node = node.right.as_ref();
+            }
+        }
+
+        Range { stack, range, _bound: std::marker::PhantomData }
+    }
+}
+
+// A place in the map for a key.
+// The key is compared once, when the entry is made. After that the entry finds its value
+// again by position, walking down with the subtree sizes and no key comparisons.
+pub enum Entry<'a, K: Ord, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+pub struct OccupiedEntry<'a, K: Ord, V> {
+    map: &'a mut AvlMap<K, V>,
+    key: K,
+    rank: usize, // how many keys are smaller
+}
+
+pub struct VacantEntry<'a, K: Ord, V> {
+    map: &'a mut AvlMap<K, V>,
+    key: K,
+    rank: usize, // where the key goes
+}
+
+impl<'a, K: Ord, V> Entry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(self, modify: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                modify(entry.get_mut());
+                Entry::Occupied(entry)
+            },
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    // the entry only exists while the key is in the map, so the unwraps can't fail
+    pub fn get(&self) -> &V {
+        self.map.value_at(self.rank).unwrap()
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        self.map.value_at_mut(self.rank).unwrap()
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        self.map.value_at_mut(self.rank).unwrap()
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.map.remove(&self.key).unwrap()
+    }
+}
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    // the new key gets exactly the position the entry saw, rebalancing doesn't change it
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.map.insert(self.key, value);
+        self.map.value_at_mut(self.rank).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avl_tree::height;
+
+    // every node must have a correct height and a balance factor of -1, 0 or 1
+    fn assert_avl<K, V>(link: &MapLink<K, V>) -> usize {
+        match link {
+            None => 0,
+            Some(node) => {
+                let left = assert_avl(&node.left);
+                let right = assert_avl(&node.right);
+                assert!(left.abs_diff(right) <= 1, "unbalanced node");
+                assert_eq!(node.height, 1 + left.max(right), "wrong height");
+                assert_eq!(node.size, 1 + size(&node.left) + size(&node.right), "wrong size");
+                node.height
+            }
+        }
+    }
+
+    fn build_test_map() -> AvlMap<i32, String> {
+        let mut map = AvlMap::new();
+        for key in [50, 25, 75, 10, 30, 60, 80] {
+            map.insert(key, key.to_string());
+        }
+        map
+    }
+
+    #[test]
+    fn test_new_map_is_empty() {
+        let map: AvlMap<i32, i32> = AvlMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.len(), 0);
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.first_key_value(), None);
+        assert_eq!(map.last_key_value(), None);
+        assert_eq!(map.iter().next(), None);
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let map = build_test_map();
+        assert_eq!(map.len(), 7);
+        assert_eq!(map.get(&30), Some(&String::from("30")));
+        assert_eq!(map.get(&31), None);
+        assert!(map.contains_key(&80));
+        assert_eq!(map.get_key_value(&10), Some((&10, &String::from("10"))));
+    }
+
+    #[test]
+    fn test_insert_returns_old_value() {
+        let mut map = AvlMap::new();
+        assert_eq!(map.insert("a", 1), None);
+        assert_eq!(map.insert("a", 2), Some(1));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get("a"), Some(&2));
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let mut map = build_test_map();
+        map.get_mut(&25).unwrap().push('!');
+        assert_eq!(map.get(&25), Some(&String::from("25!")));
+        assert_eq!(map.get_mut(&26), None);
+    }
+
+    #[test]
+    fn test_borrowed_keys() {
+        let mut map = AvlMap::new();
+        map.insert(String::from("banana"), 2);
+        map.insert(String::from("apple"), 1);
+        assert_eq!(map.get("apple"), Some(&1));
+        assert_eq!(map.remove("banana"), Some(2));
+        assert_eq!(map.range::<str, _>((Bound::Included("a"), Bound::Excluded("b"))).count(), 1);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut map = build_test_map();
+        assert_eq!(map.remove(&99), None);
+        assert_eq!(map.remove(&50), Some(String::from("50")));
+        assert_eq!(map.remove_entry(&10), Some((10, String::from("10"))));
+        assert_eq!(map.len(), 5);
+        assert_eq!(map.keys().copied().collect::<Vec<_>>(), vec![25, 30, 60, 75, 80]);
+        assert_avl(&map.root);
+    }
+
+    #[test]
+    fn test_stays_balanced() {
+        let mut map = AvlMap::new();
+        for i in 0..500 {
+            map.insert(i, i * 10);
+            assert_avl(&map.root);
+        }
+        assert!(height(&map.root) <= 13);
+
+        for i in (0..500).step_by(2) {
+            assert_eq!(map.remove(&i), Some(i * 10));
+            assert_avl(&map.root);
+        }
+        assert_eq!(map.len(), 250);
+        assert!(map.keys().all(|key| key % 2 == 1));
+    }
+
+    #[test]
+    fn test_iter_keys_values() {
+        let map = build_test_map();
+        assert_eq!(map.keys().copied().collect::<Vec<_>>(), vec![10, 25, 30, 50, 60, 75, 80]);
+        assert_eq!(map.values().map(String::as_str).collect::<Vec<_>>(), vec!["10", "25", "30", "50", "60", "75", "80"]);
+        assert_eq!(map.iter().count(), 7);
+    }
+
+    #[test]
+    fn test_first_and_last_key_value() {
+        let map = build_test_map();
+        assert_eq!(map.first_key_value(), Some((&10, &String::from("10"))));
+        assert_eq!(map.last_key_value(), Some((&80, &String::from("80"))));
+    }
+
+    #[test]
+    fn test_range() {
+        let map = build_test_map();
+        let keys = |range: Vec<(&i32, &String)>| range.into_iter().map(|(key, _)| *key).collect::<Vec<_>>();
+
+        assert_eq!(keys(map.range(25..60).collect()), vec![25, 30, 50]);
+        assert_eq!(keys(map.range(25..=60).collect()), vec![25, 30, 50, 60]);
+        assert_eq!(keys(map.range(26..).collect()), vec![30, 50, 60, 75, 80]);
+        assert_eq!(keys(map.range(..30).collect()), vec![10, 25]);
+        assert_eq!(keys(map.range(..).collect()), vec![10, 25, 30, 50, 60, 75, 80]);
+        assert_eq!(keys(map.range((Bound::Excluded(10), Bound::Excluded(30))).collect()), vec![25]);
+        assert_eq!(map.range(81..).count(), 0);
+        assert_eq!(map.range(11..25).count(), 0);
+    }
+
+    #[test]
+    fn test_entry_or_insert() {
+        let mut map: AvlMap<&str, i32> = AvlMap::new();
+        for word in ["a", "b", "a", "c", "a"] {
+            *map.entry(word).or_insert(0) += 1;
+        }
+        assert_eq!(map.get("a"), Some(&3));
+        assert_eq!(map.get("b"), Some(&1));
+
+        *map.entry("d").or_default() += 5;
+        assert_eq!(map.get("d"), Some(&5));
+        assert_avl(&map.root);
+    }
+
+    #[test]
+    fn test_entry_and_modify() {
+        let mut map = AvlMap::new();
+        map.entry(1).and_modify(|v| *v += 1).or_insert(10);
+        map.entry(1).and_modify(|v| *v += 1).or_insert(10);
+        assert_eq!(map.get(&1), Some(&11));
+    }
+
+    #[test]
+    fn test_occupied_and_vacant_entry() {
+        let mut map = build_test_map();
+        match map.entry(25) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.key(), &25);
+                assert_eq!(entry.get(), "25");
+                assert_eq!(entry.insert(String::from("x")), "25");
+                assert_eq!(entry.remove(), "x");
+            },
+            Entry::Vacant(_) => panic!("25 is in the map"),
+        }
+        assert!(!map.contains_key(&25));
+
+        match map.entry(26) {
+            Entry::Vacant(entry) => {
+                assert_eq!(entry.key(), &26);
+                entry.insert(String::from("26")).push('!');
+            },
+            Entry::Occupied(_) => panic!("26 is not in the map"),
+        }
+        assert_eq!(map.get(&26), Some(&String::from("26!")));
+        assert_eq!(map.len(), 7);
+    }
+
+    #[test]
+    fn test_vacant_insert_reference_survives_rotations() {
+        // every insert at the end of the ascending keys triggers a rotation
+        let mut map = AvlMap::new();
+        for i in 0..100 {
+            let value = map.entry(i).or_insert(0);
+            *value = i * 2;
+        }
+        for i in 0..100 {
+            assert_eq!(map.get(&i), Some(&(i * 2)));
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut map = build_test_map();
+        assert_eq!(map.validate(), Ok(()));
+        map.remove(&50);
+        map.insert(55, String::from("55"));
+        assert_eq!(map.validate(), Ok(()));
+
+        // swap two keys behind the map's back
+        map.root.as_mut().unwrap().val.0 = 0;
+        assert!(matches!(map.validate(), Err(InvariantError::OutOfOrder { .. })));
+    }
+}
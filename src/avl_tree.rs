@@ -1,11 +1,16 @@
 use std::cmp::{ max, Ordering };
+use std::fmt;
+use crate::invariant::InvariantError;
+use crate::set_ops::{ Difference, Intersection, SymmetricDifference, Union };
+use crate::tree_fmt;
 
 // Adelson-Velsky and Landis Tree -> AVL Tree
 // For every node in the tree the balance factor must be -1, 0 or 1.
+// Every node also knows the size of its subtree, which gives rank and select in O(log n).
 
-type Link<T> = Option<Box<Node<T>>>;
+pub(crate) type Link<T> = Option<Box<Node<T>>>;
 
-fn height<T>(link: &Link<T>) -> usize {
+pub(crate) fn height<T>(link: &Link<T>) -> usize {
     link.as_ref().map_or(0, |node| node.height)
 
     // match link {
@@ -14,97 +19,399 @@ fn height<T>(link: &Link<T>) -> usize {
     // }
 }
 
-fn rotate_left<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+// number of nodes in the subtree
+pub(crate) fn size<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+pub(crate) fn rotate_left<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
     // Extract right tree
     let mut new_root_node = node.right.take().unwrap();
     // Move new root left to extracted node's right
     node.right = new_root_node.left.take();
-    // Update height
-    node.update_height();
+    // Update height and size, the children changed
+    node.update();
     // Move extracted node to the left of new root
     new_root_node.left = Some(node);
-    // Update height
-    new_root_node.update_height();
+    // Update height and size, now it covers the whole subtree
+    new_root_node.update();
     new_root_node
 }
 
-fn rotate_right<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+pub(crate) fn rotate_right<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
     // Extract left tree
     let mut new_root_node = node.left.take().unwrap();
     // Move new root right to extracted node's left
     node.left = new_root_node.right.take();
-    // Update height
-    node.update_height();
+    // Update height and size, the children changed
+    node.update();
     // Move extracted node to the right of new root
     new_root_node.right = Some(node);
-    // Update height
-    new_root_node.update_height();
+    // Update height and size, now it covers the whole subtree
+    new_root_node.update();
     new_root_node
 }
 
-fn rebalance<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
-    let bf = node.balance_factor();
-    match bf {
+// Checks the height, size and balance factor stored in every node against the children, in order.
+// next_index is the in order position of the next node, used to report where the problem is.
+// Returns the height of the subtree.
+pub(crate) fn check_nodes<T>(link: &Link<T>, next_index: &mut usize) -> Result<usize, InvariantError> {
+    let Some(node) = link else {
+        return Ok(0);
+    };
+
+    let left_height = check_nodes(&node.left, next_index)?;
+    let index = *next_index;
+    *next_index += 1;
+    let right_height = check_nodes(&node.right, next_index)?;
+
+    let actual = 1 + max(left_height, right_height);
+    if node.height != actual {
+        return Err(InvariantError::WrongHeight { index, stored: node.height, actual });
+    }
+    let actual = 1 + size(&node.left) + size(&node.right);
+    if node.size != actual {
+        return Err(InvariantError::WrongSize { index, stored: node.size, actual });
+    }
+    let balance_factor = node.balance_factor();
+    if !(-1..=1).contains(&balance_factor) {
+        return Err(InvariantError::Unbalanced { index, balance_factor });
+    }
+    Ok(node.height)
+}
+
+// The four ways to fix a node whose balance factor reached 2 or -2, named after the rotations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    Left,
+    Right,
+    // left rotation on the left child, then right rotation on the node
+    LeftRight,
+    // right rotation on the right child, then left rotation on the node
+    RightLeft,
+}
+
+impl Rotation {
+    // the double rotations are two single ones
+    pub fn single_rotations(self) -> usize {
+        match self {
+            Rotation::Left | Rotation::Right => 1,
+            Rotation::LeftRight | Rotation::RightLeft => 2,
+        }
+    }
+}
+
+// which rotation the node needs, None if it's balanced
+fn rotation_needed<T>(node: &Node<T>) -> Option<Rotation> {
+    match node.balance_factor() {
         2 => {
             if node.left.as_ref().unwrap().balance_factor() >= 0 {
-                rotate_right(node)
+                Some(Rotation::Right)
             } else {
-                node.left = Some(rotate_left(node.left.unwrap()));
-                rotate_right(node)
+                Some(Rotation::LeftRight)
             }
         },
         -2 => {
             if node.right.as_ref().unwrap().balance_factor() <= 0 {
-                rotate_left(node)
+                Some(Rotation::Left)
             } else {
-                node.right = Some(rotate_right(node.right.unwrap()));
-                rotate_left(node)
+                Some(Rotation::RightLeft)
             }
+        },
+        _ => None,
+    }
+}
+
+pub(crate) fn rebalance<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    match rotation_needed(&node) {
+        None => node,
+        Some(Rotation::Right) => rotate_right(node),
+        Some(Rotation::LeftRight) => {
+            node.left = Some(rotate_left(node.left.unwrap()));
+            rotate_right(node)
+        },
+        Some(Rotation::Left) => rotate_left(node),
+        Some(Rotation::RightLeft) => {
+            node.right = Some(rotate_right(node.right.unwrap()));
+            rotate_left(node)
+        },
+    }
+}
+
+// rebalance that adds the single rotations it does to rotations
+fn counting_rebalance<T>(rotations: &mut usize) -> impl FnMut(Box<Node<T>>) -> Box<Node<T>> + '_ {
+    |node| {
+        if let Some(rotation) = rotation_needed(&node) {
+            *rotations += rotation.single_rotations();
         }
-        _ => node,
+        rebalance(node)
+    }
+}
+
+// One rebalance done during insert_traced: the subtree that was out of balance drawn before and
+// after the rotation, with the height and balance factor of every node
+pub struct RotationStep {
+    pub rotation: Rotation,
+    pub pivot: String,
+    pub before: String,
+    pub after: String,
+}
+
+impl fmt::Display for RotationStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:?} rotation at {}", self.rotation, self.pivot)?;
+        writeln!(f, "before:")?;
+        write!(f, "{}", self.before)?;
+        writeln!(f, "after:")?;
+        write!(f, "{}", self.after)
+    }
+}
+
+// the label used by the drawings, value then height and balance factor
+fn node_label<T: fmt::Display>(node: &Node<T>) -> String {
+    format!("{} (h={}, bf={})", node.val, node.height, node.balance_factor())
+}
+
+fn children<T>(node: &Node<T>) -> (Option<&Node<T>>, Option<&Node<T>>) {
+    (node.left.as_deref(), node.right.as_deref())
+}
+
+// a subtree drawn sideways with node_label
+struct Drawing<'a, T>(&'a Node<T>);
+
+impl<T: fmt::Display> fmt::Display for Drawing<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        tree_fmt::sideways(f, Some(self.0), children, node_label)
     }
 }
 
+// the AVL map reuses these nodes and rotations with T = (key, value)
 pub struct Node<T> {
-    val: T,
-    left: Link<T>,
-    right: Link<T>,
-    height: usize,
+    pub(crate) val: T,
+    pub(crate) left: Link<T>,
+    pub(crate) right: Link<T>,
+    pub(crate) height: usize,
+    pub(crate) size: usize,
 }
 
 impl<T> Node<T> {
-    fn update_height(&mut self) {
+    pub(crate) fn leaf(val: T) -> Box<Self> {
+        Box::new(Node {
+            val,
+            left: None,
+            right: None,
+            height: 1,
+            size: 1,
+        })
+    }
+
+    // recompute height and size from the children, they must already be up to date
+    pub(crate) fn update(&mut self) {
         let left_height = height(&self.left);
         let right_height = height(&self.right);
         self.height = 1 + max(left_height, right_height);
+        self.size = 1 + size(&self.left) + size(&self.right);
     }
 
-    fn balance_factor(&self) -> i32 {
+    pub(crate) fn balance_factor(&self) -> i32 {
         height(&self.left) as i32 - height(&self.right) as i32
     }
 }
 
+// Remove the minimum node of the subtree, rebalancing the path back up.
+// Returns what is left of the subtree and the detached node.
+pub(crate) fn detach_min<T>(node: Box<Node<T>>) -> (Link<T>, Box<Node<T>>) {
+    detach_min_with(node, &mut rebalance)
+}
+
+fn detach_min_with<T>(mut node: Box<Node<T>>, rebalance: &mut impl FnMut(Box<Node<T>>) -> Box<Node<T>>) -> (Link<T>, Box<Node<T>>) {
+    match node.left.take() {
+        None => {
+            let right = node.right.take();
+            (right, node)
+        },
+        Some(left) => {
+            let (left, min_node) = detach_min_with(left, rebalance);
+            node.left = left;
+            node.update();
+            (Some(rebalance(node)), min_node)
+        },
+    }
+}
+
+// Join two trees with every value of left < mid < every value of right into one AVL tree.
+// Goes down the spine of the taller tree until the heights are within one, hangs mid there and
+// rebalances on the way back up, so it costs O(|height(left) - height(right)| + 1).
+pub(crate) fn join<T>(left: Link<T>, mut mid: Box<Node<T>>, right: Link<T>) -> Box<Node<T>> {
+    let left_height = height(&left);
+    let right_height = height(&right);
+
+    if left_height > right_height + 1 {
+        let mut node = left.unwrap();
+        let joined = join(node.right.take(), mid, right);
+        node.right = Some(joined);
+        node.update();
+        return rebalance(node);
+    }
+
+    if right_height > left_height + 1 {
+        let mut node = right.unwrap();
+        let joined = join(left, mid, node.left.take());
+        node.left = Some(joined);
+        node.update();
+        return rebalance(node);
+    }
+
+    mid.left = left;
+    mid.right = right;
+    mid.update();
+    mid
+}
+
+// Split a tree into the values smaller than key, the node holding key (if any, without children)
+// and the values greater than key. Every level joins what was cut off, O(log n) in total.
+pub(crate) fn split<T: Ord>(link: Link<T>, key: &T) -> (Link<T>, Link<T>, Link<T>) {
+    let Some(mut node) = link else {
+        return (None, None, None);
+    };
+
+    let left = node.left.take();
+    let right = node.right.take();
+    match key.cmp(&node.val) {
+        Ordering::Equal => {
+            node.update();
+            (left, Some(node), right)
+        },
+        Ordering::Less => {
+            let (smaller, found, greater) = split(left, key);
+            (smaller, found, Some(join(greater, node, right)))
+        },
+        Ordering::Greater => {
+            let (smaller, found, greater) = split(right, key);
+            (Some(join(left, node, smaller)), found, greater)
+        },
+    }
+}
+
+// Union by splitting one tree with the root of the other, then joining the two halves back.
+// O(m log(n / m + 1)) for trees of sizes m <= n. On equal values the one from b is kept.
+pub(crate) fn union<T: Ord>(a: Link<T>, b: Link<T>) -> Link<T> {
+    let Some(mut root) = b else {
+        return a;
+    };
+    if a.is_none() {
+        return Some(root);
+    }
+
+    let (smaller, _, greater) = split(a, &root.val);
+    let left = union(smaller, root.left.take());
+    let right = union(greater, root.right.take());
+    Some(join(left, root, right))
+}
+
 #[derive(Default)]
 pub struct AvlTree<T> {
     root: Link<T>,
 }
 
+// struct to hold the value for the iteration, same as the binary search tree one
+pub struct Iter<'a, T> {
+    stack: Vec<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    // pop the smallest node not visited yet and stack the left side of its right subtree
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        let mut current_node = node.right.as_ref();
+        while let Some(n) = current_node {
+            self.stack.push(n);
+            current_node = n.left.as_ref();
+        }
+        Some(&node.val)
+    }
+}
+
 impl<T> AvlTree<T> {
     pub fn new() -> Self {
         Self { root: None }
     }
 
+    // the root knows the size of the whole tree
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    // number of levels, 0 for an empty tree
+    pub fn height(&self) -> usize {
+        height(&self.root)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        let mut iterator = Iter { stack: vec![] };
+        let mut current_node = self.root.as_ref();
+
+        while let Some(node) = current_node {
+            iterator.stack.push(node);
+            current_node = node.left.as_ref();
+        }
+        iterator
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        let mut node = self.root.as_ref()?;
+        while let Some(left) = &node.left {
+            node = left;
+        }
+        Some(&node.val)
+    }
+
+    pub fn max(&self) -> Option<&T> {
+        let mut node = self.root.as_ref()?;
+        while let Some(right) = &node.right {
+            node = right;
+        }
+        Some(&node.val)
+    }
+
+    // the k-th smallest value, starting at 0
+    pub fn select(&self, k: usize) -> Option<&T> {
+        let mut k = k;
+        let mut current_node = self.root.as_ref();
+        while let Some(node) = current_node {
+            let left_size = size(&node.left);
+            match k.cmp(&left_size) {
+                Ordering::Less => current_node = node.left.as_ref(),
+                Ordering::Equal => return Some(&node.val),
+                Ordering::Greater => {
+                    // skip the left subtree and this node
+                    k -= left_size + 1;
+                    current_node = node.right.as_ref();
+                },
+            }
+        }
+        None
+    }
+
     // Helper method to check if the tree maintains AVL property
+    #[cfg(test)]
     fn is_balanced(&self) -> bool {
         Self::check_balance(&self.root)
     }
 
+    #[cfg(test)]
     fn check_balance(node: &Link<T>) -> bool {
         match node {
             None => true,
             Some(n) => {
                 let bf = n.balance_factor();
-                if (-1..1).contains(&bf) {
+                if !(-1..=1).contains(&bf) {
                     return false;
                 }
                 Self::check_balance(&n.left) && Self::check_balance(&n.right)
@@ -113,15 +420,18 @@ impl<T> AvlTree<T> {
     }
 
     // Helper to get the height of the tree
+    #[cfg(test)]
     fn tree_height(&self) -> usize {
-        height(&self.root)
+        self.height()
     }
 
-    // Helper to verify heights are correctly maintained
+    // Helper to verify heights and sizes are correctly maintained
+    #[cfg(test)]
     fn verify_heights(&self) -> bool {
         Self::check_heights(&self.root)
     }
 
+    #[cfg(test)]
     fn check_heights(node: &Link<T>) -> bool {
         match node {
             None => true,
@@ -130,60 +440,286 @@ impl<T> AvlTree<T> {
                 if n.height != expected {
                     return false;
                 }
+                if n.size != 1 + size(&n.left) + size(&n.right) {
+                    return false;
+                }
                 Self::check_heights(&n.left) && Self::check_heights(&n.right)
             }
         }
     }
 
     // Helper to collect values in-order for testing
+    #[cfg(test)]
     fn in_order(&self) -> Vec<&T> {
-        let mut result = Vec::new();
-        Self::in_order_helper(&self.root, &mut result);
-        result
+        self.iter().collect()
     }
+}
 
-    fn in_order_helper<'a>(node: &'a Link<T>, result: &mut Vec<&'a T>) {
-        if let Some(n) = node {
-            Self::in_order_helper(&n.left, result);
-            result.push(&n.val);
-            Self::in_order_helper(&n.right, result);
+impl<T: Ord> AvlTree<T> {
+    // Checks every invariant of the tree: strictly increasing values in order, the stored heights
+    // and sizes, and balance factors between -1 and 1.
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        check_nodes(&self.root, &mut 0)?;
+        let mut values = self.iter().enumerate();
+        if let Some((_, mut previous)) = values.next() {
+            for (index, val) in values {
+                if previous >= val {
+                    return Err(InvariantError::OutOfOrder { index });
+                }
+                previous = val;
+            }
         }
+        Ok(())
+    }
+
+    // Builds a complete tree from sorted values in O(n), no rotations needed.
+    // Repeated values are kept once (the first). Values out of order panic in debug builds,
+    // release builds don't check them and give a tree that fails validate.
+    pub fn from_sorted_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut values: Vec<T> = iter.into_iter().collect();
+        values.dedup();
+        debug_assert!(values.windows(2).all(|pair| pair[0] < pair[1]), "from_sorted_iter needs values in increasing order");
+
+        let len = values.len();
+        let root = Self::build_sorted(&mut values.into_iter(), len);
+        AvlTree { root }
+    }
+
+    // first half to the left subtree, then the root, then the rest to the right subtree.
+    // Both halves differ by one value at most, so their heights differ by one at most.
+    fn build_sorted(values: &mut std::vec::IntoIter<T>, len: usize) -> Link<T> {
+        if len == 0 {
+            return None;
+        }
+
+        let left_len = len / 2;
+        let left = Self::build_sorted(values, left_len);
+        let mut node = Node::leaf(values.next()?);
+        node.left = left;
+        node.right = Self::build_sorted(values, len - left_len - 1);
+        node.update();
+        Some(node)
+    }
+
+    pub fn search(&self, val: &T) -> Option<&T> {
+        let mut current_node = self.root.as_ref();
+        while let Some(node) = current_node {
+            match val.cmp(&node.val) {
+                Ordering::Equal => return Some(&node.val),
+                Ordering::Less => current_node = node.left.as_ref(),
+                Ordering::Greater => current_node = node.right.as_ref(),
+            }
+        }
+        None
+    }
+
+    pub fn contains(&self, val: &T) -> bool {
+        self.search(val).is_some()
+    }
+
+    // how many values are smaller than val, val doesn't need to be in the tree
+    pub fn rank(&self, val: &T) -> usize {
+        let mut rank = 0;
+        let mut current_node = self.root.as_ref();
+        while let Some(node) = current_node {
+            match val.cmp(&node.val) {
+                Ordering::Less => current_node = node.left.as_ref(),
+                Ordering::Equal => return rank + size(&node.left),
+                Ordering::Greater => {
+                    // the whole left subtree and this node are smaller
+                    rank += size(&node.left) + 1;
+                    current_node = node.right.as_ref();
+                },
+            }
+        }
+        rank
     }
-}
 
-impl<T: Ord> AvlTree<T> {
     pub fn insert(&mut self, value: T) {
         let root = self.root.take();
-        self.root = Some(Self::insert_recursive(root, value));
+        self.root = Some(Self::insert_recursive(root, value, &mut rebalance));
     }
 
-    fn insert_recursive(link: Link<T>, value: T) -> Box<Node<T>> {
+    // The rebalancing is passed in so insert_traced can watch every rotation
+    fn insert_recursive(link: Link<T>, value: T, rebalance: &mut impl FnMut(Box<Node<T>>) -> Box<Node<T>>) -> Box<Node<T>> {
         match link {
-            None => {
-                Box::new(Node {
-                    val: value,
-                    left: None,
-                    right: None,
-                    height: 1,
-                })
-            },
+            None => Node::leaf(value),
             Some(mut node) => {
                 match value.cmp(&node.val) {
                     Ordering::Equal => (),
                     Ordering::Less => {
-                        let left = Self::insert_recursive(node.left.take(), value);
+                        let left = Self::insert_recursive(node.left.take(), value, rebalance);
                         node.left = Some(left);
                     },
                     Ordering::Greater => {
-                        let right = Self::insert_recursive(node.right.take(), value);
+                        let right = Self::insert_recursive(node.right.take(), value, rebalance);
                         node.right = Some(right);
                     },
                 }
-                node.update_height();
+                node.update();
                 rebalance(node)
             },
         }
     }
+
+    pub fn delete(&mut self, val: &T) -> Option<T> {
+        let root = self.root.take();
+        let (root, deleted) = Self::delete_recursive(root, val, &mut rebalance);
+        self.root = root;
+        deleted
+    }
+
+    // Same as insert but also returns how many single rotations it took (a double one counts 2).
+    // For comparing the balancing work with other trees on the same inputs.
+    pub fn insert_counted(&mut self, value: T) -> usize {
+        let mut rotations = 0;
+        let root = self.root.take();
+        self.root = Some(Self::insert_recursive(root, value, &mut counting_rebalance(&mut rotations)));
+        rotations
+    }
+
+    // delete and the number of single rotations it took
+    pub fn delete_counted(&mut self, val: &T) -> (Option<T>, usize) {
+        let mut rotations = 0;
+        let root = self.root.take();
+        let (root, deleted) = Self::delete_recursive(root, val, &mut counting_rebalance(&mut rotations));
+        self.root = root;
+        (deleted, rotations)
+    }
+
+    // Same as insert: go down to the node, remove it and rebalance every node on the way back up
+    fn delete_recursive(link: Link<T>, val: &T, rebalance: &mut impl FnMut(Box<Node<T>>) -> Box<Node<T>>) -> (Link<T>, Option<T>) {
+        let Some(mut node) = link else {
+            return (None, None);
+        };
+
+        let deleted = match val.cmp(&node.val) {
+            Ordering::Less => {
+                let (left, deleted) = Self::delete_recursive(node.left.take(), val, rebalance);
+                node.left = left;
+                deleted
+            },
+            Ordering::Greater => {
+                let (right, deleted) = Self::delete_recursive(node.right.take(), val, rebalance);
+                node.right = right;
+                deleted
+            },
+            Ordering::Equal => match (node.left.take(), node.right.take()) {
+                // Node has no children, nothing is left in its place
+                (None, None) => return (None, Some(node.val)),
+
+                // Node has one children, that child is already balanced
+                (Some(child), None) | (None, Some(child)) => return (Some(child), Some(node.val)),
+
+                // Node has two children, the successor (min of the right subtree) takes its value
+                (Some(left), Some(right)) => {
+                    let (right, successor) = detach_min_with(right, rebalance);
+                    node.left = Some(left);
+                    node.right = right;
+                    Some(std::mem::replace(&mut node.val, successor.val))
+                },
+            },
+        };
+
+        node.update();
+        (Some(rebalance(node)), deleted)
+    }
+
+    // Moves every value >= key into a new tree, self keeps the smaller ones.
+    // Both halves come out of split already balanced, O(log n).
+    pub fn split_off(&mut self, key: &T) -> Self {
+        let (smaller, found, greater) = split(self.root.take(), key);
+        self.root = smaller;
+        let greater = match found {
+            Some(node) => Some(join(None, node, greater)),
+            None => greater,
+        };
+        AvlTree { root: greater }
+    }
+
+    // Moves every value of other into self, leaving other empty.
+    // Join based union, values that were in both trees keep the one from other.
+    pub fn append(&mut self, other: &mut Self) {
+        self.root = union(self.root.take(), other.root.take());
+    }
+
+    pub fn union<'a>(&'a self, other: &'a Self) -> Union<'a, T, Iter<'a, T>> {
+        Union::new(self.iter(), other.iter())
+    }
+
+    pub fn intersection<'a>(&'a self, other: &'a Self) -> Intersection<'a, T, Iter<'a, T>> {
+        Intersection::new(self.iter(), other.iter())
+    }
+
+    pub fn difference<'a>(&'a self, other: &'a Self) -> Difference<'a, T, Iter<'a, T>> {
+        Difference::new(self.iter(), other.iter())
+    }
+
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Self) -> SymmetricDifference<'a, T, Iter<'a, T>> {
+        SymmetricDifference::new(self.iter(), other.iter())
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        // the sizes are known, a bigger tree can't fit
+        if self.len() > other.len() {
+            return false;
+        }
+        // few values against a big tree, looking each one up is cheaper than walking both
+        if self.len() * other.height() < other.len() {
+            return self.iter().all(|val| other.contains(val));
+        }
+        self.difference(other).next().is_none()
+    }
+
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        let (small, big) = if self.len() <= other.len() { (self, other) } else { (other, self) };
+        if small.len() * big.height() < big.len() {
+            return !small.iter().any(|val| big.contains(val));
+        }
+        self.intersection(other).next().is_none()
+    }
+}
+
+impl<T: Ord + fmt::Display> AvlTree<T> {
+    // Same as insert, but returns every rotation it did from the bottom up.
+    // Meant for debugging and code review, each step draws the subtree twice.
+    pub fn insert_traced(&mut self, value: T) -> Vec<RotationStep> {
+        let mut steps = Vec::new();
+        let mut traced_rebalance = |node: Box<Node<T>>| {
+            let Some(rotation) = rotation_needed(&node) else {
+                return node;
+            };
+            let pivot = node.val.to_string();
+            let before = Drawing(&node).to_string();
+            let node = rebalance(node);
+            steps.push(RotationStep { rotation, pivot, before, after: Drawing(&node).to_string() });
+            node
+        };
+
+        let root = self.root.take();
+        self.root = Some(Self::insert_recursive(root, value, &mut traced_rebalance));
+        steps
+    }
+}
+
+impl<T: fmt::Display> AvlTree<T> {
+    // Graphviz source, every node shows its value, height and balance factor
+    pub fn to_dot(&self) -> String {
+        tree_fmt::to_dot("AvlTree", self.root.as_deref(), children, |node| {
+            format!("{}\nh={} bf={}", node.val, node.height, node.balance_factor())
+        })
+    }
+}
+
+// draws the tree sideways, root on the left and bigger values above, with heights and balance factors
+impl<T: fmt::Display> fmt::Display for AvlTree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        tree_fmt::sideways(f, self.root.as_deref(), children, node_label)
+    }
 }
 
 #[cfg(test)]
@@ -389,8 +925,8 @@ mod tests {
         // Verify all elements are present and sorted
         let in_order = tree.in_order();
         assert_eq!(in_order.len(), n);
-        for i in 0..n {
-            assert_eq!(*in_order[i], i);
+        for (i, val) in in_order.into_iter().enumerate() {
+            assert_eq!(*val, i);
         }
     }
 
@@ -417,4 +953,419 @@ mod tests {
             assert!(in_order[i - 1] < in_order[i]);
         }
     }
+
+    #[test]
+    fn test_search_min_max_len() {
+        let mut tree = AvlTree::new();
+        assert_eq!(tree.len(), 0);
+        assert!(tree.is_empty());
+        assert_eq!(tree.min(), None);
+        assert_eq!(tree.max(), None);
+        assert_eq!(tree.search(&1), None);
+
+        for val in [50, 25, 75, 10, 30, 60, 80] {
+            tree.insert(val);
+        }
+        tree.insert(30); // duplicate, len must not change
+
+        assert_eq!(tree.len(), 7);
+        assert!(!tree.is_empty());
+        assert_eq!(tree.min(), Some(&10));
+        assert_eq!(tree.max(), Some(&80));
+        assert_eq!(tree.search(&60), Some(&60));
+        assert_eq!(tree.search(&61), None);
+        assert!(tree.contains(&25));
+        assert!(!tree.contains(&26));
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut tree = AvlTree::new();
+        assert_eq!(tree.iter().next(), None);
+
+        for val in [5, 3, 8, 1, 4, 7, 9] {
+            tree.insert(val);
+        }
+        let values: Vec<i32> = tree.iter().copied().collect();
+        assert_eq!(values, vec![1, 3, 4, 5, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_delete_from_empty_and_missing() {
+        let mut tree: AvlTree<i32> = AvlTree::new();
+        assert_eq!(tree.delete(&1), None);
+
+        tree.insert(1);
+        assert_eq!(tree.delete(&2), None);
+        assert_eq!(tree.len(), 1);
+    }
+
+    #[test]
+    fn test_delete_leaf_one_child_and_two_children() {
+        let mut tree = AvlTree::new();
+        for val in [50, 25, 75, 10, 30, 60, 80, 5] {
+            tree.insert(val);
+        }
+
+        assert_eq!(tree.delete(&5), Some(5)); // leaf
+        assert_eq!(tree.delete(&10), Some(10)); // leaf after 5 is gone
+        tree.insert(27);
+        assert_eq!(tree.delete(&25), Some(25)); // two children
+        assert_eq!(tree.delete(&50), Some(50)); // root with two children
+
+        assert!(tree.is_balanced());
+        assert!(tree.verify_heights());
+        assert_eq!(tree.in_order(), vec![&27, &30, &60, &75, &80]);
+        assert_eq!(tree.len(), 5);
+    }
+
+    #[test]
+    fn test_delete_rebalances() {
+        // deleting 10 leaves 30 with a right-heavy subtree that needs a rotation
+        let mut tree = AvlTree::new();
+        for val in [20, 10, 30, 40] {
+            tree.insert(val);
+        }
+        assert_eq!(tree.delete(&10), Some(10));
+        assert!(tree.is_balanced());
+        assert!(tree.verify_heights());
+        assert_eq!(tree.tree_height(), 2);
+        assert_eq!(tree.in_order(), vec![&20, &30, &40]);
+
+        // right-left case
+        let mut tree = AvlTree::new();
+        for val in [20, 10, 30, 25] {
+            tree.insert(val);
+        }
+        assert_eq!(tree.delete(&10), Some(10));
+        assert!(tree.is_balanced());
+        assert_eq!(tree.tree_height(), 2);
+        assert_eq!(tree.in_order(), vec![&20, &25, &30]);
+    }
+
+    #[test]
+    fn test_delete_everything_keeps_balance() {
+        let mut tree = AvlTree::new();
+        for i in 0..200 {
+            tree.insert((i * 37) % 200);
+        }
+
+        for i in 0..200 {
+            let val = (i * 91) % 200;
+            assert_eq!(tree.delete(&val), Some(val));
+            assert!(tree.is_balanced(), "Tree unbalanced after deleting {}", val);
+            assert!(tree.verify_heights(), "Heights incorrect after deleting {}", val);
+            assert_eq!(tree.search(&val), None);
+            assert_eq!(tree.len(), 199 - i);
+        }
+        assert!(tree.is_empty());
+        assert_eq!(tree.tree_height(), 0);
+    }
+
+    #[test]
+    fn test_drop_values() {
+        use std::rc::Rc;
+
+        let val = Rc::new(());
+        {
+            let mut tree = AvlTree::new();
+            for i in 0..10 {
+                tree.insert((i, Rc::clone(&val)));
+            }
+            assert!(tree.delete(&(3, Rc::clone(&val))).is_some());
+            assert_eq!(Rc::strong_count(&val), 10);
+        }
+        assert_eq!(Rc::strong_count(&val), 1);
+    }
+
+    #[test]
+    fn test_select() {
+        let mut tree = AvlTree::new();
+        assert_eq!(tree.select(0), None);
+
+        for val in [50, 25, 75, 10, 30, 60, 80] {
+            tree.insert(val);
+        }
+        let expected = [10, 25, 30, 50, 60, 75, 80];
+        for (k, val) in expected.iter().enumerate() {
+            assert_eq!(tree.select(k), Some(val));
+        }
+        assert_eq!(tree.select(7), None);
+    }
+
+    #[test]
+    fn test_rank() {
+        let mut tree = AvlTree::new();
+        assert_eq!(tree.rank(&5), 0);
+
+        for val in [50, 25, 75, 10, 30, 60, 80] {
+            tree.insert(val);
+        }
+        assert_eq!(tree.rank(&10), 0);
+        assert_eq!(tree.rank(&50), 3);
+        assert_eq!(tree.rank(&80), 6);
+        assert_eq!(tree.rank(&5), 0); // smaller than everything
+        assert_eq!(tree.rank(&55), 4); // not in the tree
+        assert_eq!(tree.rank(&100), 7);
+    }
+
+    #[test]
+    fn test_sizes_through_rotations_and_deletes() {
+        let mut tree = AvlTree::new();
+        for i in 0..300 {
+            tree.insert((i * 7) % 300);
+            assert!(tree.verify_heights(), "Sizes incorrect after inserting {}", i);
+        }
+        assert_eq!(tree.len(), 300);
+
+        for i in (0..300).step_by(3) {
+            tree.delete(&i);
+            assert!(tree.verify_heights(), "Sizes incorrect after deleting {}", i);
+        }
+        assert_eq!(tree.len(), 200);
+
+        // select and rank are inverses of each other
+        for k in 0..tree.len() {
+            let val = *tree.select(k).unwrap();
+            assert_eq!(tree.rank(&val), k);
+            assert_ne!(val % 3, 0);
+        }
+    }
+
+    fn assert_valid(tree: &AvlTree<i32>) {
+        assert!(tree.is_balanced());
+        assert!(tree.verify_heights());
+        let values: Vec<_> = tree.iter().collect();
+        assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    fn tree_of(values: impl IntoIterator<Item = i32>) -> AvlTree<i32> {
+        let mut tree = AvlTree::new();
+        for val in values {
+            tree.insert(val);
+        }
+        tree
+    }
+
+    #[test]
+    fn test_join_uneven_heights() {
+        let small = tree_of([1, 2]);
+        let big = tree_of(10..200);
+        let joined = join(small.root, Node::leaf(5), big.root);
+        let tree = AvlTree { root: Some(joined) };
+        assert_valid(&tree);
+        assert_eq!(tree.len(), 193);
+        assert_eq!(tree.select(2), Some(&5));
+    }
+
+    #[test]
+    fn test_split_off() {
+        for key in [-1, 0, 1, 37, 50, 99, 100, 150] {
+            let mut tree = tree_of(0..100);
+            let right = tree.split_off(&key);
+            assert_valid(&tree);
+            assert_valid(&right);
+            let expected = key.clamp(0, 100) as usize;
+            assert_eq!(tree.len(), expected);
+            assert_eq!(right.len(), 100 - expected);
+            assert!(tree.max().is_none_or(|max| *max < key));
+            assert!(right.min().is_none_or(|min| *min >= key));
+        }
+    }
+
+    #[test]
+    fn test_append() {
+        let mut tree = tree_of((0..100).step_by(2));
+        let mut other = tree_of((0..300).step_by(3));
+        tree.append(&mut other);
+        assert_valid(&tree);
+        assert!(other.is_empty());
+        let expected: Vec<i32> = (0..300).filter(|val| (val % 2 == 0 && *val < 100) || val % 3 == 0).collect();
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), expected);
+
+        let mut empty = AvlTree::new();
+        empty.append(&mut tree);
+        assert_eq!(empty.len(), expected.len());
+        assert!(tree.is_empty());
+    }
+
+    // only the key is compared, the label tells which tree a value came from
+    #[derive(Debug)]
+    struct Keyed(i32, &'static str);
+
+    impl PartialEq for Keyed {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    impl Eq for Keyed {}
+
+    impl PartialOrd for Keyed {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Keyed {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+
+    #[test]
+    fn test_append_keeps_other_on_duplicates() {
+        let mut tree = AvlTree::new();
+        tree.insert(Keyed(1, "self"));
+        tree.insert(Keyed(2, "self"));
+        let mut other = AvlTree::new();
+        other.insert(Keyed(2, "other"));
+        other.insert(Keyed(3, "other"));
+        tree.append(&mut other);
+        let labels: Vec<_> = tree.iter().map(|keyed| (keyed.0, keyed.1)).collect();
+        assert_eq!(labels, vec![(1, "self"), (2, "other"), (3, "other")]);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = tree_of([1, 3, 5, 7]);
+        let b = tree_of([3, 4, 5, 6]);
+        assert_eq!(a.union(&b).copied().collect::<Vec<_>>(), vec![1, 3, 4, 5, 6, 7]);
+        assert_eq!(a.intersection(&b).copied().collect::<Vec<_>>(), vec![3, 5]);
+        assert_eq!(a.difference(&b).copied().collect::<Vec<_>>(), vec![1, 7]);
+        assert_eq!(a.symmetric_difference(&b).copied().collect::<Vec<_>>(), vec![1, 4, 6, 7]);
+    }
+
+    #[test]
+    fn test_subset_and_disjoint() {
+        let small = tree_of([10, 500]);
+        let big = tree_of(0..1000);
+        let odd = tree_of((1..1000).step_by(2));
+        assert!(small.is_subset(&big));
+        assert!(big.is_superset(&small));
+        assert!(!big.is_subset(&small));
+        assert!(odd.is_subset(&big));
+        assert!(small.is_disjoint(&odd));
+        assert!(odd.is_disjoint(&small));
+        assert!(!odd.is_disjoint(&big));
+        assert!(AvlTree::new().is_subset(&small));
+    }
+
+    #[test]
+    fn test_from_sorted_iter() {
+        for len in [0, 1, 2, 3, 7, 8, 100, 1023, 1024] {
+            let tree = AvlTree::from_sorted_iter(0..len);
+            assert_valid(&tree);
+            assert_eq!(tree.len(), len as usize);
+            assert_eq!(tree.iter().copied().collect::<Vec<_>>(), (0..len).collect::<Vec<_>>());
+            let expected = (usize::BITS - (len as usize).leading_zeros()) as usize;
+            assert_eq!(tree.height(), expected);
+        }
+
+        // still a normal tree afterwards
+        let mut tree = AvlTree::from_sorted_iter((0..50).map(|val| val * 2));
+        tree.insert(51);
+        tree.delete(&20);
+        assert_valid(&tree);
+        assert_eq!(tree.rank(&51), 25);
+    }
+
+    #[test]
+    fn test_from_sorted_iter_duplicates() {
+        let tree = AvlTree::from_sorted_iter([1, 2, 2, 2, 3, 3]);
+        assert_valid(&tree);
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "increasing order")]
+    fn test_from_sorted_iter_unsorted() {
+        AvlTree::from_sorted_iter([1, 3, 2]);
+    }
+
+    #[test]
+    fn test_display_and_dot() {
+        let tree = tree_of([20, 10, 30, 25]);
+        assert_eq!(tree.to_string(), concat!(
+            "/-- 30 (h=2, bf=1)\n",
+            "|   \\-- 25 (h=1, bf=0)\n",
+            "20 (h=3, bf=-1)\n",
+            "\\-- 10 (h=1, bf=0)\n",
+        ));
+
+        let dot = tree.to_dot();
+        assert!(dot.starts_with("digraph AvlTree {"));
+        assert!(dot.contains("[label=\"20\\nh=3 bf=-1\"]"));
+        assert_eq!(dot.matches("->").count(), 4);
+    }
+
+    #[test]
+    fn test_insert_traced() {
+        let mut tree = AvlTree::new();
+        assert!(tree.insert_traced(30).is_empty());
+        assert!(tree.insert_traced(10).is_empty());
+        let steps = tree.insert_traced(20);
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].rotation, Rotation::LeftRight);
+        assert_eq!(steps[0].pivot, "30");
+        assert_eq!(steps[0].after, "/-- 30 (h=1, bf=0)\n20 (h=2, bf=0)\n\\-- 10 (h=1, bf=0)\n");
+        assert!(steps[0].to_string().starts_with("LeftRight rotation at 30\nbefore:\n"));
+
+        let steps = tree.insert_traced(40);
+        assert!(steps.is_empty());
+        let steps = tree.insert_traced(50);
+        assert_eq!(steps.iter().map(|step| step.rotation).collect::<Vec<_>>(), vec![Rotation::Left]);
+        assert_eq!(steps[0].pivot, "30");
+        assert_valid(&tree);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut tree = tree_of(0..100);
+        assert_eq!(tree.validate(), Ok(()));
+        tree.delete(&50);
+        assert_eq!(tree.validate(), Ok(()));
+
+        // break the stored height of the root
+        let root = tree.root.as_mut().unwrap();
+        root.height += 1;
+        let index = size(&root.left);
+        assert_eq!(tree.validate(), Err(InvariantError::WrongHeight { index, stored: 8, actual: 7 }));
+        tree.root.as_mut().unwrap().height -= 1;
+
+        // a tree shaped by hand
+        let mut node = Node::leaf(2);
+        node.left = Some(Node::leaf(3));
+        node.update();
+        let tree = AvlTree { root: Some(node) };
+        assert_eq!(tree.validate(), Err(InvariantError::OutOfOrder { index: 1 }));
+
+        let mut node = Node::leaf(1);
+        node.right = Some(Node::leaf(2));
+        node.right.as_mut().unwrap().right = Some(Node::leaf(3));
+        node.right.as_mut().unwrap().update();
+        node.update();
+        let tree = AvlTree { root: Some(node) };
+        let error = tree.validate().unwrap_err();
+        assert_eq!(error, InvariantError::Unbalanced { index: 0, balance_factor: -2 });
+        assert_eq!(error.to_string(), "node 0 has balance factor -2");
+    }
+
+    #[test]
+    fn test_counted_rotations() {
+        let mut tree = AvlTree::new();
+        assert_eq!(tree.insert_counted(1), 0);
+        assert_eq!(tree.insert_counted(2), 0);
+        assert_eq!(tree.insert_counted(3), 1);
+        assert_eq!(tree.insert_counted(0), 0);
+        // without 3 the root 2 leans left by two
+        assert_eq!(tree.delete_counted(&3), (Some(3), 1));
+        assert_eq!(tree.delete_counted(&2), (Some(2), 0));
+        assert_eq!(tree.delete_counted(&7), (None, 0));
+
+        let mut tree = tree_of([30, 10]);
+        assert_eq!(tree.insert_counted(20), 2);
+        assert_valid(&tree);
+    }
 }
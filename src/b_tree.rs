@@ -0,0 +1,768 @@
+use std::cmp::Ordering;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+use std::ptr;
+
+use crate::invariant::InvariantError;
+
+// B-tree of minimum degree B: every node but the root holds between B - 1 and 2B - 1 sorted keys
+// and an internal node with k keys has k + 1 children. All the leaves are at the same depth, so
+// the height is about log_B(n) and a search touches that many nodes, each one a short sorted
+// array that is cheap to scan compared with chasing one pointer per value.
+//
+// Both insert and delete go down the tree once and fix nodes on the way (split a full child
+// before entering it, refill a minimal child before entering it), they never come back up.
+// That's the version meant for disk: every node is read and written at most once per operation.
+//
+// B = 6 is what the standard library BTreeMap uses: 11 keys per node.
+pub struct BTree<T, const B: usize = 6> {
+    root: Option<Node<T>>,
+    len: usize,
+}
+
+// A node is one page: the keys are allocated at their full 2B - 1 capacity when the node is
+// created and never reallocated, an internal node does the same with its 2B children. Written to
+// disk it would be a fixed size block with a key count, the keys and the child page numbers.
+struct Node<T> {
+    keys: Vec<T>,
+    children: Vec<Node<T>>, // empty in a leaf
+}
+
+impl<T> Node<T> {
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+}
+
+impl<T, const B: usize> Default for BTree<T, B> {
+    fn default() -> Self {
+        // evaluated here and in from_sorted_iter, the two places a tree is built, so a bad B fails
+        // to compile whatever the constructor
+        let () = Self::DEGREE_CHECK;
+        BTree { root: None, len: 0 }
+    }
+}
+
+// In order iterator from both ends, also what range() returns.
+// Each stack is the path to the next value to hand out from its end: (node, index of the key in
+// that node). Only nodes that still have keys on that side are kept, so the top is always valid.
+pub struct Iter<'a, T> {
+    front: Vec<(&'a Node<T>, usize)>,
+    back: Vec<(&'a Node<T>, usize)>, // for the back the index is one past the key
+}
+
+impl<'a, T> Iter<'a, T> {
+    fn empty() -> Self {
+        Iter { front: Vec::new(), back: Vec::new() }
+    }
+
+    fn push_leftmost(&mut self, mut node: &'a Node<T>) {
+        loop {
+            self.front.push((node, 0));
+            if node.is_leaf() {
+                break;
+            }
+            node = &node.children[0];
+        }
+    }
+
+    fn push_rightmost(&mut self, mut node: &'a Node<T>) {
+        loop {
+            self.back.push((node, node.keys.len()));
+            let Some(last) = node.children.last() else {
+                break;
+            };
+            node = last;
+        }
+    }
+
+    fn front_value(&self) -> Option<&'a T> {
+        self.front.last().map(|&(node, i)| &node.keys[i])
+    }
+
+    fn back_value(&self) -> Option<&'a T> {
+        self.back.last().map(|&(node, end)| &node.keys[end - 1])
+    }
+
+    // both ends are on the same value, the last one left
+    fn ends_meet(&mut self) -> bool {
+        let met = match (self.front_value(), self.back_value()) {
+            (Some(front), Some(back)) => ptr::eq(front, back),
+            _ => false,
+        };
+        if met {
+            self.front.clear();
+            self.back.clear();
+        }
+        met
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let val = self.front_value()?;
+        if self.ends_meet() {
+            return Some(val);
+        }
+
+        // after keys[i] come the subtree right of it, then keys[i + 1]
+        let (node, i) = self.front.pop()?;
+        if i + 1 < node.keys.len() {
+            self.front.push((node, i + 1));
+        }
+        if !node.is_leaf() {
+            self.push_leftmost(&node.children[i + 1]);
+        }
+        Some(val)
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let val = self.back_value()?;
+        if self.ends_meet() {
+            return Some(val);
+        }
+
+        let (node, end) = self.back.pop()?;
+        if end > 1 {
+            self.back.push((node, end - 1));
+        }
+        if !node.is_leaf() {
+            self.push_rightmost(&node.children[end - 1]);
+        }
+        Some(val)
+    }
+}
+
+impl<T, const B: usize> BTree<T, B> {
+    const MIN_KEYS: usize = B - 1;
+    const MAX_KEYS: usize = 2 * B - 1;
+    const DEGREE_CHECK: () = assert!(B >= 2, "a B-tree needs B >= 2");
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // levels of nodes, 0 for an empty tree
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut node = self.root.as_ref();
+        while let Some(current) = node {
+            height += 1;
+            node = current.children.first();
+        }
+        height
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        let mut iter = Iter::empty();
+        if let Some(root) = &self.root {
+            iter.push_leftmost(root);
+            iter.push_rightmost(root);
+        }
+        iter
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        self.iter().next()
+    }
+
+    pub fn max(&self) -> Option<&T> {
+        self.iter().next_back()
+    }
+
+    fn new_node(leaf: bool) -> Node<T> {
+        let children = if leaf { Vec::new() } else { Vec::with_capacity(Self::MAX_KEYS + 1) };
+        Node { keys: Vec::with_capacity(Self::MAX_KEYS), children }
+    }
+
+    // Splits the full child i around its middle key, which moves up into node:
+    //
+    //   [.. a ..]                  [.. a  m ..]
+    //        |            ->           /    \
+    //   [x .. m .. y]           [x ..]      [.. y]
+    fn split_child(node: &mut Node<T>, i: usize) {
+        let child = &mut node.children[i];
+        let mut right = Self::new_node(child.is_leaf());
+        right.keys.extend(child.keys.drain(B..));
+        if !child.is_leaf() {
+            right.children.extend(child.children.drain(B..));
+        }
+        let middle = child.keys.pop().expect("a full node has a middle key");
+
+        node.keys.insert(i, middle);
+        node.children.insert(i + 1, right);
+    }
+
+    // Merges child i + 1 and the key between them into child i, both must have B - 1 keys
+    fn merge_children(node: &mut Node<T>, i: usize) {
+        let right = node.children.remove(i + 1);
+        let separator = node.keys.remove(i);
+        let left = &mut node.children[i];
+        left.keys.push(separator);
+        left.keys.extend(right.keys);
+        left.children.extend(right.children);
+    }
+
+    // Makes sure child i has more than B - 1 keys before going down into it: borrow one through
+    // the parent from a sibling that can spare it, or merge with a sibling. Returns where the
+    // child ended up, a merge with the left sibling moves it one place left.
+    fn fill_child(node: &mut Node<T>, i: usize) -> usize {
+        if node.children[i].keys.len() > Self::MIN_KEYS {
+            return i;
+        }
+
+        if i > 0 && node.children[i - 1].keys.len() > Self::MIN_KEYS {
+            let (left, right) = node.children.split_at_mut(i);
+            let (sibling, child) = (&mut left[i - 1], &mut right[0]);
+            let borrowed = sibling.keys.pop().expect("the sibling has spare keys");
+            child.keys.insert(0, mem::replace(&mut node.keys[i - 1], borrowed));
+            if let Some(grandchild) = sibling.children.pop() {
+                child.children.insert(0, grandchild);
+            }
+            return i;
+        }
+
+        if i + 1 < node.children.len() && node.children[i + 1].keys.len() > Self::MIN_KEYS {
+            let (left, right) = node.children.split_at_mut(i + 1);
+            let (child, sibling) = (&mut left[i], &mut right[0]);
+            let borrowed = sibling.keys.remove(0);
+            child.keys.push(mem::replace(&mut node.keys[i], borrowed));
+            if !sibling.is_leaf() {
+                child.children.push(sibling.children.remove(0));
+            }
+            return i;
+        }
+
+        if i + 1 < node.children.len() {
+            Self::merge_children(node, i);
+            i
+        } else {
+            Self::merge_children(node, i - 1);
+            i - 1
+        }
+    }
+
+    // the node must have more than B - 1 keys, or be the root
+    fn pop_min(mut node: &mut Node<T>) -> T {
+        loop {
+            if node.is_leaf() {
+                return node.keys.remove(0);
+            }
+            let i = Self::fill_child(node, 0);
+            node = &mut node.children[i];
+        }
+    }
+
+    fn pop_max(mut node: &mut Node<T>) -> T {
+        loop {
+            if node.is_leaf() {
+                return node.keys.pop().expect("a filled node has keys");
+            }
+            let i = Self::fill_child(node, node.children.len() - 1);
+            node = &mut node.children[i];
+        }
+    }
+
+    // a root left without keys is replaced by its only child, or dropped if it was a leaf
+    fn shrink_root(&mut self) {
+        if let Some(root) = &mut self.root
+            && root.keys.is_empty()
+        {
+            self.root = root.children.pop();
+        }
+    }
+}
+
+impl<T: Ord, const B: usize> BTree<T, B> {
+    pub fn search(&self, val: &T) -> Option<&T> {
+        let mut node = self.root.as_ref()?;
+        loop {
+            match node.keys.binary_search(val) {
+                Ok(i) => return Some(&node.keys[i]),
+                Err(i) => node = node.children.get(i)?,
+            }
+        }
+    }
+
+    pub fn contains(&self, val: &T) -> bool {
+        self.search(val).is_some()
+    }
+
+    // a value that is already in the tree is left as it is
+    pub fn insert(&mut self, val: T) {
+        let root = self.root.get_or_insert_with(|| Self::new_node(true));
+        // the only place the tree grows taller: a full root splits under a new one
+        if root.keys.len() == Self::MAX_KEYS {
+            let old_root = mem::replace(root, Self::new_node(false));
+            root.children.push(old_root);
+            Self::split_child(root, 0);
+        }
+
+        if Self::insert_non_full(root, val) {
+            self.len += 1;
+        }
+    }
+
+    // node is not full, so a child split on the way can always take the middle key
+    fn insert_non_full(mut node: &mut Node<T>, val: T) -> bool {
+        loop {
+            let mut i = match node.keys.binary_search(&val) {
+                Ok(_) => return false,
+                Err(i) => i,
+            };
+            if node.is_leaf() {
+                node.keys.insert(i, val);
+                return true;
+            }
+
+            if node.children[i].keys.len() == Self::MAX_KEYS {
+                Self::split_child(node, i);
+                match val.cmp(&node.keys[i]) {
+                    Ordering::Equal => return false,
+                    Ordering::Greater => i += 1,
+                    Ordering::Less => {},
+                }
+            }
+            node = &mut node.children[i];
+        }
+    }
+
+    pub fn delete(&mut self, val: &T) -> Option<T> {
+        let removed = Self::delete_from(self.root.as_mut()?, val);
+        self.shrink_root();
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    // Every node entered below the root has more than B - 1 keys, so taking one out of a leaf
+    // never leaves it short. A key in an internal node is swapped with its predecessor or
+    // successor, whichever side can spare one, or both sides are merged around it first.
+    fn delete_from(mut node: &mut Node<T>, val: &T) -> Option<T> {
+        loop {
+            match node.keys.binary_search(val) {
+                Ok(i) if node.is_leaf() => return Some(node.keys.remove(i)),
+                Ok(i) => {
+                    if node.children[i].keys.len() > Self::MIN_KEYS {
+                        let predecessor = Self::pop_max(&mut node.children[i]);
+                        return Some(mem::replace(&mut node.keys[i], predecessor));
+                    }
+                    if node.children[i + 1].keys.len() > Self::MIN_KEYS {
+                        let successor = Self::pop_min(&mut node.children[i + 1]);
+                        return Some(mem::replace(&mut node.keys[i], successor));
+                    }
+                    Self::merge_children(node, i);
+                    node = &mut node.children[i];
+                },
+                Err(_) if node.is_leaf() => return None,
+                Err(i) => {
+                    let i = Self::fill_child(node, i);
+                    node = &mut node.children[i];
+                },
+            }
+        }
+    }
+
+    // values inside range in order, from both ends: tree.range(3..7), tree.range(10..).rev()
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> Iter<'_, T> {
+        let Some(root) = &self.root else {
+            return Iter::empty();
+        };
+
+        let mut iter = Iter::empty();
+        let mut node: &Node<T> = root;
+        loop {
+            let i = node.keys.partition_point(|key| match range.start_bound() {
+                Bound::Included(start) => key < start,
+                Bound::Excluded(start) => key <= start,
+                Bound::Unbounded => false,
+            });
+            if i < node.keys.len() {
+                iter.front.push((node, i));
+            }
+            let Some(child) = node.children.get(i) else {
+                break;
+            };
+            node = child;
+        }
+
+        node = root;
+        loop {
+            let end = node.keys.partition_point(|key| match range.end_bound() {
+                Bound::Included(end) => key <= end,
+                Bound::Excluded(end) => key < end,
+                Bound::Unbounded => true,
+            });
+            if end > 0 {
+                iter.back.push((node, end));
+            }
+            let Some(child) = node.children.get(end) else {
+                break;
+            };
+            node = child;
+        }
+
+        // an empty range like 5..5 leaves them crossed
+        match (iter.front_value(), iter.back_value()) {
+            (Some(front), Some(back)) if front <= back => iter,
+            _ => Iter::empty(),
+        }
+    }
+
+    // Bulk load in O(n), no splits: picks the lowest height that fits len values and spreads
+    // them evenly, so every node starts about half full and takes inserts without splitting.
+    // Repeated values are kept once (the first). Panics if the values are out of order, in
+    // release builds too: the check is one pass over values already collected.
+    pub fn from_sorted_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let () = Self::DEGREE_CHECK;
+        let mut values: Vec<T> = iter.into_iter().collect();
+        values.dedup();
+        assert!(values.windows(2).all(|pair| pair[0] < pair[1]), "from_sorted_iter needs values in increasing order");
+
+        let len = values.len();
+        if len == 0 {
+            return Self::new();
+        }
+
+        let mut height = 1;
+        while Self::max_len(height) < len {
+            height += 1;
+        }
+        let root = Self::build_sorted(&mut values.into_iter(), len, height);
+        BTree { root: Some(root), len }
+    }
+
+    // most values a tree of this height holds: (2B)^height - 1
+    fn max_len(height: u32) -> usize {
+        (2 * B).saturating_pow(height).saturating_sub(1)
+    }
+
+    // Takes the next len values into a subtree of the given height. A subtree of height h needs
+    // at least B^h - 1 values, so the node gets as many children as can get that many (at most
+    // 2B) and the values are shared out evenly between them.
+    fn build_sorted(values: &mut std::vec::IntoIter<T>, len: usize, height: u32) -> Node<T> {
+        let mut node = Self::new_node(height == 1);
+        if height == 1 {
+            node.keys.extend(values.by_ref().take(len));
+            return node;
+        }
+
+        let children = ((len + 1) / B.pow(height - 1)).min(2 * B);
+        let (share, extra) = ((len + 1) / children, (len + 1) % children);
+        for k in 0..children {
+            let child_len = share - 1 + usize::from(k < extra);
+            node.children.push(Self::build_sorted(values, child_len, height - 1));
+            if k + 1 < children {
+                node.keys.extend(values.next());
+            }
+        }
+        node
+    }
+
+    // Checks every invariant: the key count of every node, one more child than keys in the
+    // internal nodes, all the leaves at the same depth, strictly increasing values in order and
+    // the stored length
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        let mut walk = Walk { next_index: 0, previous: None, leaf_depth: None };
+        if let Some(root) = &self.root {
+            walk.check_node::<B>(root, 0)?;
+        }
+
+        if walk.next_index != self.len {
+            return Err(InvariantError::WrongLength { stored: self.len, actual: walk.next_index });
+        }
+        Ok(())
+    }
+}
+
+// state of the in order walk done by validate()
+struct Walk<'a, T> {
+    next_index: usize,
+    previous: Option<&'a T>,
+    leaf_depth: Option<usize>,
+}
+
+impl<'a, T: Ord> Walk<'a, T> {
+    fn check_node<const B: usize>(&mut self, node: &'a Node<T>, depth: usize) -> Result<(), InvariantError> {
+        let index = self.next_index;
+        let min = if depth == 0 { 1 } else { B - 1 };
+        let max = 2 * B - 1;
+        if node.keys.len() < min || node.keys.len() > max {
+            return Err(InvariantError::KeyCount { index, len: node.keys.len(), min, max });
+        }
+
+        if node.is_leaf() {
+            let expected = *self.leaf_depth.get_or_insert(depth);
+            if depth != expected {
+                return Err(InvariantError::LeafDepth { index, depth, expected });
+            }
+        } else if node.children.len() != node.keys.len() + 1 {
+            return Err(InvariantError::ChildCount { index, keys: node.keys.len(), children: node.children.len() });
+        }
+
+        for (i, key) in node.keys.iter().enumerate() {
+            if let Some(child) = node.children.get(i) {
+                self.check_node::<B>(child, depth + 1)?;
+            }
+            if self.previous.is_some_and(|previous| previous >= key) {
+                return Err(InvariantError::OutOfOrder { index: self.next_index });
+            }
+            self.previous = Some(key);
+            self.next_index += 1;
+        }
+        if let Some(last) = node.children.last() {
+            self.check_node::<B>(last, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T, const B: usize> IntoIterator for &'a BTree<T, B> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Ord, const B: usize> FromIterator<T> for BTree<T, B> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = BTree::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<T: Ord, const B: usize> Extend<T> for BTree<T, B> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(val);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xorshift::shuffled;
+    use std::collections::BTreeSet;
+    use std::rc::Rc;
+
+    fn values<T: Copy, const B: usize>(tree: &BTree<T, B>) -> Vec<T> {
+        tree.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let mut tree: BTree<i32> = BTree::new();
+        assert!(tree.is_empty());
+        assert_eq!(tree.height(), 0);
+        assert_eq!(tree.search(&1), None);
+        assert_eq!(tree.delete(&1), None);
+        assert_eq!(tree.iter().next(), None);
+        assert_eq!(tree.range(..).next_back(), None);
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_insert_splits() {
+        // B = 2 is a 2-3-4 tree, a node splits at its fourth key
+        let mut tree: BTree<i32, 2> = BTree::new();
+        for val in [10, 20, 30] {
+            tree.insert(val);
+        }
+        assert_eq!(tree.height(), 1);
+        tree.insert(40);
+        assert_eq!(tree.height(), 2);
+        tree.insert(20);
+        assert_eq!(tree.len(), 4);
+        assert_eq!(values(&tree), vec![10, 20, 30, 40]);
+        assert_eq!(tree.search(&30), Some(&30));
+        assert_eq!(tree.search(&35), None);
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_insert_and_delete_keep_invariants() {
+        let mut tree: BTree<u64, 2> = BTree::new();
+        for val in shuffled(500) {
+            tree.insert(val);
+            assert_eq!(tree.validate(), Ok(()));
+        }
+        assert_eq!(values(&tree), (0..500).collect::<Vec<_>>());
+
+        for (removed, val) in shuffled(500).iter().rev().enumerate() {
+            assert_eq!(tree.delete(val), Some(*val));
+            assert_eq!(tree.delete(val), None);
+            assert_eq!(tree.len(), 500 - removed - 1);
+            assert_eq!(tree.validate(), Ok(()));
+        }
+        assert!(tree.root.is_none());
+    }
+
+    #[test]
+    fn test_mixed_operations() {
+        let mut tree: BTree<u64, 3> = BTree::new();
+        let mut expected = BTreeSet::new();
+        for (step, val) in shuffled(3000).into_iter().enumerate() {
+            let val = val % 400;
+            if step % 3 == 0 {
+                assert_eq!(tree.delete(&val), expected.take(&val));
+            } else {
+                tree.insert(val);
+                expected.insert(val);
+            }
+        }
+        assert_eq!(tree.validate(), Ok(()));
+        assert!(tree.iter().eq(expected.iter()));
+        assert!(tree.iter().rev().eq(expected.iter().rev()));
+    }
+
+    #[test]
+    fn test_sorted_inserts_height() {
+        let tree: BTree<i32> = (0..10_000).collect();
+        assert_eq!(tree.validate(), Ok(()));
+        // at least B - 1 = 5 keys per node below the root
+        assert!(tree.height() <= 6);
+        assert_eq!(tree.min(), Some(&0));
+        assert_eq!(tree.max(), Some(&9999));
+    }
+
+    #[test]
+    fn test_iter_both_ends() {
+        let tree: BTree<i32, 2> = (1..=20).collect();
+        let mut iter = tree.iter();
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&20));
+        let middle: Vec<_> = iter.by_ref().take(9).copied().collect();
+        assert_eq!(middle, (2..=10).collect::<Vec<_>>());
+        assert_eq!(iter.next_back(), Some(&19));
+        assert_eq!(iter.rev().copied().collect::<Vec<_>>(), (11..=18).rev().collect::<Vec<_>>());
+        assert_eq!((&tree).into_iter().count(), 20);
+    }
+
+    #[test]
+    fn test_range() {
+        let tree: BTree<i32, 2> = (0..50).map(|val| val * 2).collect();
+        assert_eq!(tree.range(5..11).copied().collect::<Vec<_>>(), vec![6, 8, 10]);
+        assert_eq!(tree.range(6..=10).rev().copied().collect::<Vec<_>>(), vec![10, 8, 6]);
+        assert_eq!(tree.range((Bound::Excluded(90), Bound::Unbounded)).count(), 4);
+        assert_eq!(tree.range(..4).copied().collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(tree.range(7..7).next(), None);
+        assert_eq!(tree.range(7..8).next(), None);
+        assert_eq!(tree.range(200..).next(), None);
+        assert_eq!(tree.range(..).count(), 50);
+
+        // every bound against a plain filter
+        for start in -1..101 {
+            for end in start..101 {
+                let expected: Vec<i32> = values(&tree).into_iter().filter(|val| (start..end).contains(val)).collect();
+                assert_eq!(tree.range(start..end).copied().collect::<Vec<_>>(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_sorted_iter() {
+        for len in [0, 1, 2, 3, 4, 10, 11, 12, 100, 143, 144, 1000, 5000] {
+            let tree: BTree<usize, 2> = BTree::from_sorted_iter(0..len);
+            assert_eq!(tree.validate(), Ok(()), "len {len}");
+            assert_eq!(values(&tree), (0..len).collect::<Vec<_>>());
+
+            let tree: BTree<usize> = BTree::from_sorted_iter(0..len);
+            assert_eq!(tree.validate(), Ok(()), "len {len}");
+            assert_eq!(tree.len(), len);
+        }
+
+        // lowest height that fits: 11 keys in one node, 143 in two levels
+        assert_eq!(BTree::<i32>::from_sorted_iter(0..11).height(), 1);
+        assert_eq!(BTree::<i32>::from_sorted_iter(0..12).height(), 2);
+        assert_eq!(BTree::<i32>::from_sorted_iter(0..143).height(), 2);
+        assert_eq!(BTree::<i32>::from_sorted_iter(0..144).height(), 3);
+
+        let mut tree: BTree<i32, 2> = BTree::from_sorted_iter(0..100);
+        tree.insert(1000);
+        assert_eq!(tree.delete(&50), Some(50));
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_from_sorted_iter_duplicates() {
+        let tree: BTree<i32, 2> = BTree::from_sorted_iter([1, 1, 2, 3, 3, 3, 4]);
+        assert_eq!(tree.validate(), Ok(()));
+        assert_eq!(values(&tree), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "increasing order")]
+    fn test_from_sorted_iter_unsorted() {
+        BTree::<i32>::from_sorted_iter([1, 3, 2]);
+    }
+
+    #[test]
+    fn test_nodes_never_reallocate() {
+        let mut tree: BTree<u64, 3> = shuffled(300).into_iter().collect();
+        for val in shuffled(300).iter().step_by(2) {
+            tree.delete(val);
+        }
+
+        let mut stack = vec![tree.root.as_ref().unwrap()];
+        while let Some(node) = stack.pop() {
+            assert_eq!(node.keys.capacity(), 5);
+            assert_eq!(node.children.capacity(), if node.is_leaf() { 0 } else { 6 });
+            stack.extend(node.children.iter());
+        }
+    }
+
+    #[test]
+    fn test_drop_values() {
+        let alive = Rc::new(());
+        let mut tree: BTree<(i32, Rc<()>), 2> = BTree::new();
+        for val in 0..50 {
+            tree.insert((val, alive.clone()));
+        }
+        drop(tree.delete(&(10, alive.clone())));
+        assert_eq!(Rc::strong_count(&alive), 50);
+        drop(tree);
+        assert_eq!(Rc::strong_count(&alive), 1);
+    }
+
+    #[test]
+    fn test_validate_finds_broken_trees() {
+        let mut tree: BTree<i32, 2> = (0..10).collect();
+        let root = tree.root.as_mut().unwrap();
+
+        let first = mem::replace(&mut root.children[0].keys[0], 100);
+        assert!(matches!(tree.validate(), Err(InvariantError::OutOfOrder { .. })));
+        let root = tree.root.as_mut().unwrap();
+        root.children[0].keys[0] = first;
+
+        let keys = mem::take(&mut root.children[0].keys);
+        assert_eq!(tree.validate(), Err(InvariantError::KeyCount { index: 0, len: 0, min: 1, max: 3 }));
+        let root = tree.root.as_mut().unwrap();
+        root.children[0].keys = keys;
+
+        let child = root.children.pop().unwrap();
+        assert!(matches!(tree.validate(), Err(InvariantError::ChildCount { .. })));
+        let root = tree.root.as_mut().unwrap();
+        root.children.push(child);
+        assert_eq!(tree.validate(), Ok(()));
+
+        tree.len = 3;
+        assert_eq!(tree.validate(), Err(InvariantError::WrongLength { stored: 3, actual: 10 }));
+    }
+}
@@ -1,63 +1,390 @@
 use std::ptr;
 use std::cmp::Ordering;
+use std::fmt;
+use std::marker::PhantomData;
+use crate::invariant::InvariantError;
+use crate::set_ops::{ Difference, Intersection, SymmetricDifference, Union };
+use crate::tree_fmt;
+use std::ops::{Bound, RangeBounds};
 
-type Link<T> = Option<Box<Node<T>>>;
-
-#[derive(Debug, PartialEq)]
+// The tree owns its nodes through raw pointers (Box::into_raw), like the red-black tree.
+// The cursors and iterators keep pointers to nodes across changes to the tree, and a pointer
+// into a Box doesn't survive the Box being moved or mutably borrowed again.
 struct Node<T> {
     val: T,
     parent: *mut Node<T>,
-    left: Link<T>,
-    right: Link<T>,
+    left: *mut Node<T>,
+    right: *mut Node<T>,
+}
+
+impl<T> Node<T> {
+    // the children as references, they live as long as the borrow of the tree the node came from
+    fn left(&self) -> Option<&Node<T>> {
+        unsafe { self.left.as_ref() }
+    }
+
+    fn right(&self) -> Option<&Node<T>> {
+        unsafe { self.right.as_ref() }
+    }
 }
 
 pub struct BinarySearchTree<T> {
-    root: Link<T>,
+    root: *mut Node<T>,
+    _marker: PhantomData<Box<Node<T>>>, // the tree owns its nodes
 }
 
+// the nodes are only reachable through the tree, so it can move between threads like a Box
+unsafe impl<T: Send> Send for BinarySearchTree<T> {}
+unsafe impl<T: Sync> Sync for BinarySearchTree<T> {}
+
 impl<T> Default for BinarySearchTree<T> {
     fn default() -> Self {
-        Self { root: None }
+        Self { root: ptr::null_mut(), _marker: PhantomData }
     }
 }
 
 impl<T> Drop for BinarySearchTree<T> {
     fn drop(&mut self) {
-        let mut stack = Vec::new();
-        if let Some(root) = self.root.take() {
-            stack.push(root);
+        let mut stack = vec![self.root];
+        while let Some(node) = stack.pop() {
+            if node.is_null() {
+                continue;
+            }
+            let node = unsafe { Box::from_raw(node) };
+            stack.push(node.left);
+            stack.push(node.right);
         }
+    }
+}
 
-        while let Some(mut node) = stack.pop() {
-            if let Some(left_child) = node.left.take() {
-                stack.push(left_child);
-            }
-            if let Some(right_child) = node.right.take() {
-                stack.push(right_child);
-            }
+// Safety for the helpers below: node must be a valid node of a tree with correct parent pointers
+unsafe fn leftmost<T>(mut node: *mut Node<T>) -> *mut Node<T> {
+    unsafe {
+        while !(*node).left.is_null() {
+            node = (*node).left;
+        }
+    }
+    node
+}
+
+unsafe fn rightmost<T>(mut node: *mut Node<T>) -> *mut Node<T> {
+    unsafe {
+        while !(*node).right.is_null() {
+            node = (*node).right;
+        }
+    }
+    node
+}
+
+// Next node in order, null after the last one.
+// If there is a right subtree the next node is its minimum, otherwise climb the parents
+// until coming up from a left child. Every edge is walked at most twice in a full walk,
+// so this is amortized O(1) without any stack.
+unsafe fn next_node<T>(node: *mut Node<T>) -> *mut Node<T> {
+    unsafe {
+        if !(*node).right.is_null() {
+            return leftmost((*node).right);
+        }
+
+        let mut child = node;
+        let mut parent = (*node).parent;
+        while !parent.is_null() && (*parent).right == child {
+            child = parent;
+            parent = (*parent).parent;
         }
+        parent
     }
 }
 
-// struct to hold the value for the iteration
+// mirror of next_node
+unsafe fn prev_node<T>(node: *mut Node<T>) -> *mut Node<T> {
+    unsafe {
+        if !(*node).left.is_null() {
+            return rightmost((*node).left);
+        }
+
+        let mut child = node;
+        let mut parent = (*node).parent;
+        while !parent.is_null() && (*parent).left == child {
+            child = parent;
+            parent = (*parent).parent;
+        }
+        parent
+    }
+}
+
+// struct to hold the value for the iteration.
+// front and back are the next nodes to hand out from each end, walking with the parent pointers.
+// Both become null once they cross.
 pub struct Iter<'a,T> {
-    stack: Vec<&'a Node<T>>,
+    front: *mut Node<T>,
+    back: *mut Node<T>,
+    _marker: PhantomData<&'a T>, // the iterator borrows the tree
 }
 
 // implement the Iterator trait and the next method for the struct
 impl<'a,T> Iterator for Iter<'a,T> {
     type Item = &'a T;
 
-    // Get the value from the stack.
-    // Go to the right branck from the poped node since all the left side was stacked on the iter
-    // function.
-    // Retrieve the left side of that right node.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front.is_null() {
+            return None;
+        }
+
+        let node = self.front;
+        if self.front == self.back {
+            // the last one, both ends met
+            self.front = ptr::null_mut();
+            self.back = ptr::null_mut();
+        } else {
+            self.front = unsafe { next_node(node) };
+        }
+        Some(unsafe { &(*node).val })
+    }
+}
+
+impl<'a,T> DoubleEndedIterator for Iter<'a,T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.back.is_null() {
+            return None;
+        }
+
+        let node = self.back;
+        if self.front == self.back {
+            self.front = ptr::null_mut();
+            self.back = ptr::null_mut();
+        } else {
+            self.back = unsafe { prev_node(node) };
+        }
+        Some(unsafe { &(*node).val })
+    }
+}
+
+// Read only cursor over the values in order.
+// Besides pointing at a value it can point at the "ghost" position (current is null),
+// which sits between the last and the first value: moving next from it goes to the minimum
+// and moving prev goes to the maximum.
+pub struct Cursor<'a, T> {
+    current: *mut Node<T>,
+    tree: &'a BinarySearchTree<T>,
+}
+
+impl<'a, T> Cursor<'a, T> {
+    pub fn current(&self) -> Option<&'a T> {
+        unsafe { self.current.as_ref().map(|node| &node.val) }
+    }
+
+    fn next_of(&self, node: *mut Node<T>) -> *mut Node<T> {
+        if node.is_null() {
+            return self.tree.first();
+        }
+        unsafe { next_node(node) }
+    }
+
+    fn prev_of(&self, node: *mut Node<T>) -> *mut Node<T> {
+        if node.is_null() {
+            return self.tree.last();
+        }
+        unsafe { prev_node(node) }
+    }
+
+    pub fn move_next(&mut self) {
+        self.current = self.next_of(self.current);
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = self.prev_of(self.current);
+    }
+
+    pub fn peek_next(&self) -> Option<&'a T> {
+        unsafe { self.next_of(self.current).as_ref().map(|node| &node.val) }
+    }
+
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        unsafe { self.prev_of(self.current).as_ref().map(|node| &node.val) }
+    }
+}
+
+// Cursor that can also remove or replace the value it points at
+pub struct CursorMut<'a, T> {
+    current: *mut Node<T>,
+    tree: &'a mut BinarySearchTree<T>,
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    // the value can't be borrowed mutably, changing it could break the tree order.
+    // Use replace_current instead
+    pub fn current(&self) -> Option<&T> {
+        unsafe { self.current.as_ref().map(|node| &node.val) }
+    }
+
+    // a read only cursor at the same position, it borrows this one
+    pub fn as_cursor(&self) -> Cursor<'_, T> {
+        Cursor { current: self.current, tree: self.tree }
+    }
+
+    pub fn move_next(&mut self) {
+        self.current = self.as_cursor().next_of(self.current);
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = self.as_cursor().prev_of(self.current);
+    }
+
+    pub fn peek_next(&self) -> Option<&T> {
+        let cursor = self.as_cursor();
+        unsafe { cursor.next_of(self.current).as_ref().map(|node| &node.val) }
+    }
+
+    pub fn peek_prev(&self) -> Option<&T> {
+        let cursor = self.as_cursor();
+        unsafe { cursor.prev_of(self.current).as_ref().map(|node| &node.val) }
+    }
+
+    // Removes the value at the cursor and moves to the next one (or to the ghost position
+    // after the maximum). Does nothing on the ghost position.
+    pub fn remove_current(&mut self) -> Option<T> {
+        if self.current.is_null() {
+            return None;
+        }
+
+        // the next node is relinked by the removal at most, never freed
+        let next = unsafe { next_node(self.current) };
+        let val = unsafe { self.tree.remove_node(self.current) };
+        self.current = next;
+        Some(val)
+    }
+}
+
+impl<'a, T: Ord> CursorMut<'a, T> {
+    // Swaps the value at the cursor for val, as long as val still sits between its neighbours.
+    // Gives val back otherwise, or on the ghost position.
+    pub fn replace_current(&mut self, val: T) -> Result<T, T> {
+        if self.current.is_null() {
+            return Err(val);
+        }
+
+        unsafe {
+            let after_prev = prev_node(self.current).as_ref().is_none_or(|prev| prev.val < val);
+            let before_next = next_node(self.current).as_ref().is_none_or(|next| val < next.val);
+            if !(after_prev && before_next) {
+                return Err(val);
+            }
+            Ok(std::mem::replace(&mut (*self.current).val, val))
+        }
+    }
+}
+
+// Owning iterator, hands out the values in order from both ends.
+// front is always the minimum of what is left (no left child) and back the maximum
+// (no right child), so taking them out never moves another node.
+pub struct IntoIter<T> {
+    tree: BinarySearchTree<T>,
+    front: *mut Node<T>,
+    back: *mut Node<T>,
+}
+
+impl<T> IntoIter<T> {
+    fn take(&mut self, node: *mut Node<T>) -> Option<T> {
+        Some(unsafe { self.tree.remove_node(node) })
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front.is_null() {
+            return None;
+        }
+
+        let node = self.front;
+        if self.front == self.back {
+            self.front = ptr::null_mut();
+            self.back = ptr::null_mut();
+        } else {
+            self.front = unsafe { next_node(node) };
+        }
+        self.take(node)
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.back.is_null() {
+            return None;
+        }
+
+        let node = self.back;
+        if self.front == self.back {
+            self.front = ptr::null_mut();
+            self.back = ptr::null_mut();
+        } else {
+            self.back = unsafe { prev_node(node) };
+        }
+        self.take(node)
+    }
+}
+
+// Lazy iterator from extract_if, removes and yields the values matching the predicate in order.
+// Whatever is not visited stays in the tree.
+pub struct ExtractIf<'a, T, F: FnMut(&T) -> bool> {
+    cursor: CursorMut<'a, T>,
+    pred: F,
+}
+
+impl<'a, T, F: FnMut(&T) -> bool> Iterator for ExtractIf<'a, T, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(val) = self.cursor.current() {
+            if (self.pred)(val) {
+                return self.cursor.remove_current();
+            }
+            self.cursor.move_next();
+        }
+        None
+    }
+}
+
+// Iterator over the values inside a range.
+// Same idea as Iter, but the stack starts with the path to the first value inside the range
+// (O(log n) on a balanced tree) and it stops at the first value past the end.
+pub struct Range<'a, T, R> {
+    stack: Vec<&'a Node<T>>,
+    range: R,
+}
+
+fn after_start<T: Ord>(val: &T, start: Bound<&T>) -> bool {
+    match start {
+        Bound::Included(start) => val >= start,
+        Bound::Excluded(start) => val > start,
+        Bound::Unbounded => true,
+    }
+}
+
+fn before_end<T: Ord>(val: &T, end: Bound<&T>) -> bool {
+    match end {
+        Bound::Included(end) => val <= end,
+        Bound::Excluded(end) => val < end,
+        Bound::Unbounded => true,
+    }
+}
+
+impl<'a, T: Ord, R: RangeBounds<T>> Iterator for Range<'a, T, R> {
+    type Item = &'a T;
+
     fn next(&mut self) -> Option<Self::Item> {
         let node = self.stack.pop()?;
-        let mut current_node = node.right.as_ref();
+        if !before_end(&node.val, self.range.end_bound()) {
+            self.stack.clear(); // everything left on the stack is even bigger
+            return None;
+        }
+
+        let mut current_node = node.right();
         while let Some(n) = current_node {
             self.stack.push(n);
-            current_node = n.left.as_ref();
+            current_node = n.left();
         }
         Some(&node.val)
     }
@@ -68,171 +395,577 @@ impl<T> BinarySearchTree<T> {
         Self::default()
     }
 
+    fn root_node(&self) -> Option<&Node<T>> {
+        unsafe { self.root.as_ref() }
+    }
+
+    // the node with the minimum, null if the tree is empty
+    fn first(&self) -> *mut Node<T> {
+        if self.root.is_null() {
+            return ptr::null_mut();
+        }
+        unsafe { leftmost(self.root) }
+    }
+
+    fn last(&self) -> *mut Node<T> {
+        if self.root.is_null() {
+            return ptr::null_mut();
+        }
+        unsafe { rightmost(self.root) }
+    }
+
     // populate the Iter struct so can be called the next function of trait Iterator
     pub fn iter<'a>(&'a self) -> Iter<'a,T> {
-        let mut iterator = Iter { stack: vec![] };
-        let mut current_node = self.root.as_ref();
+        Iter { front: self.first(), back: self.last(), _marker: PhantomData }
+    }
 
-        while let Some(node) = current_node {
-            iterator.stack.push(node);
-            current_node = node.left.as_ref();
+    // cursor at the minimum, or at the ghost position if the tree is empty
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        Cursor { current: self.first(), tree: self }
+    }
+
+    // cursor at the maximum, or at the ghost position if the tree is empty
+    pub fn cursor_back(&self) -> Cursor<'_, T> {
+        Cursor { current: self.last(), tree: self }
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut { current: self.first(), tree: self }
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut { current: self.last(), tree: self }
+    }
+
+    // Empties the tree, the values come out in order.
+    // The tree is empty right away, values left in the iterator are dropped with it.
+    pub fn drain(&mut self) -> IntoIter<T> {
+        std::mem::take(self).into_iter()
+    }
+
+    // keeps only the values for which keep returns true, visiting them in order
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut keep: F) {
+        self.extract_if(|val| !keep(val)).for_each(drop);
+    }
+
+    // The values only get a shared reference, changing them in place could break the order
+    pub fn extract_if<F: FnMut(&T) -> bool>(&mut self, pred: F) -> ExtractIf<'_, T, F> {
+        ExtractIf { cursor: self.cursor_front_mut(), pred }
+    }
+
+    // Day-Stout-Warren: flatten the tree into a sorted right-leaning vine with right rotations,
+    // then fold the vine back into a complete tree with rounds of left rotations.
+    // O(n) time and no extra memory besides the final walk that fixes the parent pointers.
+    pub fn rebalance(&mut self) {
+        unsafe {
+            let len = Self::tree_to_vine(&raw mut self.root);
+
+            // the leaves that don't fit in a perfect tree go first
+            // perfect is the size of the biggest perfect tree that fits, 2^k - 1 <= len
+            let mut perfect = 0;
+            while perfect * 2 < len {
+                perfect = perfect * 2 + 1;
+            }
+            let mut rotations = len - perfect;
+            Self::compress(&raw mut self.root, rotations);
+            rotations = perfect;
+            while rotations > 1 {
+                rotations /= 2;
+                Self::compress(&raw mut self.root, rotations);
+            }
         }
-        iterator
+
+        self.fix_parents();
     }
 
-    pub fn min(&self) -> Option<&T> {
-        let mut node = self.root.as_ref()?;
-        while let Some(left) = &node.left {
-            node = left;
+    // Safety for the rotations: link must hold a node with a child on the side it rotates from.
+    // They don't touch the parents, rebalance sets them all at the end
+    unsafe fn rotate_link_right(link: *mut *mut Node<T>) {
+        unsafe {
+            let node = *link;
+            let left = (*node).left;
+            (*node).left = (*left).right;
+            (*left).right = node;
+            *link = left;
         }
-        Some(&node.val)
+    }
+
+    unsafe fn rotate_link_left(link: *mut *mut Node<T>) {
+        unsafe {
+            let node = *link;
+            let right = (*node).right;
+            (*node).right = (*right).left;
+            (*right).left = node;
+            *link = right;
+        }
+    }
+
+    // rotates right until no node has a left child, returns how many nodes there are
+    unsafe fn tree_to_vine(root: *mut *mut Node<T>) -> usize {
+        let mut len = 0;
+        let mut current_link = root;
+        unsafe {
+            while !(*current_link).is_null() {
+                let node = *current_link;
+                if !(*node).left.is_null() {
+                    Self::rotate_link_right(current_link);
+                } else {
+                    len += 1;
+                    current_link = &raw mut (*node).right;
+                }
+            }
+        }
+        len
+    }
+
+    // one left rotation on every other node of the vine, count times
+    unsafe fn compress(root: *mut *mut Node<T>, count: usize) {
+        let mut current_link = root;
+        for _ in 0..count {
+            unsafe {
+                Self::rotate_link_left(current_link);
+                current_link = &raw mut (**current_link).right;
+            }
+        }
+    }
+
+    // rotations don't know about parents, set them all again from the root
+    fn fix_parents(&mut self) {
+        if self.root.is_null() {
+            return;
+        }
+
+        unsafe {
+            (*self.root).parent = ptr::null_mut();
+            let mut stack = vec![self.root];
+            while let Some(node) = stack.pop() {
+                for child in [(*node).left, (*node).right] {
+                    if !child.is_null() {
+                        (*child).parent = node;
+                        stack.push(child);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        unsafe { self.first().as_ref().map(|node| &node.val) }
     }
     
     pub fn max(&self) -> Option<&T> {
-        let mut node = self.root.as_ref()?;
-        while let Some(right) = &node.right {
-            node = right;
+        unsafe { self.last().as_ref().map(|node| &node.val) }
+    }
+
+    // puts new where old hangs: the root or the left/right link of old's parent
+    // Safety: old must be a node of this tree, new null or a node
+    unsafe fn transplant(&mut self, old: *mut Node<T>, new: *mut Node<T>) {
+        unsafe {
+            let parent = (*old).parent;
+            if parent.is_null() {
+                self.root = new;
+            } else if (*parent).left == old {
+                (*parent).left = new;
+            } else {
+                (*parent).right = new;
+            }
+            if !new.is_null() {
+                (*new).parent = parent;
+            }
+        }
+    }
+
+    // Takes node out of the tree, frees it and puts its subtrees back together.
+    // With two children the successor node takes its place, no value moves between nodes, so
+    // pointers to every other node stay good.
+    // Safety: node must be a node of this tree
+    unsafe fn remove_node(&mut self, node: *mut Node<T>) -> T {
+        unsafe {
+            let replacement = if (*node).left.is_null() {
+                (*node).right
+            } else if (*node).right.is_null() {
+                (*node).left
+            } else {
+                // GET THE MOST LEFT NODE FROM THE RIGHT TREE
+                let successor = leftmost((*node).right);
+                if successor != (*node).right {
+                    // it has no left child, its right subtree takes its place
+                    self.transplant(successor, (*successor).right);
+                    (*successor).right = (*node).right;
+                    (*(*successor).right).parent = successor;
+                }
+                (*successor).left = (*node).left;
+                (*(*successor).left).parent = successor;
+                successor
+            };
+
+            self.transplant(node, replacement);
+            Box::from_raw(node).val
         }
-        Some(&node.val)
     }
 }
 
 impl<T: Ord> BinarySearchTree<T> {
     pub fn search(&self, val: &T) -> Option<&T> {
-        let mut current_node = self.root.as_ref();
-        while let Some(node) = current_node {
-            if node.val == *val {
-                return Some(&node.val);
-            } else if node.val < *val {
-                current_node = node.right.as_ref();
-            } else {
-                current_node = node.left.as_ref();
+        unsafe { self.find_node(val).as_ref().map(|node| &node.val) }
+    }
+
+    // Builds a complete tree from sorted values in O(n), repeated values are kept once (the first).
+    // Inserting sorted values one by one would give a tree shaped like a linked list.
+    // Values out of order panic in debug builds, release builds don't check them and give a tree
+    // that fails validate.
+    pub fn from_sorted_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut values: Vec<T> = iter.into_iter().collect();
+        values.dedup();
+        debug_assert!(values.windows(2).all(|pair| pair[0] < pair[1]), "from_sorted_iter needs values in increasing order");
+
+        let len = values.len();
+        let mut values = values.into_iter();
+        let root = Self::build_sorted(&mut values, len);
+        BinarySearchTree { root, _marker: PhantomData }
+    }
+
+    // Takes the next len values in order: the first half goes to the left subtree, then the root,
+    // then the rest to the right subtree. The caller sets the parent of the returned node.
+    fn build_sorted(values: &mut std::vec::IntoIter<T>, len: usize) -> *mut Node<T> {
+        if len == 0 {
+            return ptr::null_mut();
+        }
+
+        let left_len = len / 2;
+        let left = Self::build_sorted(values, left_len);
+        let node = Box::into_raw(Box::new(Node {
+            val: values.next().expect("build_sorted is given at least len values"),
+            parent: ptr::null_mut(),
+            left,
+            right: ptr::null_mut(),
+        }));
+
+        unsafe {
+            (*node).right = Self::build_sorted(values, len - left_len - 1);
+            for child in [(*node).left, (*node).right] {
+                if !child.is_null() {
+                    (*child).parent = node;
+                }
             }
         }
-        None
+        node
     }
 
-    #[allow(clippy::while_let_loop)] // while let will create a single continuous borrow
-                                    // here is needed multiple (non-simultaneously) borrows and multible matches
-    pub fn insert(&mut self, val: T) {
-        let mut current_link: &mut Link<T> = &mut self.root;
-        let mut parent: *mut Node<T> = ptr::null_mut();
-        
+    // Checks every invariant of the tree: strictly increasing values in order and every node
+    // pointing back at its parent (the root at null). Walks with a stack, no recursion.
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        // nodes waiting to be visited with the parent they should point at
+        let mut stack: Vec<(&Node<T>, *const Node<T>)> = Vec::new();
+        let mut current = self.root_node().map(|root| (root, ptr::null()));
+        let mut previous: Option<&T> = None;
+        let mut index = 0;
+
         loop {
-            let node = match current_link {
-                Some(node) => node,
-                None => break,
+            while let Some((node, parent)) = current {
+                stack.push((node, parent));
+                current = node.left().map(|left| (left, node as *const Node<T>));
+            }
+            let Some((node, parent)) = stack.pop() else {
+                return Ok(());
             };
 
-            parent = &mut **node as *mut Node<T>;
+            if !ptr::eq(node.parent, parent) {
+                return Err(InvariantError::WrongParent { index });
+            }
+            if previous.is_some_and(|previous| *previous >= node.val) {
+                return Err(InvariantError::OutOfOrder { index });
+            }
+            previous = Some(&node.val);
+            index += 1;
+            current = node.right().map(|right| (right, node as *const Node<T>));
+        }
+    }
+
+    // the node holding val, null if there is none
+    fn find_node(&self, val: &T) -> *mut Node<T> {
+        let mut current = self.root;
+        unsafe {
+            while !current.is_null() {
+                match val.cmp(&(*current).val) {
+                    Ordering::Equal => break,
+                    Ordering::Less => current = (*current).left,
+                    Ordering::Greater => current = (*current).right,
+                }
+            }
+        }
+        current
+    }
+
+    // cursor at val, or at the ghost position if val is not in the tree
+    pub fn cursor_at(&self, val: &T) -> Cursor<'_, T> {
+        Cursor { current: self.find_node(val), tree: self }
+    }
 
+    pub fn cursor_at_mut(&mut self, val: &T) -> CursorMut<'_, T> {
+        CursorMut { current: self.find_node(val), tree: self }
+    }
+
+    // values inside range in order, tree.range(3..7), tree.range(10..), tree.range(..=5)
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> Range<'_, T, R> {
+        // stack the path to the first value inside the range:
+        // a node before the start is skipped together with its left subtree
+        let mut stack = Vec::new();
+        let mut current_node = self.root_node();
+        while let Some(node) = current_node {
+            if after_start(&node.val, range.start_bound()) {
+                stack.push(node);
+                current_node = node.left();
+            } else {
+                current_node = node.right();
+            }
+        }
+
+        Range { stack, range }
+    }
+
+    // Walk down from the root remembering the last node that fits.
+    // When accept_equal is set an exact match is returned right away.
+    // Going left looks for smaller values, going right for bigger ones.
+    fn closest(&self, val: &T, smaller: bool, accept_equal: bool) -> Option<&T> {
+        let mut best = None;
+        let mut current_node = self.root_node();
+        while let Some(node) = current_node {
             match val.cmp(&node.val) {
-                Ordering::Less => current_link = &mut node.left,
-                Ordering::Greater => current_link = &mut node.right,
-                Ordering::Equal => return,
+                Ordering::Equal if accept_equal => return Some(&node.val),
+                // equal but not accepted: the answer is in the subtree on the wanted side
+                Ordering::Equal if smaller => current_node = node.left(),
+                Ordering::Equal => current_node = node.right(),
+                Ordering::Less => {
+                    if !smaller {
+                        best = Some(&node.val);
+                    }
+                    current_node = node.left();
+                },
+                Ordering::Greater => {
+                    if smaller {
+                        best = Some(&node.val);
+                    }
+                    current_node = node.right();
+                },
             }
         }
+        best
+    }
+
+    // biggest value <= val
+    pub fn floor(&self, val: &T) -> Option<&T> {
+        self.closest(val, true, true)
+    }
 
-        let new_node = Box::new(Node {
-            val,
-            parent,
-            left: None,
-            right: None,
-        });
-        
-        *current_link = Some(new_node);
+    // smallest value >= val
+    pub fn ceiling(&self, val: &T) -> Option<&T> {
+        self.closest(val, false, true)
+    }
+
+    // biggest value < val
+    pub fn predecessor(&self, val: &T) -> Option<&T> {
+        self.closest(val, true, false)
+    }
+
+    // smallest value > val
+    pub fn successor(&self, val: &T) -> Option<&T> {
+        self.closest(val, false, false)
+    }
+
+    pub fn insert(&mut self, val: T) {
+        let mut parent = ptr::null_mut();
+        let mut link: *mut *mut Node<T> = &raw mut self.root;
+
+        unsafe {
+            while !(*link).is_null() {
+                parent = *link;
+                match val.cmp(&(*parent).val) {
+                    Ordering::Less => link = &raw mut (*parent).left,
+                    Ordering::Greater => link = &raw mut (*parent).right,
+                    Ordering::Equal => return,
+                }
+            }
+
+            *link = Box::into_raw(Box::new(Node {
+                val,
+                parent,
+                left: ptr::null_mut(),
+                right: ptr::null_mut(),
+            }));
+        }
     }
 
     pub fn delete(&mut self, val: &T) -> Option<T> {
-        // The only way this works is with a raw pointer and some unsafe expressions.
-        // The borrow checker did not allowed some safe code.
-        // Maybe I'm just dumb and don't know how to do it with 100% safe rust.
-        let mut current_link_ptr: *mut Link<T> = &mut self.root;
+        let node = self.find_node(val);
+        if node.is_null() {
+            return None;
+        }
+        Some(unsafe { self.remove_node(node) })
+    }
 
-        loop {
-            let node = unsafe {
-                match (*current_link_ptr).as_mut() {
-                    Some(node) => node,
-                    None => break,
+    // Moves every value >= key into a new tree, self keeps the smaller ones.
+    // Walks one path down cutting it in two, so it's O(height) and no node is reallocated.
+    pub fn split_off(&mut self, key: &T) -> Self {
+        let mut right = BinarySearchTree::new();
+        let mut current = std::mem::replace(&mut self.root, ptr::null_mut());
+
+        // where the next node of each side gets hooked, and the node that owns that hook
+        let mut left_hook: *mut *mut Node<T> = &raw mut self.root;
+        let mut left_parent: *mut Node<T> = ptr::null_mut();
+        let mut right_hook: *mut *mut Node<T> = &raw mut right.root;
+        let mut right_parent: *mut Node<T> = ptr::null_mut();
+
+        unsafe {
+            while !current.is_null() {
+                let node = current;
+                if (*node).val < *key {
+                    // node and its left subtree stay, keep cutting its right subtree
+                    current = std::mem::replace(&mut (*node).right, ptr::null_mut());
+                    (*node).parent = left_parent;
+                    *left_hook = node;
+                    left_hook = &raw mut (*node).right;
+                    left_parent = node;
+                } else {
+                    // node and its right subtree move, keep cutting its left subtree
+                    current = std::mem::replace(&mut (*node).left, ptr::null_mut());
+                    (*node).parent = right_parent;
+                    *right_hook = node;
+                    right_hook = &raw mut (*node).left;
+                    right_parent = node;
                 }
+            }
+        }
+        right
+    }
+
+    // Moves every value of other into self, leaving other empty.
+    // Values that were in both trees keep the one from other, like AvlTree::append.
+    // Merges the two in-order walks and rebuilds a complete tree, O(n + m).
+    pub fn append(&mut self, other: &mut Self) {
+        let mut mine = std::mem::take(self).into_iter().peekable();
+        let mut theirs = std::mem::take(other).into_iter().peekable();
+        let mut merged = Vec::new();
+        loop {
+            let next = match (mine.peek(), theirs.peek()) {
+                (Some(a), Some(b)) => match a.cmp(b) {
+                    Ordering::Less => mine.next(),
+                    Ordering::Greater => theirs.next(),
+                    Ordering::Equal => {
+                        mine.next(); // the same value is in other
+                        theirs.next()
+                    },
+                },
+                (Some(_), None) => mine.next(),
+                (None, _) => theirs.next(),
             };
-            
-            match val.cmp(&node.val) {
-                Ordering::Less => current_link_ptr = &mut node.left,
-                Ordering::Greater => current_link_ptr = &mut node.right,
-                Ordering::Equal => break,
+            match next {
+                Some(val) => merged.push(val),
+                None => break,
             }
         }
 
-        let node_to_delete = unsafe { (*current_link_ptr).take() }?;
-        let parent_of_deleted = node_to_delete.parent;
+        let len = merged.len();
+        self.root = Self::build_sorted(&mut merged.into_iter(), len);
+    }
 
-        let deleted_val = match (node_to_delete.left, node_to_delete.right) {
-            // Node has no children
-            (None, None) => {
-                // since the node where taken, it has None as its value, so the job is already done
-                node_to_delete.val
-            },
+    // the lazy set operations walk both trees in order at the same time, O(n + m) in total
+    pub fn union<'a>(&'a self, other: &'a Self) -> Union<'a, T, Iter<'a, T>> {
+        Union::new(self.iter(), other.iter())
+    }
 
-            // Node has one children
-            (Some(mut child), None) | (None, Some(mut child)) => {
-                child.parent = parent_of_deleted;
-                unsafe { *current_link_ptr = Some(child); }
-                node_to_delete.val
-            },
+    pub fn intersection<'a>(&'a self, other: &'a Self) -> Intersection<'a, T, Iter<'a, T>> {
+        Intersection::new(self.iter(), other.iter())
+    }
 
-            // Node has two children
-            (Some(mut left), Some(right)) => {
-                // GET THE MOST LEFT NODE FROM THE RIGHT TREE
-                let mut right_subtree_link = Some(right);
-                let mut successor_node = Self::detach_min(&mut right_subtree_link);
-                successor_node.parent = parent_of_deleted;
+    pub fn difference<'a>(&'a self, other: &'a Self) -> Difference<'a, T, Iter<'a, T>> {
+        Difference::new(self.iter(), other.iter())
+    }
+
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Self) -> SymmetricDifference<'a, T, Iter<'a, T>> {
+        SymmetricDifference::new(self.iter(), other.iter())
+    }
 
-                // UPDATE SUCCESSOR LEFT SIDE
-                left.parent = &mut *successor_node;
-                successor_node.left = Some(left);
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.difference(other).next().is_none()
+    }
 
-                // UPDATE SUCCESSOR RIGHT SIDE
-                if let Some(ref mut right_node) = right_subtree_link {
-                    right_node.parent = &mut *successor_node;
-                }
-                successor_node.right = right_subtree_link;
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
 
-                // ATTACH THE NEW SUBTREE TO THE REST OF THE TREE
-                unsafe { *current_link_ptr = Some(successor_node) };
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.intersection(other).next().is_none()
+    }
+}
 
-                node_to_delete.val
-            }
-        };
+impl<T: fmt::Display> BinarySearchTree<T> {
+    // Graphviz source with one node per value
+    pub fn to_dot(&self) -> String {
+        tree_fmt::to_dot("BinarySearchTree", self.root_node(), children, |node| node.val.to_string())
+    }
+}
+
+fn children<T>(node: &Node<T>) -> (Option<&Node<T>>, Option<&Node<T>>) {
+    (node.left(), node.right())
+}
 
-        Some(deleted_val)
+// draws the tree sideways, root on the left and bigger values above
+impl<T: fmt::Display> fmt::Display for BinarySearchTree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        tree_fmt::sideways(f, self.root_node(), children, |node| node.val.to_string())
     }
+}
 
-    fn detach_min(link: &mut Link<T>) -> Box<Node<T>> {
-        let mut current_link = link;
+impl<T> IntoIterator for BinarySearchTree<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
 
-        // here the first link must be Some
-        // search the node where the left is None (the minimum value)
-        while current_link.as_ref().unwrap().left.is_some() {
-            current_link = &mut current_link.as_mut().unwrap().left;
-        }
+    // the ends are found once the tree sits inside the iterator, through the iterator's own tree
+    fn into_iter(self) -> Self::IntoIter {
+        let mut iter = IntoIter { tree: self, front: ptr::null_mut(), back: ptr::null_mut() };
+        let tree = &mut iter.tree;
+        iter.front = tree.first();
+        iter.back = tree.last();
+        iter
+    }
+}
 
-        // remove from the tree
-        let mut min_node = current_link.take().unwrap();
+impl<'a, T> IntoIterator for &'a BinarySearchTree<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
 
-        // attach the right node to the place of the removed node
-        // if there is no right child take() will return None
-        *current_link = min_node.right.take();
+// There is no IntoIterator for &mut BinarySearchTree: handing out &mut T would let the caller
+// change the values that keep the tree ordered. CursorMut::replace_current is the checked way.
 
-        // if there is a right child, link the parent as the parent of the removed node
-        if let Some(right_child) = current_link.as_mut() {
-            right_child.parent = min_node.parent;
+impl<T: Ord> FromIterator<T> for BinarySearchTree<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = BinarySearchTree::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<T: Ord> Extend<T> for BinarySearchTree<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(val);
         }
-        
-        min_node
     }
 }
 
+impl<'a, T: Ord + Copy + 'a> Extend<&'a T> for BinarySearchTree<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+// also run under Miri, see the README
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -259,7 +992,7 @@ mod tests {
     #[test]
     fn test_new_empty() {
         let mut tree: BinarySearchTree<i32> = BinarySearchTree::new();
-        assert!(tree.root.is_none());
+        assert!(tree.root.is_null());
         assert_eq!(tree.min(), None);
         assert_eq!(tree.max(), None);
         assert_eq!(tree.delete(&1), None);
@@ -283,9 +1016,9 @@ mod tests {
         let mut tree = BinarySearchTree::new();
         tree.insert(10);
         tree.insert(10); // Should be ignored
-        assert_eq!(tree.root.as_ref().unwrap().val, 10);
-        assert!(tree.root.as_ref().unwrap().left.is_none());
-        assert!(tree.root.as_ref().unwrap().right.is_none());
+        assert_eq!(tree.root_node().unwrap().val, 10);
+        assert!(tree.root_node().unwrap().left.is_null());
+        assert!(tree.root_node().unwrap().right.is_null());
     }
 
     #[test]
@@ -338,8 +1071,8 @@ mod tests {
         tree.insert(4);
         tree.delete(&5); // Delete 5, leaving 4 as the left child of 7
         assert_eq!(tree.search(&5), None);
-        assert_eq!(tree.root.as_ref().unwrap().left.as_ref().unwrap().val, 7);
-        assert_eq!(tree.root.as_ref().unwrap().left.as_ref().unwrap().left.as_ref().unwrap().val, 4);
+        assert_eq!(tree.root_node().unwrap().left().unwrap().val, 7);
+        assert_eq!(tree.root_node().unwrap().left().unwrap().left().unwrap().val, 4);
     }
 
     #[test]
@@ -347,8 +1080,8 @@ mod tests {
         let mut tree = build_test_tree();
         tree.delete(&7);
         assert_eq!(tree.search(&7), None);
-        assert_eq!(tree.root.as_ref().unwrap().left.as_ref().unwrap().val, 5);
-        assert_eq!(tree.root.as_ref().unwrap().left.as_ref().unwrap().right, None);
+        assert_eq!(tree.root_node().unwrap().left().unwrap().val, 5);
+        assert!(tree.root_node().unwrap().left().unwrap().right().is_none());
     }
 
     #[test]
@@ -357,8 +1090,8 @@ mod tests {
         assert_eq!(tree.delete(&5), Some(5));
         assert_eq!(tree.search(&5), None);
         // 7 should be the successor
-        assert_eq!(tree.root.as_ref().unwrap().left.as_ref().unwrap().val, 7);
-        assert_eq!(tree.root.as_ref().unwrap().left.as_ref().unwrap().left.as_ref().unwrap().val, 3);
+        assert_eq!(tree.root_node().unwrap().left().unwrap().val, 7);
+        assert_eq!(tree.root_node().unwrap().left().unwrap().left().unwrap().val, 3);
     }
 
     #[test]
@@ -367,7 +1100,7 @@ mod tests {
         assert_eq!(tree.delete(&10), Some(10));
         assert_eq!(tree.search(&10), None);
         // 12 should be the successor
-        assert_eq!(tree.root.as_ref().unwrap().val, 12);
+        assert_eq!(tree.root_node().unwrap().val, 12);
         assert_eq!(tree.max(), Some(&18));
         assert_eq!(tree.min(), Some(&3));
     }
@@ -379,7 +1112,7 @@ mod tests {
         for val in values {
             assert!(tree.delete(&val).is_some());
         }
-        assert!(tree.root.is_none());
+        assert!(tree.root.is_null());
     }
 
     #[test]
@@ -397,7 +1130,7 @@ mod tests {
         // We need Ord/PartialEq/Eq for the tree.
         impl<'a> PartialEq for DropCounter<'a> { fn eq(&self, other: &Self) -> bool { self._id == other._id } }
         impl<'a> Eq for DropCounter<'a> {}
-        impl<'a> PartialOrd for DropCounter<'a> { fn partial_cmp(&self, other: &Self) -> Option<Ordering> { self._id.partial_cmp(&other._id) } }
+        impl<'a> PartialOrd for DropCounter<'a> { fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) } }
         impl<'a> Ord for DropCounter<'a> { fn cmp(&self, other: &Self) -> Ordering { self._id.cmp(&other._id) } }
 
         let drop_counter = RefCell::new(0);
@@ -411,4 +1144,504 @@ mod tests {
         // If our drop works, all 7 nodes should have been dropped.
         assert_eq!(*drop_counter.borrow(), 7);
     }
+
+    #[test]
+    fn test_range() {
+        let tree = build_test_tree();
+        fn collect<'a>(range: impl Iterator<Item = &'a i32>) -> Vec<i32> {
+            range.copied().collect()
+        }
+
+        assert_eq!(collect(tree.range(5..12)), vec![5, 7, 10]);
+        assert_eq!(collect(tree.range(5..=12)), vec![5, 7, 10, 12]);
+        assert_eq!(collect(tree.range(6..)), vec![7, 10, 12, 15, 18]);
+        assert_eq!(collect(tree.range(..7)), vec![3, 5]);
+        assert_eq!(collect(tree.range(..)), vec![3, 5, 7, 10, 12, 15, 18]);
+        assert_eq!(collect(tree.range((Bound::Excluded(3), Bound::Excluded(10)))), vec![5, 7]);
+        assert_eq!(tree.range(19..).count(), 0);
+        assert_eq!(tree.range(8..10).count(), 0);
+        assert_eq!(tree.range(10..10).count(), 0);
+    }
+
+    #[test]
+    fn test_range_empty_tree() {
+        let tree: BinarySearchTree<i32> = BinarySearchTree::new();
+        assert_eq!(tree.range(..).next(), None);
+    }
+
+    #[test]
+    fn test_range_matches_filter() {
+        let mut tree = BinarySearchTree::new();
+        for i in 0..200 {
+            tree.insert((i * 53) % 200);
+        }
+        for (lo, hi) in [(0, 200), (17, 18), (50, 120), (199, 250), (-5, 3)] {
+            let expected: Vec<i32> = tree.iter().copied().filter(|v| (lo..hi).contains(v)).collect();
+            assert_eq!(tree.range(lo..hi).copied().collect::<Vec<_>>(), expected);
+        }
+    }
+
+    #[test]
+    fn test_floor_and_ceiling() {
+        let tree = build_test_tree();
+        assert_eq!(tree.floor(&10), Some(&10));
+        assert_eq!(tree.floor(&11), Some(&10));
+        assert_eq!(tree.floor(&2), None);
+        assert_eq!(tree.floor(&100), Some(&18));
+
+        assert_eq!(tree.ceiling(&10), Some(&10));
+        assert_eq!(tree.ceiling(&11), Some(&12));
+        assert_eq!(tree.ceiling(&19), None);
+        assert_eq!(tree.ceiling(&0), Some(&3));
+    }
+
+    #[test]
+    fn test_predecessor_and_successor() {
+        let tree = build_test_tree();
+        assert_eq!(tree.predecessor(&10), Some(&7));
+        assert_eq!(tree.predecessor(&12), Some(&10));
+        assert_eq!(tree.predecessor(&3), None);
+        assert_eq!(tree.predecessor(&4), Some(&3));
+
+        assert_eq!(tree.successor(&10), Some(&12));
+        assert_eq!(tree.successor(&7), Some(&10));
+        assert_eq!(tree.successor(&18), None);
+        assert_eq!(tree.successor(&16), Some(&18));
+
+        let empty: BinarySearchTree<i32> = BinarySearchTree::new();
+        assert_eq!(empty.floor(&1), None);
+        assert_eq!(empty.successor(&1), None);
+    }
+
+    // every child must point back at its parent, the root at null
+    fn check_parents<T>(node: Option<&Node<T>>, parent: *const Node<T>) -> bool {
+        match node {
+            None => true,
+            Some(node) => ptr::eq(node.parent, parent)
+                && check_parents(node.left(), node)
+                && check_parents(node.right(), node),
+        }
+    }
+
+    #[test]
+    fn test_iter_rev() {
+        let tree = build_test_tree();
+        let values: Vec<_> = tree.iter().rev().copied().collect();
+        assert_eq!(values, vec![18, 15, 12, 10, 7, 5, 3]);
+
+        let empty: BinarySearchTree<i32> = BinarySearchTree::new();
+        assert_eq!(empty.iter().next_back(), None);
+    }
+
+    #[test]
+    fn test_iter_both_ends_meet() {
+        let tree = build_test_tree();
+        let mut iter = tree.iter();
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next_back(), Some(&18));
+        assert_eq!(iter.next_back(), Some(&15));
+        assert_eq!(iter.next(), Some(&5));
+        assert_eq!(iter.next(), Some(&7));
+        assert_eq!(iter.next_back(), Some(&12));
+        assert_eq!(iter.next(), Some(&10));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn test_parents_after_inserts_and_deletes() {
+        let mut tree = build_test_tree();
+        assert!(check_parents(tree.root_node(), ptr::null()));
+        for val in [10, 3, 15, 7] {
+            tree.delete(&val);
+            assert!(check_parents(tree.root_node(), ptr::null()));
+        }
+        tree.insert(11);
+        tree.insert(13);
+        assert!(check_parents(tree.root_node(), ptr::null()));
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![5, 11, 12, 13, 18]);
+    }
+
+    #[test]
+    fn test_cursor_walk_and_wrap() {
+        let tree = build_test_tree();
+        let mut cursor = tree.cursor_front();
+        assert_eq!(cursor.current(), Some(&3));
+        assert_eq!(cursor.peek_prev(), None);
+        cursor.move_prev();
+        // ghost position
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.peek_next(), Some(&3));
+        assert_eq!(cursor.peek_prev(), Some(&18));
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(&18));
+
+        let mut cursor = tree.cursor_at(&10);
+        assert_eq!(cursor.peek_prev(), Some(&7));
+        assert_eq!(cursor.peek_next(), Some(&12));
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&12));
+
+        assert_eq!(tree.cursor_at(&4).current(), None);
+        assert_eq!(tree.cursor_back().current(), Some(&18));
+    }
+
+    #[test]
+    fn test_cursor_remove_current() {
+        let mut tree = build_test_tree();
+        let mut cursor = tree.cursor_at_mut(&10);
+        assert_eq!(cursor.remove_current(), Some(10));
+        assert_eq!(cursor.current(), Some(&12));
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(&7));
+        assert_eq!(cursor.remove_current(), Some(7));
+        assert_eq!(cursor.current(), Some(&12));
+        assert!(check_parents(tree.root_node(), ptr::null()));
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![3, 5, 12, 15, 18]);
+
+        // remove everything walking from the front
+        let mut cursor = tree.cursor_front_mut();
+        while cursor.remove_current().is_some() {}
+        assert_eq!(cursor.current(), None);
+        assert!(tree.root.is_null());
+    }
+
+    #[test]
+    fn test_cursor_replace_current() {
+        let mut tree = build_test_tree();
+        let mut cursor = tree.cursor_at_mut(&10);
+        assert_eq!(cursor.replace_current(11), Ok(10));
+        assert_eq!(cursor.replace_current(12), Err(12));
+        assert_eq!(cursor.replace_current(6), Err(6));
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&18));
+        assert_eq!(cursor.replace_current(100), Ok(18));
+        cursor.move_next();
+        assert_eq!(cursor.replace_current(1), Err(1));
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![3, 5, 7, 11, 12, 15, 100]);
+        assert_eq!(tree.search(&11), Some(&11));
+    }
+
+    #[test]
+    fn test_into_iter_both_ends() {
+        let tree = build_test_tree();
+        let mut iter = tree.into_iter();
+        assert_eq!(iter.next(), Some(3));
+        assert_eq!(iter.next_back(), Some(18));
+        assert_eq!(iter.next(), Some(5));
+        assert_eq!(iter.collect::<Vec<_>>(), vec![7, 10, 12, 15]);
+
+        let reversed: Vec<_> = build_test_tree().into_iter().rev().collect();
+        assert_eq!(reversed, vec![18, 15, 12, 10, 7, 5, 3]);
+    }
+
+    #[test]
+    fn test_into_iter_moved_between_calls() {
+        let mut iter = build_test_tree().into_iter();
+        assert_eq!(iter.next(), Some(3));
+
+        // the iterator and the tree inside it move, the ends must still be good
+        let mut iters = vec![iter];
+        let mut iter = iters.pop().unwrap();
+        assert_eq!(iter.next_back(), Some(18));
+        let moved = Box::new(iter);
+        assert_eq!(moved.collect::<Vec<_>>(), vec![5, 7, 10, 12, 15]);
+    }
+
+    #[test]
+    fn test_into_iter_drops_rest() {
+        // every value holds a clone of alive, the count tells how many are still around
+        let alive = std::rc::Rc::new(());
+        let tree: BinarySearchTree<_> = (0..10).map(|id| (id, alive.clone())).collect();
+        assert_eq!(std::rc::Rc::strong_count(&alive), 11);
+
+        let mut iter = tree.into_iter();
+        assert_eq!(iter.next().map(|(id, _)| id), Some(0));
+        assert_eq!(std::rc::Rc::strong_count(&alive), 10);
+        drop(iter);
+        assert_eq!(std::rc::Rc::strong_count(&alive), 1);
+    }
+
+    #[test]
+    fn test_for_loop_on_reference() {
+        let tree = build_test_tree();
+        let mut sum = 0;
+        for val in &tree {
+            sum += val;
+        }
+        assert_eq!(sum, 70);
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut tree = build_test_tree();
+        let drained: Vec<_> = tree.drain().collect();
+        assert_eq!(drained, vec![3, 5, 7, 10, 12, 15, 18]);
+        assert!(tree.root.is_null());
+        tree.insert(1);
+        assert_eq!(tree.min(), Some(&1));
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut tree: BinarySearchTree<i32> = (0..20).collect();
+        tree.retain(|val| val % 3 == 0);
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![0, 3, 6, 9, 12, 15, 18]);
+        assert!(check_parents(tree.root_node(), ptr::null()));
+
+        tree.retain(|_| false);
+        assert!(tree.root.is_null());
+    }
+
+    #[test]
+    fn test_extract_if() {
+        let mut tree = build_test_tree();
+        let evens: Vec<_> = tree.extract_if(|val| val % 2 == 0).collect();
+        assert_eq!(evens, vec![10, 12, 18]);
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![3, 5, 7, 15]);
+
+        // stopping early leaves the rest alone
+        let mut tree = build_test_tree();
+        let first = tree.extract_if(|val| *val > 4).next();
+        assert_eq!(first, Some(5));
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![3, 7, 10, 12, 15, 18]);
+    }
+
+    #[test]
+    fn test_from_iter_and_extend() {
+        let mut tree: BinarySearchTree<i32> = vec![5, 1, 9, 1, 3].into_iter().collect();
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 3, 5, 9]);
+
+        tree.extend(vec![2, 9, 10]);
+        tree.extend(&[0, 4]);
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5, 9, 10]);
+        assert!(check_parents(tree.root_node(), ptr::null()));
+
+        let copy: Vec<i32> = tree.into_iter().collect();
+        assert_eq!(copy.len(), 8);
+    }
+
+    fn values(tree: &BinarySearchTree<i32>) -> Vec<i32> {
+        tree.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_split_off() {
+        let mut tree = build_test_tree();
+        let right = tree.split_off(&10);
+        assert_eq!(values(&tree), vec![3, 5, 7]);
+        assert_eq!(values(&right), vec![10, 12, 15, 18]);
+        assert!(check_parents(tree.root_node(), ptr::null()));
+        assert!(check_parents(right.root_node(), ptr::null()));
+
+        let mut tree = build_test_tree();
+        let right = tree.split_off(&8);
+        assert_eq!(values(&tree), vec![3, 5, 7]);
+        assert_eq!(values(&right), vec![10, 12, 15, 18]);
+        assert!(check_parents(tree.root_node(), ptr::null()));
+        assert!(check_parents(right.root_node(), ptr::null()));
+
+        let mut tree = build_test_tree();
+        assert!(tree.split_off(&100).root.is_null());
+        assert_eq!(values(&tree.split_off(&0)), vec![3, 5, 7, 10, 12, 15, 18]);
+        assert!(tree.root.is_null());
+    }
+
+    #[test]
+    fn test_split_off_then_cursor() {
+        let mut tree: BinarySearchTree<i32> = [50, 20, 80, 10, 30, 70, 90, 25, 35, 75].into_iter().collect();
+        let right = tree.split_off(&33);
+        let back: Vec<_> = tree.iter().rev().copied().collect();
+        assert_eq!(back, vec![30, 25, 20, 10]);
+        let back: Vec<_> = right.iter().rev().copied().collect();
+        assert_eq!(back, vec![90, 80, 75, 70, 50, 35]);
+    }
+
+    #[test]
+    fn test_append() {
+        let mut tree = build_test_tree();
+        let mut other: BinarySearchTree<i32> = [1, 10, 20].into_iter().collect();
+        tree.append(&mut other);
+        assert_eq!(values(&tree), vec![1, 3, 5, 7, 10, 12, 15, 18, 20]);
+        assert!(other.root.is_null());
+        assert!(check_parents(tree.root_node(), ptr::null()));
+        assert_eq!(tree.validate(), Ok(()));
+
+        // appending sorted values doesn't leave a vine behind
+        let mut tree: BinarySearchTree<i32> = BinarySearchTree::new();
+        for start in (0..1000).step_by(100) {
+            let mut other = BinarySearchTree::from_sorted_iter(start..start + 100);
+            tree.append(&mut other);
+        }
+        assert_eq!(depth(tree.root_node()), 10);
+    }
+
+    // only the key is compared, the label tells which tree a value came from
+    #[derive(Debug)]
+    struct Keyed(i32, &'static str);
+
+    impl PartialEq for Keyed {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    impl Eq for Keyed {}
+
+    impl PartialOrd for Keyed {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Keyed {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+
+    #[test]
+    fn test_append_keeps_other_on_duplicates() {
+        let mut tree: BinarySearchTree<_> = [Keyed(1, "self"), Keyed(2, "self")].into_iter().collect();
+        let mut other: BinarySearchTree<_> = [Keyed(2, "other"), Keyed(3, "other")].into_iter().collect();
+        tree.append(&mut other);
+        let labels: Vec<_> = tree.iter().map(|keyed| (keyed.0, keyed.1)).collect();
+        assert_eq!(labels, vec![(1, "self"), (2, "other"), (3, "other")]);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a: BinarySearchTree<i32> = [1, 3, 5, 7].into_iter().collect();
+        let b: BinarySearchTree<i32> = [3, 4, 5, 6].into_iter().collect();
+        assert_eq!(a.union(&b).copied().collect::<Vec<_>>(), vec![1, 3, 4, 5, 6, 7]);
+        assert_eq!(a.intersection(&b).copied().collect::<Vec<_>>(), vec![3, 5]);
+        assert_eq!(a.difference(&b).copied().collect::<Vec<_>>(), vec![1, 7]);
+        assert_eq!(a.symmetric_difference(&b).copied().collect::<Vec<_>>(), vec![1, 4, 6, 7]);
+    }
+
+    #[test]
+    fn test_subset_and_disjoint() {
+        let a: BinarySearchTree<i32> = [3, 5].into_iter().collect();
+        let b = build_test_tree();
+        let c: BinarySearchTree<i32> = [1, 2, 4].into_iter().collect();
+        let empty = BinarySearchTree::new();
+        assert!(a.is_subset(&b));
+        assert!(b.is_superset(&a));
+        assert!(!b.is_subset(&a));
+        assert!(empty.is_subset(&a));
+        assert!(b.is_disjoint(&c));
+        assert!(!a.is_disjoint(&b));
+        assert!(empty.is_disjoint(&empty));
+    }
+
+    fn depth<T>(node: Option<&Node<T>>) -> usize {
+        node.map_or(0, |node| 1 + depth(node.left()).max(depth(node.right())))
+    }
+
+    #[test]
+    fn test_from_sorted_iter() {
+        for len in [0, 1, 2, 3, 7, 8, 100, 1023, 1024] {
+            let tree = BinarySearchTree::from_sorted_iter(0..len);
+            assert_eq!(values(&tree), (0..len).collect::<Vec<_>>());
+            assert!(check_parents(tree.root_node(), ptr::null()));
+            // complete tree: ceil(log2(len + 1)) levels
+            let expected = (usize::BITS - (len as usize).leading_zeros()) as usize;
+            assert_eq!(depth(tree.root_node()), expected);
+        }
+    }
+
+    #[test]
+    fn test_from_sorted_iter_duplicates() {
+        let tree = BinarySearchTree::from_sorted_iter([1, 2, 2, 2, 3, 3]);
+        assert_eq!(tree.validate(), Ok(()));
+        assert_eq!(values(&tree), vec![1, 2, 3]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "increasing order")]
+    fn test_from_sorted_iter_unsorted() {
+        BinarySearchTree::from_sorted_iter([1, 3, 2]);
+    }
+
+    #[test]
+    fn test_rebalance_degenerate() {
+        // sorted inserts give a vine
+        let mut tree = BinarySearchTree::new();
+        for val in 0..1000 {
+            tree.insert(val);
+        }
+        assert_eq!(depth(tree.root_node()), 1000);
+
+        tree.rebalance();
+        assert_eq!(depth(tree.root_node()), 10);
+        assert_eq!(values(&tree), (0..1000).collect::<Vec<_>>());
+        assert!(check_parents(tree.root_node(), ptr::null()));
+        // the cursors walk with the fixed parents
+        assert_eq!(tree.iter().next_back(), Some(&999));
+        assert_eq!(tree.cursor_at(&500).peek_prev(), Some(&499));
+    }
+
+    #[test]
+    fn test_rebalance_small_trees() {
+        for len in 0..20 {
+            let mut tree: BinarySearchTree<i32> = (0..len).rev().collect();
+            tree.rebalance();
+            assert_eq!(values(&tree), (0..len).collect::<Vec<_>>());
+            assert!(check_parents(tree.root_node(), ptr::null()));
+            let expected = (u32::BITS - (len as u32).leading_zeros()) as usize;
+            assert_eq!(depth(tree.root_node()), expected);
+        }
+    }
+
+    #[test]
+    fn test_display_and_dot() {
+        let tree = build_test_tree();
+        let drawing = tree.to_string();
+        assert_eq!(drawing.lines().collect::<Vec<_>>(), vec![
+            "    /-- 18",
+            "/-- 15",
+            "|   \\-- 12",
+            "10",
+            "|   /-- 7",
+            "\\-- 5",
+            "    \\-- 3",
+        ]);
+
+        let dot = tree.to_dot();
+        assert!(dot.starts_with("digraph BinarySearchTree {"));
+        assert_eq!(dot.matches("->").count(), 6);
+        assert!(dot.contains("[label=\"12\"]"));
+        assert_eq!(BinarySearchTree::<i32>::new().to_string(), "");
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut tree = build_test_tree();
+        assert_eq!(tree.validate(), Ok(()));
+        tree.delete(&10);
+        tree.split_off(&15);
+        assert_eq!(tree.validate(), Ok(()));
+
+        // 5 stops pointing at the root
+        let tree = build_test_tree();
+        unsafe { (*(*tree.root).left).parent = ptr::null_mut() };
+        assert_eq!(tree.validate(), Err(InvariantError::WrongParent { index: 1 }));
+
+        // 7 becomes bigger than the root
+        let tree = build_test_tree();
+        unsafe { (*(*(*tree.root).left).right).val = 11 };
+        let error = tree.validate().unwrap_err();
+        assert_eq!(error, InvariantError::OutOfOrder { index: 3 });
+        assert_eq!(error.to_string(), "value at in-order position 3 is not greater than the one before it");
+
+        // a long vine doesn't overflow the stack
+        let mut tree = BinarySearchTree::new();
+        for val in 0..3000 {
+            tree.insert(val);
+        }
+        assert_eq!(tree.validate(), Ok(()));
+    }
 }
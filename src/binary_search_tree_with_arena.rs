@@ -0,0 +1,493 @@
+use std::cmp::Ordering;
+use std::fmt;
+use crate::arena::{Arena, Index};
+use crate::invariant::InvariantError;
+use crate::tree_fmt;
+
+struct Node<T> {
+    val: T,
+    left: Option<Index>,
+    right: Option<Index>,
+}
+
+// where a node hangs: the root or one side of its parent
+#[derive(Clone, Copy)]
+enum Hook {
+    Root,
+    Left(Index),
+    Right(Index),
+}
+
+// Binary search tree with its nodes in an Arena. Deleting relinks nodes instead of moving
+// values between them, so the index insert returns keeps pointing at the same value until that
+// value is deleted. Dropping the tree is one pass over the arena Vec, no walk down the links.
+pub struct BinarySearchTree<T> {
+    nodes: Arena<Node<T>>,
+    root: Option<Index>,
+}
+
+impl<T> Default for BinarySearchTree<T> {
+    fn default() -> Self {
+        BinarySearchTree { nodes: Arena::new(), root: None }
+    }
+}
+
+// in order iterator, the stack holds the nodes whose left side was already visited
+pub struct Iter<'a, T> {
+    nodes: &'a Arena<Node<T>>,
+    stack: Vec<&'a Node<T>>,
+    remaining: usize,
+}
+
+impl<'a, T> Iter<'a, T> {
+    fn push_left_spine(&mut self, mut link: Option<Index>) {
+        while let Some(index) = link {
+            let node = &self.nodes[index];
+            self.stack.push(node);
+            link = node.left;
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left_spine(node.right);
+        self.remaining -= 1;
+        Some(&node.val)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a BinarySearchTree<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for BinarySearchTree<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = BinarySearchTree::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<T: Ord> Extend<T> for BinarySearchTree<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(val);
+        }
+    }
+}
+
+impl<T> BinarySearchTree<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // room for capacity nodes before the arena has to grow
+    pub fn with_capacity(capacity: usize) -> Self {
+        BinarySearchTree { nodes: Arena::with_capacity(capacity), root: None }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        let mut iter = Iter { nodes: &self.nodes, stack: Vec::new(), remaining: self.nodes.len() };
+        iter.push_left_spine(self.root);
+        iter
+    }
+
+    // None once the value at index was deleted, even if its slot holds a new node.
+    // There is no get_mut, changing a value in place could break the order
+    pub fn get(&self, index: Index) -> Option<&T> {
+        self.nodes.get(index).map(|node| &node.val)
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        let mut node = &self.nodes[self.root?];
+        while let Some(left) = node.left {
+            node = &self.nodes[left];
+        }
+        Some(&node.val)
+    }
+
+    pub fn max(&self) -> Option<&T> {
+        let mut node = &self.nodes[self.root?];
+        while let Some(right) = node.right {
+            node = &self.nodes[right];
+        }
+        Some(&node.val)
+    }
+
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut level: Vec<Index> = self.root.into_iter().collect();
+        while !level.is_empty() {
+            height += 1;
+            level = level
+                .iter()
+                .flat_map(|&index| [self.nodes[index].left, self.nodes[index].right])
+                .flatten()
+                .collect();
+        }
+        height
+    }
+
+    // keeps the arena memory for the next inserts
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.root = None;
+    }
+
+    fn link(&self, hook: Hook) -> Option<Index> {
+        match hook {
+            Hook::Root => self.root,
+            Hook::Left(parent) => self.nodes[parent].left,
+            Hook::Right(parent) => self.nodes[parent].right,
+        }
+    }
+
+    fn set_link(&mut self, hook: Hook, child: Option<Index>) {
+        match hook {
+            Hook::Root => self.root = child,
+            Hook::Left(parent) => self.nodes[parent].left = child,
+            Hook::Right(parent) => self.nodes[parent].right = child,
+        }
+    }
+
+    // Takes the node at hook out and puts its subtrees back together. With two children the
+    // successor node is moved into its place, the values stay in their nodes.
+    fn remove_at(&mut self, hook: Hook) -> Option<T> {
+        let target = self.link(hook)?;
+        let replacement = match (self.nodes[target].left, self.nodes[target].right) {
+            (None, child) | (child, None) => child,
+            (Some(left), Some(right)) => {
+                let mut successor = right;
+                let mut successor_parent = None;
+                while let Some(smaller) = self.nodes[successor].left {
+                    successor_parent = Some(successor);
+                    successor = smaller;
+                }
+
+                // the successor has no left child, its right subtree takes its place
+                if let Some(parent) = successor_parent {
+                    self.nodes[parent].left = self.nodes[successor].right;
+                    self.nodes[successor].right = Some(right);
+                }
+                self.nodes[successor].left = Some(left);
+                Some(successor)
+            },
+        };
+
+        self.set_link(hook, replacement);
+        self.nodes.remove(target).map(|node| node.val)
+    }
+}
+
+impl<T: Ord> BinarySearchTree<T> {
+    // the hook holding val, or the empty one where it would be inserted
+    fn find_hook(&self, val: &T) -> Hook {
+        let mut hook = Hook::Root;
+        while let Some(index) = self.link(hook) {
+            hook = match val.cmp(&self.nodes[index].val) {
+                Ordering::Less => Hook::Left(index),
+                Ordering::Greater => Hook::Right(index),
+                Ordering::Equal => break,
+            };
+        }
+        hook
+    }
+
+    // index of the new node, None if the value was already in the tree
+    pub fn insert(&mut self, val: T) -> Option<Index> {
+        let hook = self.find_hook(&val);
+        if self.link(hook).is_some() {
+            return None;
+        }
+        let index = self.nodes.insert(Node { val, left: None, right: None });
+        self.set_link(hook, Some(index));
+        Some(index)
+    }
+
+    pub fn index_of(&self, val: &T) -> Option<Index> {
+        self.link(self.find_hook(val))
+    }
+
+    pub fn search(&self, val: &T) -> Option<&T> {
+        self.get(self.index_of(val)?)
+    }
+
+    pub fn contains(&self, val: &T) -> bool {
+        self.index_of(val).is_some()
+    }
+
+    pub fn delete(&mut self, val: &T) -> Option<T> {
+        let hook = self.find_hook(val);
+        self.remove_at(hook)
+    }
+
+    // the path down to the node is found by comparing its value, there are no parent links
+    pub fn remove(&mut self, index: Index) -> Option<T> {
+        let hook = self.find_hook(&self.nodes.get(index)?.val);
+        self.remove_at(hook)
+    }
+
+    // Values must be strictly increasing in order, every link must point at a live node and
+    // every node in the arena must be in the tree
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        self.nodes.validate()?;
+
+        let mut stack: Vec<&Node<T>> = Vec::new();
+        let mut previous: Option<&T> = None;
+        let mut index = 0;
+        let mut reached = 0;
+        let mut current = self.root;
+
+        loop {
+            while let Some(link) = current {
+                let Some(node) = self.nodes.get(link) else {
+                    return Err(InvariantError::Dangling { index });
+                };
+                // reaching more nodes than there are means some node is linked twice
+                reached += 1;
+                if reached > self.nodes.len() {
+                    return Err(InvariantError::WrongLength { stored: self.nodes.len(), actual: reached });
+                }
+                stack.push(node);
+                current = node.left;
+            }
+            let Some(node) = stack.pop() else {
+                break;
+            };
+
+            if previous.is_some_and(|previous| *previous >= node.val) {
+                return Err(InvariantError::OutOfOrder { index });
+            }
+            previous = Some(&node.val);
+            index += 1;
+            current = node.right;
+        }
+
+        if reached != self.nodes.len() {
+            return Err(InvariantError::WrongLength { stored: self.nodes.len(), actual: reached });
+        }
+        Ok(())
+    }
+}
+
+impl<T: fmt::Display> BinarySearchTree<T> {
+    // Graphviz source with one node per value
+    pub fn to_dot(&self) -> String {
+        tree_fmt::to_dot("BinarySearchTree", self.root_node(), |node| self.children(node), |node| node.val.to_string())
+    }
+
+    fn root_node(&self) -> Option<&Node<T>> {
+        self.root.map(|root| &self.nodes[root])
+    }
+
+    fn children<'a>(&'a self, node: &'a Node<T>) -> (Option<&'a Node<T>>, Option<&'a Node<T>>) {
+        (node.left.map(|left| &self.nodes[left]), node.right.map(|right| &self.nodes[right]))
+    }
+}
+
+// draws the tree sideways, root on the left and bigger values above
+impl<T: fmt::Display> fmt::Display for BinarySearchTree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        tree_fmt::sideways(f, self.root_node(), |node| self.children(node), |node| node.val.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xorshift::shuffled;
+
+    fn values<T: Copy>(tree: &BinarySearchTree<T>) -> Vec<T> {
+        tree.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_new_tree_is_empty() {
+        let tree: BinarySearchTree<i32> = BinarySearchTree::new();
+        assert!(tree.is_empty());
+        assert_eq!(tree.len(), 0);
+        assert_eq!(tree.height(), 0);
+        assert_eq!(tree.min(), None);
+        assert_eq!(tree.iter().next(), None);
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_insert_and_search() {
+        let mut tree = BinarySearchTree::new();
+        assert!(tree.insert(5).is_some());
+        assert!(tree.insert(3).is_some());
+        assert!(tree.insert(8).is_some());
+        assert_eq!(tree.insert(5), None);
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.height(), 2);
+        assert_eq!(tree.search(&3), Some(&3));
+        assert_eq!(tree.search(&4), None);
+        assert!(tree.contains(&8));
+        assert_eq!(tree.min(), Some(&3));
+        assert_eq!(tree.max(), Some(&8));
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_iter_in_order() {
+        let tree: BinarySearchTree<u64> = shuffled(100).into_iter().collect();
+        let mut iter = tree.iter();
+        assert_eq!(iter.len(), 100);
+        iter.next();
+        assert_eq!(iter.len(), 99);
+        assert_eq!(values(&tree), (0..100).collect::<Vec<_>>());
+        assert_eq!((&tree).into_iter().count(), 100);
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut tree: BinarySearchTree<i32> = [50, 30, 70, 20, 40, 60, 80, 35, 45, 65].into_iter().collect();
+        // leaf, one child and two children, with the successor deep and right under the node
+        assert_eq!(tree.delete(&20), Some(20));
+        assert_eq!(tree.delete(&60), Some(60));
+        assert_eq!(tree.delete(&30), Some(30));
+        assert_eq!(tree.delete(&70), Some(70));
+        assert_eq!(tree.delete(&50), Some(50));
+        assert_eq!(tree.delete(&50), None);
+        assert_eq!(values(&tree), vec![35, 40, 45, 65, 80]);
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_delete_everything_shuffled() {
+        let mut tree: BinarySearchTree<u64> = shuffled(200).into_iter().collect();
+        for (deleted, val) in shuffled(200).into_iter().rev().enumerate() {
+            assert_eq!(tree.delete(&val), Some(val));
+            assert_eq!(tree.len(), 199 - deleted);
+            if deleted % 20 == 0 {
+                assert_eq!(tree.validate(), Ok(()));
+            }
+        }
+        assert!(tree.is_empty());
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_indices_survive_deletes() {
+        let mut tree = BinarySearchTree::new();
+        let indices: Vec<(u64, Index)> = shuffled(50).into_iter().map(|val| (val, tree.insert(val).unwrap())).collect();
+
+        // deleting nodes with two children relinks their successors, it doesn't move values
+        for val in (0..50).step_by(3) {
+            tree.delete(&val);
+        }
+        tree.extend(100..120);
+        for (val, index) in indices {
+            if val % 3 == 0 {
+                assert_eq!(tree.get(index), None);
+            } else {
+                assert_eq!(tree.get(index), Some(&val));
+                assert_eq!(tree.index_of(&val), Some(index));
+            }
+        }
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_remove_by_index() {
+        let mut tree: BinarySearchTree<i32> = [4, 2, 6, 1, 3, 5, 7].into_iter().collect();
+        let root = tree.index_of(&4).unwrap();
+        assert_eq!(tree.remove(root), Some(4));
+        assert_eq!(tree.remove(root), None);
+        let leaf = tree.index_of(&1).unwrap();
+        assert_eq!(tree.remove(leaf), Some(1));
+        assert_eq!(values(&tree), vec![2, 3, 5, 6, 7]);
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut tree = BinarySearchTree::with_capacity(10);
+        let old = tree.insert(1).unwrap();
+        tree.extend(2..=10);
+        tree.clear();
+        assert!(tree.is_empty());
+        assert_eq!(tree.get(old), None);
+        tree.extend(1..=10);
+        assert_eq!(tree.nodes.capacity(), 10);
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_drop_values() {
+        use std::rc::Rc;
+        let alive = Rc::new(());
+        let mut tree = BinarySearchTree::new();
+        for val in 0..10 {
+            tree.insert((val, alive.clone()));
+        }
+        drop(tree.delete(&(0, alive.clone())));
+        assert_eq!(Rc::strong_count(&alive), 10);
+        drop(tree);
+        assert_eq!(Rc::strong_count(&alive), 1);
+    }
+
+    #[test]
+    fn test_draw() {
+        let tree: BinarySearchTree<i32> = [2, 1, 3].into_iter().collect();
+        let boxed: crate::binary_search_tree::BinarySearchTree<i32> = {
+            let mut tree = crate::binary_search_tree::BinarySearchTree::new();
+            [2, 1, 3].into_iter().for_each(|val| tree.insert(val));
+            tree
+        };
+        // same shape, same drawing as the box based tree
+        assert_eq!(tree.to_string(), boxed.to_string());
+        assert_eq!(tree.to_dot(), boxed.to_dot());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut tree: BinarySearchTree<i32> = [2, 1, 3].into_iter().collect();
+        assert_eq!(tree.validate(), Ok(()));
+        let root = tree.root.unwrap();
+        let left = tree.index_of(&1).unwrap();
+        let right = tree.index_of(&3).unwrap();
+
+        tree.nodes[root].left = Some(right);
+        tree.nodes[root].right = Some(left);
+        assert_eq!(tree.validate(), Err(InvariantError::OutOfOrder { index: 1 }));
+
+        // a link back up to the root
+        tree.nodes[root].left = Some(left);
+        tree.nodes[root].right = None;
+        tree.nodes[left].right = Some(root);
+        assert_eq!(tree.validate(), Err(InvariantError::WrongLength { stored: 3, actual: 4 }));
+
+        // a node left in the arena but not linked
+        tree.nodes[left].right = None;
+        assert_eq!(tree.validate(), Err(InvariantError::WrongLength { stored: 3, actual: 2 }));
+
+        tree.nodes.remove(right);
+        tree.nodes[root].right = Some(right);
+        assert_eq!(tree.validate(), Err(InvariantError::Dangling { index: 2 }));
+    }
+}
@@ -0,0 +1,108 @@
+// Pieces shared by the binary trees that own their nodes as Option<Box<Node>> links and keep no
+// parent pointers: the splay tree and the treap. Every tree keeps its own node type and
+// implements BoxNode for it, the iterator only sees the values and the children.
+
+pub(crate) type Link<N> = Option<Box<N>>;
+
+pub trait BoxNode<T> {
+    fn val(&self) -> &T;
+    fn left(&self) -> Option<&dyn BoxNode<T>>;
+    fn right(&self) -> Option<&dyn BoxNode<T>>;
+
+    // both children, leaving the node without any
+    fn take_children(&mut self) -> [Link<Self>; 2]
+    where
+        Self: Sized;
+}
+
+// Drops the nodes one by one with a stack. A long run of sorted inserts into a splay tree leaves
+// a path as deep as the tree is long, the recursive drop of the boxes would overflow the stack.
+pub(crate) fn drop_nodes<T, N: BoxNode<T>>(root: Link<N>) {
+    let mut stack: Vec<Box<N>> = root.into_iter().collect();
+    while let Some(mut node) = stack.pop() {
+        stack.extend(node.take_children().into_iter().flatten());
+    }
+}
+
+fn as_dyn<T, N: BoxNode<T>>(link: &Link<N>) -> Option<&dyn BoxNode<T>> {
+    link.as_deref().map(|node| node as &dyn BoxNode<T>)
+}
+
+// number of levels, 0 for an empty tree
+pub(crate) fn height<T, N: BoxNode<T>>(root: &Link<N>) -> usize {
+    let mut height = 0;
+    let mut level: Vec<&dyn BoxNode<T>> = as_dyn(root).into_iter().collect();
+    while !level.is_empty() {
+        height += 1;
+        level = level
+            .iter()
+            .flat_map(|node| [node.left(), node.right()])
+            .flatten()
+            .collect();
+    }
+    height
+}
+
+// In order iterator from both ends. Each end keeps the stack of nodes still to visit from its
+// side, the count of values left tells when they meet.
+pub struct Iter<'a, T> {
+    front: Vec<&'a dyn BoxNode<T>>,
+    back: Vec<&'a dyn BoxNode<T>>,
+    remaining: usize,
+}
+
+impl<'a, T> Iter<'a, T> {
+    pub(crate) fn new<N: BoxNode<T>>(root: &'a Link<N>, len: usize) -> Self {
+        let root = as_dyn(root);
+        let mut iter = Iter { front: Vec::new(), back: Vec::new(), remaining: len };
+        iter.push_left_spine(root);
+        iter.push_right_spine(root);
+        iter
+    }
+
+    fn push_left_spine(&mut self, mut link: Option<&'a dyn BoxNode<T>>) {
+        while let Some(node) = link {
+            self.front.push(node);
+            link = node.left();
+        }
+    }
+
+    fn push_right_spine(&mut self, mut link: Option<&'a dyn BoxNode<T>>) {
+        while let Some(node) = link {
+            self.back.push(node);
+            link = node.right();
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let node = self.front.pop()?;
+        self.push_left_spine(node.right());
+        Some(node.val())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let node = self.back.pop()?;
+        self.push_right_spine(node.left());
+        Some(node.val())
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
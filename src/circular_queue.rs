@@ -1,5 +1,6 @@
 use std::mem::MaybeUninit;
 use std::ptr::drop_in_place;
+use crate::invariant::InvariantError;
 
 #[derive(Default)]
 pub struct CircularQueue<T> {
@@ -125,6 +126,36 @@ impl<T> CircularQueue<T> {
         self.tail = len;
     }
 
+    // Checks that head and tail are inside the buffer and agree with the empty flag.
+    // head == tail means empty or full, only is_empty tells them apart, so it must not be set
+    // when they differ.
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        let capacity = self.capacity();
+        if capacity == 0 {
+            if !self.is_empty {
+                return Err(InvariantError::NoCapacity);
+            }
+            if self.head != 0 {
+                return Err(InvariantError::IndexOutOfBounds { field: "head", index: self.head, capacity });
+            }
+            if self.tail != 0 {
+                return Err(InvariantError::IndexOutOfBounds { field: "tail", index: self.tail, capacity });
+            }
+            return Ok(());
+        }
+
+        if self.head >= capacity {
+            return Err(InvariantError::IndexOutOfBounds { field: "head", index: self.head, capacity });
+        }
+        if self.tail >= capacity {
+            return Err(InvariantError::IndexOutOfBounds { field: "tail", index: self.tail, capacity });
+        }
+        if self.is_empty && self.head != self.tail {
+            return Err(InvariantError::EmptyFlagMismatch { head: self.head, tail: self.tail });
+        }
+        Ok(())
+    }
+
     pub fn enqueue(&mut self, val: T) {
         if self.is_full() || self.capacity() == 0 {
             self.grow();
@@ -354,5 +385,35 @@ mod tests {
         queue.enqueue(5);
         assert!(!queue.is_full());
     }
-}
 
+    #[test]
+    fn test_validate() {
+        let mut queue = CircularQueue::new();
+        assert_eq!(queue.validate(), Ok(()));
+        for i in 0..10 {
+            queue.enqueue(i);
+            assert_eq!(queue.validate(), Ok(()));
+            if i % 3 == 0 {
+                queue.dequeue();
+                assert_eq!(queue.validate(), Ok(()));
+            }
+        }
+        while queue.dequeue().is_some() {
+            assert_eq!(queue.validate(), Ok(()));
+        }
+
+        let mut queue = CircularQueue::new();
+        queue.enqueue(1);
+        queue.enqueue(2);
+        queue.is_empty = true;
+        assert_eq!(queue.validate(), Err(InvariantError::EmptyFlagMismatch { head: 0, tail: 2 }));
+        queue.is_empty = false;
+        queue.tail = 4;
+        assert_eq!(queue.validate(), Err(InvariantError::IndexOutOfBounds { field: "tail", index: 4, capacity: 4 }));
+        queue.tail = 2;
+
+        let mut empty: CircularQueue<i32> = CircularQueue::new();
+        empty.is_empty = false;
+        assert_eq!(empty.validate(), Err(InvariantError::NoCapacity));
+    }
+}
@@ -0,0 +1,774 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr;
+
+use crate::invariant::InvariantError;
+
+// Doubly linked list: every node knows the one before it too, so both ends push and pop in O(1)
+// and a node can be unlinked in O(1) once something points at it. That something is CursorMut,
+// which walks the list and edits it around its position, the way an LRU cache moves an entry to
+// the front or drops the last one.
+// Nodes are Boxes turned into raw pointers, null marks both ends.
+
+struct Node<T> {
+    val: T,
+    prev: *mut Node<T>,
+    next: *mut Node<T>,
+}
+
+pub struct LinkedList<T> {
+    head: *mut Node<T>,
+    tail: *mut Node<T>,
+    len: usize,
+    _marker: PhantomData<Box<Node<T>>>, // the list owns its nodes
+}
+
+// the nodes are only reachable through the list, so it can move between threads like a Vec
+unsafe impl<T: Send> Send for LinkedList<T> {}
+unsafe impl<T: Sync> Sync for LinkedList<T> {}
+
+impl<T> Default for LinkedList<T> {
+    fn default() -> Self {
+        LinkedList { head: ptr::null_mut(), tail: ptr::null_mut(), len: 0, _marker: PhantomData }
+    }
+}
+
+impl<T> Drop for LinkedList<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+// In order iterator from both ends, the count of values left tells when they meet
+pub struct Iter<'a, T> {
+    front: *const Node<T>,
+    back: *const Node<T>,
+    remaining: usize,
+    _marker: PhantomData<&'a T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let node = unsafe { &*self.front };
+        self.front = node.next;
+        Some(&node.val)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let node = unsafe { &*self.back };
+        self.back = node.prev;
+        Some(&node.val)
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+// Same as Iter but hands out mutable references, each node is visited once
+pub struct IterMut<'a, T> {
+    front: *mut Node<T>,
+    back: *mut Node<T>,
+    remaining: usize,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let node = unsafe { &mut *self.front };
+        self.front = node.next;
+        Some(&mut node.val)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let node = unsafe { &mut *self.back };
+        self.back = node.prev;
+        Some(&mut node.val)
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+// Owning iterator, pops from the ends
+pub struct IntoIter<T> {
+    list: LinkedList<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> LinkedList<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        unsafe { self.head.as_ref().map(|node| &node.val) }
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        unsafe { self.tail.as_ref().map(|node| &node.val) }
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.head.as_mut().map(|node| &mut node.val) }
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.tail.as_mut().map(|node| &mut node.val) }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { front: self.head, back: self.tail, remaining: self.len, _marker: PhantomData }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { front: self.head, back: self.tail, remaining: self.len, _marker: PhantomData }
+    }
+
+    pub fn push_front(&mut self, val: T) {
+        unsafe { self.link_between(val, ptr::null_mut(), self.head) };
+    }
+
+    pub fn push_back(&mut self, val: T) {
+        unsafe { self.link_between(val, self.tail, ptr::null_mut()) };
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.head.is_null() {
+            return None;
+        }
+        Some(unsafe { self.unlink(self.head) })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.tail.is_null() {
+            return None;
+        }
+        Some(unsafe { self.unlink(self.tail) })
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    // Moves every node of other to the back of this list, O(1)
+    pub fn append(&mut self, other: &mut Self) {
+        unsafe { self.splice_between(mem::take(other), self.tail, ptr::null_mut()) };
+    }
+
+    // Splits the list in two at the index, the returned list gets the nodes from at on.
+    // O(min(at, len - at)), the walk starts from the closer end.
+    // Panics if at > len.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len, "split_off index {at} is past the length {}", self.len);
+        if at == 0 {
+            return mem::take(self);
+        }
+        if at == self.len {
+            return Self::new();
+        }
+
+        let first = self.node_at(at);
+        unsafe {
+            let last_kept = (*first).prev;
+            (*last_kept).next = ptr::null_mut();
+            (*first).prev = ptr::null_mut();
+
+            let other = LinkedList { head: first, tail: self.tail, len: self.len - at, _marker: PhantomData };
+            self.tail = last_kept;
+            self.len = at;
+            other
+        }
+    }
+
+    // Cursor on the first value, or on the ghost position when the list is empty
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut { current: self.head, index: 0, list: self }
+    }
+
+    // Cursor on the last value, or on the ghost position when the list is empty
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        let index = self.len.saturating_sub(1);
+        CursorMut { current: self.tail, index, list: self }
+    }
+
+    // the node at index < len, walking from the closer end
+    fn node_at(&self, index: usize) -> *mut Node<T> {
+        unsafe {
+            if index < self.len / 2 {
+                let mut node = self.head;
+                for _ in 0..index {
+                    node = (*node).next;
+                }
+                node
+            } else {
+                let mut node = self.tail;
+                for _ in index..self.len - 1 {
+                    node = (*node).prev;
+                }
+                node
+            }
+        }
+    }
+
+    // Links a new node between prev and next, which must be neighbours in this list. A null prev
+    // means the front and a null next the back. Returns the new node.
+    unsafe fn link_between(&mut self, val: T, prev: *mut Node<T>, next: *mut Node<T>) -> *mut Node<T> {
+        let node = Box::into_raw(Box::new(Node { val, prev, next }));
+        unsafe {
+            match prev.as_mut() {
+                Some(prev) => prev.next = node,
+                None => self.head = node,
+            }
+            match next.as_mut() {
+                Some(next) => next.prev = node,
+                None => self.tail = node,
+            }
+        }
+        self.len += 1;
+        node
+    }
+
+    // Takes a node of this list out and frees it, returns its value
+    unsafe fn unlink(&mut self, node: *mut Node<T>) -> T {
+        let node = unsafe { Box::from_raw(node) };
+        unsafe {
+            match node.prev.as_mut() {
+                Some(prev) => prev.next = node.next,
+                None => self.head = node.next,
+            }
+            match node.next.as_mut() {
+                Some(next) => next.prev = node.prev,
+                None => self.tail = node.prev,
+            }
+        }
+        self.len -= 1;
+        node.val
+    }
+
+    // Moves all the nodes of other between prev and next, neighbours in this list like for
+    // link_between. Other is left empty, its nodes now belong to this list.
+    unsafe fn splice_between(&mut self, mut other: Self, prev: *mut Node<T>, next: *mut Node<T>) {
+        if other.is_empty() {
+            return;
+        }
+
+        unsafe {
+            (*other.head).prev = prev;
+            (*other.tail).next = next;
+            match prev.as_mut() {
+                Some(prev) => prev.next = other.head,
+                None => self.head = other.head,
+            }
+            match next.as_mut() {
+                Some(next) => next.prev = other.tail,
+                None => self.tail = other.tail,
+            }
+        }
+        self.len += other.len;
+        // nothing left for other's drop to free
+        other.head = ptr::null_mut();
+        other.tail = ptr::null_mut();
+        other.len = 0;
+    }
+
+    // Both directions must agree: every next and prev pointer, head and tail, and the stored
+    // length. index is the position of the node whose prev pointer is wrong.
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        let mut previous: *mut Node<T> = ptr::null_mut();
+        let mut current = self.head;
+        let mut count = 0;
+        while !current.is_null() {
+            if unsafe { (*current).prev } != previous {
+                return Err(InvariantError::WrongPrev { index: count });
+            }
+            previous = current;
+            current = unsafe { (*current).next };
+            count += 1;
+        }
+
+        if previous != self.tail {
+            return Err(InvariantError::WrongTail);
+        }
+        if count != self.len {
+            return Err(InvariantError::WrongLength { stored: self.len, actual: count });
+        }
+        Ok(())
+    }
+}
+
+impl<T: PartialEq> LinkedList<T> {
+    pub fn contains(&self, val: &T) -> bool {
+        self.iter().any(|v| v == val)
+    }
+}
+
+// A position in the list that can edit around itself in O(1).
+// Between the back and the front there is a ghost position with no value, moving past either
+// end lands there and moving again from it wraps to the other end, like the tree cursors.
+pub struct CursorMut<'a, T> {
+    current: *mut Node<T>, // null on the ghost position
+    index: usize,          // len on the ghost position
+    list: &'a mut LinkedList<T>,
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    // position of the current value, None on the ghost position
+    pub fn index(&self) -> Option<usize> {
+        (!self.current.is_null()).then_some(self.index)
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        unsafe { self.current.as_mut().map(|node| &mut node.val) }
+    }
+
+    pub fn move_next(&mut self) {
+        match unsafe { self.current.as_ref() } {
+            None => {
+                self.current = self.list.head;
+                self.index = 0;
+            },
+            Some(node) => {
+                self.current = node.next;
+                self.index += 1;
+            },
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        match unsafe { self.current.as_ref() } {
+            None => {
+                self.current = self.list.tail;
+                self.index = self.list.len.saturating_sub(1);
+            },
+            Some(node) => {
+                self.current = node.prev;
+                // stepping back from the front goes to the ghost position, which is at len
+                self.index = if self.current.is_null() { self.list.len } else { self.index - 1 };
+            },
+        }
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        let next = match unsafe { self.current.as_ref() } {
+            None => self.list.head,
+            Some(node) => node.next,
+        };
+        unsafe { next.as_mut().map(|node| &mut node.val) }
+    }
+
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        let prev = match unsafe { self.current.as_ref() } {
+            None => self.list.tail,
+            Some(node) => node.prev,
+        };
+        unsafe { prev.as_mut().map(|node| &mut node.val) }
+    }
+
+    // the neighbours a new node goes between, before or after the current position
+    fn before(&self) -> (*mut Node<T>, *mut Node<T>) {
+        match unsafe { self.current.as_ref() } {
+            None => (self.list.tail, ptr::null_mut()),
+            Some(node) => (node.prev, self.current),
+        }
+    }
+
+    fn after(&self) -> (*mut Node<T>, *mut Node<T>) {
+        match unsafe { self.current.as_ref() } {
+            None => (ptr::null_mut(), self.list.head),
+            Some(node) => (self.current, node.next),
+        }
+    }
+
+    // Inserts before the current value, on the ghost position that is the back of the list.
+    // The cursor stays on the same value.
+    pub fn insert_before(&mut self, val: T) {
+        let (prev, next) = self.before();
+        unsafe { self.list.link_between(val, prev, next) };
+        self.index += 1;
+    }
+
+    // Inserts after the current value, on the ghost position that is the front of the list
+    pub fn insert_after(&mut self, val: T) {
+        let (prev, next) = self.after();
+        unsafe { self.list.link_between(val, prev, next) };
+        if self.current.is_null() {
+            self.index = self.list.len;
+        }
+    }
+
+    // Takes the current value out in O(1) and moves to the next one, nothing to do on the
+    // ghost position
+    pub fn remove_current(&mut self) -> Option<T> {
+        if self.current.is_null() {
+            return None;
+        }
+        let node = self.current;
+        self.current = unsafe { (*node).next };
+        Some(unsafe { self.list.unlink(node) })
+    }
+
+    // Moves all of other in before the current value in O(1), at the back on the ghost position.
+    // The cursor stays on the same value.
+    pub fn splice_before(&mut self, other: LinkedList<T>) {
+        let (prev, next) = self.before();
+        self.index += other.len;
+        unsafe { self.list.splice_between(other, prev, next) };
+    }
+
+    // Moves all of other in after the current value in O(1), at the front on the ghost position
+    pub fn splice_after(&mut self, other: LinkedList<T>) {
+        let (prev, next) = self.after();
+        unsafe { self.list.splice_between(other, prev, next) };
+        if self.current.is_null() {
+            self.index = self.list.len;
+        }
+    }
+}
+
+impl<T> IntoIterator for LinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a LinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut LinkedList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = LinkedList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for LinkedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.push_back(val);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LinkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    fn values<T: Copy>(list: &LinkedList<T>) -> Vec<T> {
+        list.iter().copied().collect()
+    }
+
+    fn list_of(values: impl IntoIterator<Item = i32>) -> LinkedList<i32> {
+        values.into_iter().collect()
+    }
+
+    #[test]
+    fn test_push_and_pop_both_ends() {
+        let mut list = LinkedList::new();
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+        list.push_back(2);
+        list.push_front(1);
+        list.push_back(3);
+        assert_eq!(values(&list), vec![1, 2, 3]);
+        assert_eq!((list.front(), list.back()), (Some(&1), Some(&3)));
+        assert_eq!(list.len(), 3);
+
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_back(), Some(2));
+        assert!(list.is_empty());
+        assert!(list.head.is_null() && list.tail.is_null());
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_iterators() {
+        let mut list = list_of(1..=5);
+        assert!(list.iter().rev().copied().eq([5, 4, 3, 2, 1]));
+        let mut iter = list.iter();
+        assert_eq!((iter.next(), iter.next_back()), (Some(&1), Some(&5)));
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.collect::<Vec<_>>(), vec![&2, &3, &4]);
+
+        for val in &mut list {
+            *val *= 10;
+        }
+        *list.front_mut().unwrap() += 1;
+        *list.back_mut().unwrap() += 2;
+        assert_eq!(values(&list), vec![11, 20, 30, 40, 52]);
+        assert_eq!(list.into_iter().rev().collect::<Vec<_>>(), vec![52, 40, 30, 20, 11]);
+        assert_eq!(format!("{:?}", list_of(1..3)), "[1, 2]");
+    }
+
+    #[test]
+    fn test_cursor_moves_through_ghost() {
+        let mut list = list_of(1..=3);
+        let mut cursor = list.cursor_front_mut();
+        assert_eq!((cursor.index(), cursor.current().copied()), (Some(0), Some(1)));
+        cursor.move_prev();
+        assert_eq!((cursor.index(), cursor.current()), (None, None));
+        assert_eq!(cursor.peek_next().copied(), Some(1));
+        assert_eq!(cursor.peek_prev().copied(), Some(3));
+        cursor.move_prev();
+        assert_eq!((cursor.index(), cursor.current().copied()), (Some(2), Some(3)));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!((cursor.index(), cursor.current().copied()), (Some(0), Some(1)));
+
+        let mut empty: LinkedList<i32> = LinkedList::new();
+        let mut cursor = empty.cursor_back_mut();
+        assert_eq!(cursor.current(), None);
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+    }
+
+    #[test]
+    fn test_cursor_insert() {
+        let mut list = list_of([2, 4]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.insert_before(1);
+        cursor.insert_after(3);
+        assert_eq!((cursor.index(), cursor.current().copied()), (Some(1), Some(2)));
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next(); // ghost
+        cursor.insert_before(5); // back
+        cursor.insert_after(0); // front
+        assert_eq!(cursor.index(), None);
+        cursor.move_next();
+        assert_eq!((cursor.index(), cursor.current().copied()), (Some(0), Some(0)));
+        assert_eq!(values(&list), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_cursor_remove_current() {
+        let mut list = list_of(1..=5);
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(2));
+        assert_eq!((cursor.index(), cursor.current().copied()), (Some(1), Some(3)));
+
+        let mut cursor = list.cursor_back_mut();
+        assert_eq!(cursor.remove_current(), Some(5));
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.remove_current(), None);
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(values(&list), vec![3, 4]);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_cursor_splice() {
+        let mut list = list_of([1, 5]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.splice_after(list_of(2..=4));
+        assert_eq!(cursor.index(), Some(0));
+        cursor.splice_before(LinkedList::new());
+        cursor.splice_before(list_of([-1, 0]));
+        assert_eq!((cursor.index(), cursor.current().copied()), (Some(2), Some(1)));
+        cursor.move_prev();
+        assert_eq!(cursor.current().copied(), Some(0));
+
+        let mut cursor = list.cursor_back_mut();
+        cursor.move_next(); // ghost
+        cursor.splice_before(list_of([6, 7]));
+        cursor.splice_after(list_of([-3, -2]));
+        assert_eq!(cursor.index(), None);
+        assert_eq!(values(&list), vec![-3, -2, -1, 0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(list.len(), 11);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_split_off() {
+        for at in 0..=6 {
+            let mut list = list_of(0..6);
+            let tail = list.split_off(at);
+            assert_eq!(values(&list), (0..at as i32).collect::<Vec<_>>());
+            assert_eq!(values(&tail), (at as i32..6).collect::<Vec<_>>());
+            assert_eq!(list.validate(), Ok(()));
+            assert_eq!(tail.validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "past the length")]
+    fn test_split_off_past_the_end() {
+        list_of(0..3).split_off(4);
+    }
+
+    #[test]
+    fn test_append() {
+        let mut list = list_of(0..3);
+        let mut other = list_of(3..5);
+        list.append(&mut other);
+        assert!(other.is_empty());
+        assert_eq!(other.validate(), Ok(()));
+        list.append(&mut other);
+        other.append(&mut list);
+        assert!(list.is_empty());
+        assert_eq!(values(&other), vec![0, 1, 2, 3, 4]);
+        assert_eq!(other.validate(), Ok(()));
+    }
+
+    // what the cursor is for: an LRU cache of capacity 3, most recent first
+    #[test]
+    fn test_lru_with_cursor() {
+        fn touch(cache: &mut LinkedList<i32>, key: i32) {
+            let mut cursor = cache.cursor_front_mut();
+            while let Some(&mut current) = cursor.current() {
+                if current == key {
+                    cursor.remove_current();
+                    break;
+                }
+                cursor.move_next();
+            }
+            cache.push_front(key);
+            if cache.len() > 3 {
+                cache.pop_back();
+            }
+        }
+
+        let mut cache = LinkedList::new();
+        for key in [1, 2, 3, 1, 4, 2] {
+            touch(&mut cache, key);
+        }
+        assert_eq!(values(&cache), vec![2, 4, 1]);
+    }
+
+    #[test]
+    fn test_drop_values() {
+        let drop_count = RefCell::new(0);
+
+        struct DropCounter<'a> {
+            count: &'a RefCell<i32>,
+        }
+
+        impl<'a> Drop for DropCounter<'a> {
+            fn drop(&mut self) {
+                *self.count.borrow_mut() += 1;
+            }
+        }
+
+        let mut list: LinkedList<DropCounter> = (0..6).map(|_| DropCounter { count: &drop_count }).collect();
+        drop(list.pop_back());
+        drop(list.cursor_front_mut().remove_current());
+        assert_eq!(*drop_count.borrow(), 2);
+        let tail = list.split_off(2);
+        drop(list);
+        assert_eq!(*drop_count.borrow(), 4);
+        let mut iter = tail.into_iter();
+        drop(iter.next());
+        assert_eq!(*drop_count.borrow(), 5);
+        drop(iter);
+        assert_eq!(*drop_count.borrow(), 6);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut list = list_of(0..4);
+        assert_eq!(list.validate(), Ok(()));
+
+        unsafe {
+            let second = (*list.head).next;
+            let prev = mem::replace(&mut (*second).prev, ptr::null_mut());
+            assert_eq!(list.validate(), Err(InvariantError::WrongPrev { index: 1 }));
+            (*second).prev = prev;
+        }
+
+        let tail = mem::replace(&mut list.tail, list.head);
+        assert_eq!(list.validate(), Err(InvariantError::WrongTail));
+        list.tail = tail;
+
+        list.len = 1;
+        assert_eq!(list.validate(), Err(InvariantError::WrongLength { stored: 1, actual: 4 }));
+        list.len = 4;
+    }
+}
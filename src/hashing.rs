@@ -1,79 +1,1455 @@
-#[derive(Clone)] // allow this to be cloned so can be initialized at new function
-struct KeyValuePair {
-    key: String, // canonical key
-    values: Vec<String>, // a list of anagrams
-}
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher, RandomState};
+use std::io::{self, BufRead};
+use crate::invariant::InvariantError;
 
+// Horner's Method over the bytes fed by the Hash trait.
+// A prime number multiplier helps to scramble better the input.
+// It is fast and deterministic, but anyone who knows it can craft keys that all collide,
+// so prefer RandomState (SipHash with a random seed per map) for user supplied input.
 #[derive(Default)]
-pub struct HashMap {
-    buckets: Vec<Vec<KeyValuePair>>,
+pub struct HornerHasher {
+    hash_value: u64,
 }
 
-impl HashMap {
-    fn get_alphabet_position(c: char) -> usize {
-        let lower = c.to_ascii_lowercase();
-        let position = (lower as u32) - ('a' as u32) + 1;
-        position as usize
+// hashing strategy to plug into HashMap::with_hasher
+pub type BuildHornerHasher = BuildHasherDefault<HornerHasher>;
+
+impl Hasher for HornerHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let prime_number: u64 = 31;
+        for &byte in bytes {
+            // wrapping so long keys don't overflow
+            self.hash_value = self.hash_value.wrapping_mul(prime_number).wrapping_add(byte as u64);
+        }
     }
 
-    fn create_canonical_key(word: &str) -> String {
-        let mut letters: Vec<char> = word.chars().collect();
-        letters.sort_unstable();
-        letters.into_iter().collect()
+    fn finish(&self) -> u64 {
+        self.hash_value
     }
+}
 
-    fn hash(&self, key: &str) -> usize {
-        let prime_number: usize = 31; // a prime number helps to scramble better the input
-        let mut hash_value: usize = 0;
-        for c in key.chars() {
-            let alphabet_position = Self::get_alphabet_position(c);
-            // Horner's Method
-            hash_value = (hash_value * prime_number) + alphabet_position;
-        }
-        
-        hash_value % self.buckets.len()
+struct KeyValuePair<K, V> {
+    key: K,
+    value: V,
+}
+
+// average number of pairs per bucket allowed before the table is rehashed
+const DEFAULT_MAX_LOAD_FACTOR: f64 = 0.75;
+
+// Separate chaining: every bucket holds the pairs whose key hashes to its index.
+// S decides how keys are hashed, by default a randomly seeded SipHash like std's HashMap.
+pub struct HashMap<K, V, S = RandomState> {
+    buckets: Vec<Vec<KeyValuePair<K, V>>>,
+    len: usize,
+    max_load_factor: f64,
+    hash_builder: S,
+}
+
+impl<K, V, S: Default> Default for HashMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(0, S::default())
     }
+}
+
+// How well a hashing strategy spread the current keys over the buckets
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollisionStats {
+    pub buckets: usize,
+    pub used_buckets: usize, // buckets with at least one pair
+    pub collisions: usize, // pairs that share a bucket with an earlier pair
+    pub longest_chain: usize,
+    pub average_chain: f64, // average length of the used buckets
+}
 
+fn empty_buckets<K, V>(size: usize) -> Vec<Vec<KeyValuePair<K, V>>> {
+    let mut buckets = Vec::with_capacity(size);
+    buckets.resize_with(size, Vec::new); // KeyValuePair is not Clone, so no vec! macro
+    buckets
+}
+
+impl<K, V> HashMap<K, V, RandomState> {
+    // size is the initial number of buckets, the table still grows when it gets too loaded
     pub fn new(size: usize) -> Self {
+        Self::with_hasher(size, RandomState::new())
+    }
+
+    // enough buckets to hold capacity pairs without rehashing
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K, V, S> HashMap<K, V, S> {
+    pub fn with_hasher(size: usize, hash_builder: S) -> Self {
         Self {
-            buckets: vec![Vec::new(); size],
+            buckets: empty_buckets(size),
+            len: 0,
+            max_load_factor: DEFAULT_MAX_LOAD_FACTOR,
+            hash_builder,
         }
     }
 
-    pub fn insert(&mut self, word: &str) {
-        let key = Self::create_canonical_key(word);
-        let bucket_index = self.hash(&key);
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        let mut map = Self::with_hasher(0, hash_builder);
+        map.buckets = empty_buckets(map.buckets_needed(capacity));
+        map
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    // how many pairs fit before the next rehash
+    pub fn capacity(&self) -> usize {
+        (self.buckets.len() as f64 * self.max_load_factor) as usize
+    }
+
+    pub fn load_factor(&self) -> f64 {
+        if self.buckets.is_empty() {
+            return 0.0;
+        }
+        self.len as f64 / self.buckets.len() as f64
+    }
+
+    pub fn max_load_factor(&self) -> f64 {
+        self.max_load_factor
+    }
+
+    fn buckets_needed(&self, len: usize) -> usize {
+        (len as f64 / self.max_load_factor).ceil() as usize
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.buckets
+            .iter()
+            .flat_map(|bucket| bucket.iter().map(|pair| (&pair.key, &pair.value)))
+    }
+
+    pub fn collision_stats(&self) -> CollisionStats {
+        let used_buckets = self.buckets.iter().filter(|bucket| !bucket.is_empty()).count();
+        let longest_chain = self.buckets.iter().map(Vec::len).max().unwrap_or(0);
+        let average_chain = if used_buckets == 0 {
+            0.0
+        } else {
+            self.len as f64 / used_buckets as f64
+        };
+
+        CollisionStats {
+            buckets: self.buckets.len(),
+            used_buckets,
+            collisions: self.len - used_buckets,
+            longest_chain,
+            average_chain,
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> HashMap<K, V, S> {
+    // must never be called with zero buckets
+    fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        (self.hash_builder.hash_one(key) % self.buckets.len() as u64) as usize
+    }
+
+    // move every pair to a new table with bucket_count buckets
+    fn rehash(&mut self, bucket_count: usize) {
+        let old_buckets = std::mem::replace(&mut self.buckets, empty_buckets(bucket_count));
+        for pair in old_buckets.into_iter().flatten() {
+            let bucket_index = self.hash(&pair.key);
+            self.buckets[bucket_index].push(pair);
+        }
+    }
+
+    // the load factor must be positive, a bigger one means longer chains but fewer buckets
+    pub fn set_max_load_factor(&mut self, load_factor: f64) -> Result<(), &'static str> {
+        if !(load_factor > 0.0 && load_factor.is_finite()) {
+            return Err("Load factor must be a positive number.");
+        }
+        self.max_load_factor = load_factor;
+        if self.load_factor() > load_factor {
+            self.rehash(self.buckets_needed(self.len));
+        }
+        Ok(())
+    }
+
+    // make room for at least additional more pairs without rehashing
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.buckets_needed(self.len + additional);
+        if needed > self.buckets.len() {
+            self.rehash(needed);
+        }
+    }
+
+    // use the fewest buckets that keep the load factor under the limit
+    pub fn shrink_to_fit(&mut self) {
+        let needed = self.buckets_needed(self.len);
+        if needed < self.buckets.len() {
+            self.rehash(needed);
+        }
+    }
+
+    // Checks that len counts the pairs, the load factor is under the limit, and every pair sits
+    // in the bucket its key hashes to with no key repeated
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        let actual = self.buckets.iter().map(Vec::len).sum();
+        if actual != self.len {
+            return Err(InvariantError::WrongLength { stored: self.len, actual });
+        }
+        if self.len as f64 > self.buckets.len() as f64 * self.max_load_factor {
+            return Err(InvariantError::Overloaded { len: self.len, buckets: self.buckets.len() });
+        }
+
+        for (index, bucket) in self.buckets.iter().enumerate() {
+            for (position, pair) in bucket.iter().enumerate() {
+                let expected = self.hash(&pair.key);
+                if expected != index {
+                    return Err(InvariantError::WrongBucket { index, expected });
+                }
+                if bucket[..position].iter().any(|other| other.key == pair.key) {
+                    return Err(InvariantError::DuplicateKey { index });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // returns the old value if the key was already present
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if self.buckets.is_empty() {
+            self.rehash(1);
+        }
+        let mut bucket_index = self.hash(&key);
 
         // Search inside the corresponding bucket
-        for pairs in self.buckets[bucket_index].iter_mut() {
-            // If find a corresponding key, insert inside the anagram list
-            if pairs.key == key {
-                pairs.values.push(String::from(word));
-                return
+        for pair in self.buckets[bucket_index].iter_mut() {
+            if pair.key == key {
+                return Some(std::mem::replace(&mut pair.value, value));
             }
         }
 
-        // If didnt find, create a new group and insert inside the corresponding bucket
-        let new_pair = KeyValuePair {
-            key,
-            values: vec![String::from(word)],
+        // If the new pair overloads the table, double the buckets before inserting
+        if (self.len + 1) as f64 > self.buckets.len() as f64 * self.max_load_factor {
+            let bucket_count = (self.buckets.len() * 2).max(self.buckets_needed(self.len + 1));
+            self.rehash(bucket_count);
+            bucket_index = self.hash(&key);
+        }
+
+        // If didnt find, insert inside the corresponding bucket
+        self.buckets[bucket_index].push(KeyValuePair { key, value });
+        self.len += 1;
+        None
+    }
+
+    // K: Borrow<Q> allows a HashMap<String, _> to be searched with a &str
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.len == 0 {
+            return None;
+        }
+        let bucket_index = self.hash(key);
+        self.buckets[bucket_index]
+            .iter()
+            .find(|pair| pair.key.borrow() == key)
+            .map(|pair| &pair.value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.len == 0 {
+            return None;
+        }
+        let bucket_index = self.hash(key);
+        self.buckets[bucket_index]
+            .iter_mut()
+            .find(|pair| pair.key.borrow() == key)
+            .map(|pair| &mut pair.value)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.len == 0 {
+            return None;
+        }
+        let bucket_index = self.hash(key);
+        let bucket = &mut self.buckets[bucket_index];
+        let position = bucket.iter().position(|pair| pair.key.borrow() == key)?;
+
+        // order inside a bucket doesn't matter, so swap_remove is fine
+        let pair = bucket.swap_remove(position);
+        self.len -= 1;
+        Some(pair.value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+}
+
+// Pair stored inline in the open addressing table, the full hash is kept to avoid
+// rehashing keys when the table grows and to skip most key comparisons
+struct Slot<K, V> {
+    hash: u64,
+    key: K,
+    value: V,
+}
+
+// must stay below 1 so there is always an empty slot to stop a probe
+const DEFAULT_MAX_PROBE_LOAD_FACTOR: f64 = 0.875;
+
+// Open addressing: all the pairs live in a single Vec, a key that collides is placed
+// in the next free slot (linear probing).
+// Robin Hood: while probing, a pair that is far from its home slot steals the place of
+// a pair that is closer to its own home, so probe lengths stay short and even.
+// Deletion shifts the following pairs one slot back instead of leaving tombstones.
+pub struct OpenAddressingMap<K, V, S = RandomState> {
+    slots: Vec<Option<Slot<K, V>>>,
+    len: usize,
+    max_load_factor: f64,
+    hash_builder: S,
+}
+
+impl<K, V, S: Default> Default for OpenAddressingMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(0, S::default())
+    }
+}
+
+fn empty_slots<K, V>(size: usize) -> Vec<Option<Slot<K, V>>> {
+    let mut slots = Vec::with_capacity(size);
+    slots.resize_with(size, || None);
+    slots
+}
+
+impl<K, V> OpenAddressingMap<K, V, RandomState> {
+    // size is the initial number of slots, the table still grows when it gets too loaded
+    pub fn new(size: usize) -> Self {
+        Self::with_hasher(size, RandomState::new())
+    }
+
+    // enough slots to hold capacity pairs without rehashing
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K, V, S> OpenAddressingMap<K, V, S> {
+    pub fn with_hasher(size: usize, hash_builder: S) -> Self {
+        Self {
+            slots: empty_slots(size),
+            len: 0,
+            max_load_factor: DEFAULT_MAX_PROBE_LOAD_FACTOR,
+            hash_builder,
+        }
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        let mut map = Self::with_hasher(0, hash_builder);
+        map.slots = empty_slots(map.slots_needed(capacity));
+        map
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // every slot is a bucket that holds at most one pair
+    pub fn bucket_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    // how many pairs fit before the next rehash
+    pub fn capacity(&self) -> usize {
+        (self.slots.len() as f64 * self.max_load_factor) as usize
+    }
+
+    pub fn load_factor(&self) -> f64 {
+        if self.slots.is_empty() {
+            return 0.0;
+        }
+        self.len as f64 / self.slots.len() as f64
+    }
+
+    pub fn max_load_factor(&self) -> f64 {
+        self.max_load_factor
+    }
+
+    fn slots_needed(&self, len: usize) -> usize {
+        (len as f64 / self.max_load_factor).ceil() as usize
+    }
+
+    fn home(&self, hash: u64) -> usize {
+        (hash % self.slots.len() as u64) as usize
+    }
+
+    // how many slots away from its home a pair stored at index is
+    fn probe_distance(&self, hash: u64, index: usize) -> usize {
+        let home = self.home(hash);
+        if index >= home {
+            index - home
+        } else {
+            index + self.slots.len() - home
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.slots
+            .iter()
+            .flatten()
+            .map(|slot| (&slot.key, &slot.value))
+    }
+
+    // pairs are grouped by their home slot, so the numbers compare with the chained HashMap
+    pub fn collision_stats(&self) -> CollisionStats {
+        let mut chains = vec![0; self.slots.len()];
+        for slot in self.slots.iter().flatten() {
+            chains[self.home(slot.hash)] += 1;
+        }
+        let used_buckets = chains.iter().filter(|&&chain| chain > 0).count();
+        let longest_chain = chains.iter().copied().max().unwrap_or(0);
+        let average_chain = if used_buckets == 0 {
+            0.0
+        } else {
+            self.len as f64 / used_buckets as f64
         };
-        self.buckets[bucket_index].push(new_pair);
+
+        CollisionStats {
+            buckets: self.slots.len(),
+            used_buckets,
+            collisions: self.len - used_buckets,
+            longest_chain,
+            average_chain,
+        }
+    }
+
+    // the longest walk a lookup has to do from a home slot
+    pub fn max_probe_distance(&self) -> usize {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| slot.as_ref().map(|slot| self.probe_distance(slot.hash, index)))
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Robin Hood placement of a pair whose key is known not to be in the table
+    fn place(&mut self, mut slot: Slot<K, V>) {
+        let mut index = self.home(slot.hash);
+        let mut distance = 0;
+
+        loop {
+            let resident_distance = match &self.slots[index] {
+                None => {
+                    self.slots[index] = Some(slot);
+                    return;
+                }
+                Some(resident) => self.probe_distance(resident.hash, index),
+            };
+
+            // the resident is richer (closer to home), so it gives its place away
+            // and continues probing in place of the new pair
+            if resident_distance < distance {
+                std::mem::swap(self.slots[index].as_mut().unwrap(), &mut slot);
+                distance = resident_distance;
+            }
+
+            index = (index + 1) % self.slots.len();
+            distance += 1;
+        }
+    }
+
+    // move every pair to a new table with slot_count slots
+    fn rehash(&mut self, slot_count: usize) {
+        let old_slots = std::mem::replace(&mut self.slots, empty_slots(slot_count));
+        for slot in old_slots.into_iter().flatten() {
+            self.place(slot);
+        }
+    }
+
+    // the load factor must be between 0 and 1, a bigger one means longer probes but less memory
+    pub fn set_max_load_factor(&mut self, load_factor: f64) -> Result<(), &'static str> {
+        if !(load_factor > 0.0 && load_factor < 1.0) {
+            return Err("Load factor must be between 0 and 1.");
+        }
+        self.max_load_factor = load_factor;
+        if self.load_factor() > load_factor {
+            self.rehash(self.slots_needed(self.len));
+        }
+        Ok(())
+    }
+
+    // make room for at least additional more pairs without rehashing
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.slots_needed(self.len + additional);
+        if needed > self.slots.len() {
+            self.rehash(needed);
+        }
+    }
+
+    // use the fewest slots that keep the load factor under the limit
+    pub fn shrink_to_fit(&mut self) {
+        let needed = self.slots_needed(self.len);
+        if needed < self.slots.len() {
+            self.rehash(needed);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> OpenAddressingMap<K, V, S> {
+    // index of the slot holding key
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.len == 0 {
+            return None;
+        }
+        let hash = self.hash_builder.hash_one(key);
+        let mut index = self.home(hash);
+        let mut distance = 0;
+
+        // the load factor keeps at least one slot empty, so this always ends
+        while let Some(slot) = &self.slots[index] {
+            // Robin Hood keeps probes sorted by distance, if the key were here
+            // it would have taken the place of this richer pair
+            if self.probe_distance(slot.hash, index) < distance {
+                return None;
+            }
+            if slot.hash == hash && slot.key.borrow() == key {
+                return Some(index);
+            }
+            index = (index + 1) % self.slots.len();
+            distance += 1;
+        }
+        None
     }
 
-    pub fn get_anagram_groups(&self) -> Vec<& Vec<String>> {
-        let mut groups = Vec::new();
+    // Checks that len counts the pairs, the load factor is under the limit, the stored hashes
+    // match the keys, and a lookup of every key finds it in its own slot.
+    // That last one covers the Robin Hood order and repeated keys: find stops at the first match
+    // or at the first pair richer than the key would be.
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        let actual = self.slots.iter().flatten().count();
+        if actual != self.len {
+            return Err(InvariantError::WrongLength { stored: self.len, actual });
+        }
+        if self.len as f64 > self.slots.len() as f64 * self.max_load_factor {
+            return Err(InvariantError::Overloaded { len: self.len, buckets: self.slots.len() });
+        }
+
+        for (index, slot) in self.slots.iter().enumerate() {
+            let Some(slot) = slot else {
+                continue;
+            };
+            if self.hash_builder.hash_one(&slot.key) != slot.hash {
+                return Err(InvariantError::WrongHash { index });
+            }
+            match self.find(&slot.key) {
+                Some(found) if found == index => (),
+                Some(_) => return Err(InvariantError::DuplicateKey { index }),
+                None => return Err(InvariantError::Unreachable { index }),
+            }
+        }
+        Ok(())
+    }
+
+    // returns the old value if the key was already present
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(index) = self.find(&key) {
+            let slot = self.slots[index].as_mut().unwrap();
+            return Some(std::mem::replace(&mut slot.value, value));
+        }
 
-        for bucket in self.buckets.iter() {
-            for pair in bucket.iter() {
-                // if there is more then one word is a group of anagrams
-                if pair.values.len() > 1 {
-                    groups.push(&pair.values);
+        // If the new pair overloads the table, double the slots before inserting
+        if (self.len + 1) as f64 > self.slots.len() as f64 * self.max_load_factor {
+            let slot_count = (self.slots.len() * 2).max(self.slots_needed(self.len + 1));
+            self.rehash(slot_count);
+        }
+
+        let hash = self.hash_builder.hash_one(&key);
+        self.place(Slot { hash, key, value });
+        self.len += 1;
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(key)?;
+        self.slots[index].as_ref().map(|slot| &slot.value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(key)?;
+        self.slots[index].as_mut().map(|slot| &mut slot.value)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut index = self.find(key)?;
+        let removed = self.slots[index].take()?;
+        self.len -= 1;
+
+        // Backward shift: pull the following pairs one slot closer to their home
+        // until an empty slot or a pair that is already at home
+        loop {
+            let next = (index + 1) % self.slots.len();
+            match &self.slots[next] {
+                Some(slot) if self.probe_distance(slot.hash, next) > 0 => {
+                    self.slots[index] = self.slots[next].take();
+                    index = next;
                 }
+                _ => break,
             }
         }
 
+        Some(removed.value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+}
+
+// Decides which characters of a word count when looking for anagrams
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Normalization {
+    pub fold_case: bool, // "Listen" and "silent" are anagrams
+    pub ignore_whitespace: bool, // "dormitory" and "dirty room" are anagrams
+    pub ignore_punctuation: bool, // anything that is not a letter, digit or whitespace is dropped
+    pub graphemes: bool, // sort "e\u{301}" as one letter instead of splitting the accent off
+}
+
+impl Default for Normalization {
+    fn default() -> Self {
+        Self {
+            fold_case: true,
+            ignore_whitespace: false,
+            ignore_punctuation: false,
+            graphemes: false,
+        }
+    }
+}
+
+// Combining marks, variation selectors and the zero width joiner stick to the previous character.
+// This is only an approximation of unicode extended grapheme clusters, but it covers
+// decomposed accents and most emoji sequences without an external crate.
+fn extends_grapheme(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}' // combining diacritical marks
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{FE20}'..='\u{FE2F}'
+        | '\u{FE00}'..='\u{FE0F}' // variation selectors
+        | '\u{200D}' // zero width joiner
+    )
+}
+
+impl Normalization {
+    // every character counts, exactly like a plain sort of the chars
+    pub fn none() -> Self {
+        Self {
+            fold_case: false,
+            ignore_whitespace: false,
+            ignore_punctuation: false,
+            graphemes: false,
+        }
+    }
+
+    // fold case and drop everything that is not a letter or a digit
+    pub fn letters_only() -> Self {
+        Self {
+            fold_case: true,
+            ignore_whitespace: true,
+            ignore_punctuation: true,
+            graphemes: true,
+        }
+    }
+
+    fn keeps(&self, c: char) -> bool {
+        if c.is_whitespace() {
+            return !self.ignore_whitespace;
+        }
+        if !c.is_alphanumeric() && !extends_grapheme(c) {
+            return !self.ignore_punctuation;
+        }
+        true
+    }
+
+    // words with the same canonical key are anagrams of each other
+    pub fn canonical_key(&self, word: &str) -> String {
+        let mut chars: Vec<char> = Vec::with_capacity(word.len());
+        for c in word.chars().filter(|&c| self.keeps(c)) {
+            if self.fold_case {
+                chars.extend(c.to_lowercase());
+            } else {
+                chars.push(c);
+            }
+        }
+
+        if !self.graphemes {
+            chars.sort_unstable();
+            return chars.into_iter().collect();
+        }
+
+        let mut clusters: Vec<String> = Vec::new();
+        let mut joined = false; // the previous char was a zero width joiner
+        for c in chars {
+            match clusters.last_mut() {
+                Some(cluster) if joined || extends_grapheme(c) => cluster.push(c),
+                _ => clusters.push(String::from(c)),
+            }
+            joined = c == '\u{200D}';
+        }
+        clusters.sort_unstable();
+        clusters.concat()
+    }
+}
+
+// Groups words by their sorted letters, so every group is a list of anagrams
+#[derive(Default)]
+pub struct AnagramMap<S = RandomState> {
+    groups: HashMap<String, Vec<String>, S>, // canonical key -> list of anagrams
+    normalization: Normalization,
+}
+
+impl AnagramMap<RandomState> {
+    pub fn new(size: usize) -> Self {
+        Self::with_hasher(size, RandomState::new())
+    }
+
+    pub fn with_normalization(size: usize, normalization: Normalization) -> Self {
+        Self::with_normalization_and_hasher(size, normalization, RandomState::new())
+    }
+}
+
+impl<S> AnagramMap<S> {
+    pub fn with_hasher(size: usize, hash_builder: S) -> Self {
+        Self::with_normalization_and_hasher(size, Normalization::default(), hash_builder)
+    }
+
+    pub fn with_normalization_and_hasher(size: usize, normalization: Normalization, hash_builder: S) -> Self {
+        Self {
+            groups: HashMap::with_hasher(size, hash_builder),
+            normalization,
+        }
+    }
+
+    pub fn normalization(&self) -> Normalization {
+        self.normalization
+    }
+
+    pub fn collision_stats(&self) -> CollisionStats {
+        self.groups.collision_stats()
+    }
+}
+
+impl<S: BuildHasher> AnagramMap<S> {
+    pub fn insert(&mut self, word: &str) {
+        let key = self.normalization.canonical_key(word);
+
+        // If find a corresponding key, insert inside the anagram list
+        if let Some(values) = self.groups.get_mut(&key) {
+            values.push(String::from(word));
+            return;
+        }
+
+        // If didnt find, create a new group
+        self.groups.insert(key, vec![String::from(word)]);
+    }
+
+    // removes one occurrence of word, returns false if it was not in the map
+    pub fn remove_word(&mut self, word: &str) -> bool {
+        let key = self.normalization.canonical_key(word);
+        let Some(values) = self.groups.get_mut(&key) else {
+            return false;
+        };
+        let Some(position) = values.iter().position(|value| value == word) else {
+            return false;
+        };
+
+        values.remove(position); // keep the insertion order of the group
+        if values.is_empty() {
+            self.groups.remove(&key);
+        }
+        true
+    }
+
+    // every stored word made of the same letters as word, except word itself
+    pub fn anagrams_of(&self, word: &str) -> Vec<&str> {
+        let key = self.normalization.canonical_key(word);
+        self.groups
+            .get(&key)
+            .map_or(Vec::new(), |values| {
+                values
+                    .iter()
+                    .filter(|value| *value != word)
+                    .map(String::as_str)
+                    .collect()
+            })
+    }
+
+    // reads one word per line, blank lines are skipped. Returns how many words were inserted
+    pub fn insert_lines<R: BufRead>(&mut self, reader: R) -> io::Result<usize> {
+        let mut inserted = 0;
+        for line in reader.lines() {
+            let line = line?;
+            let word = line.trim();
+            if !word.is_empty() {
+                self.insert(word);
+                inserted += 1;
+            }
+        }
+        Ok(inserted)
+    }
+}
+
+impl<S> AnagramMap<S> {
+    // if there is more then one word is a group of anagrams
+    fn anagram_groups(&self) -> impl Iterator<Item = &Vec<String>> {
+        self.groups
+            .iter()
+            .map(|(_, values)| values)
+            .filter(|values| values.len() > 1)
+    }
+
+    pub fn get_anagram_groups(&self) -> Vec<&Vec<String>> {
+        self.anagram_groups().collect()
+    }
+
+    // number of groups with more than one word, the same groups get_anagram_groups returns
+    pub fn group_count(&self) -> usize {
+        self.anagram_groups().count()
+    }
+
+    // total number of words stored, counting words without anagrams
+    pub fn word_count(&self) -> usize {
+        self.groups.iter().map(|(_, values)| values.len()).sum()
+    }
+
+    // the n biggest groups of anagrams, biggest first.
+    // Groups of the same size are ordered by their words so the result is stable
+    pub fn largest_groups(&self, n: usize) -> Vec<&Vec<String>> {
+        let mut groups = self.get_anagram_groups();
+        groups.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        groups.truncate(n);
         groups
     }
 }
 
+impl<'a, S: BuildHasher> Extend<&'a str> for AnagramMap<S> {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        for word in iter {
+            self.insert(word);
+        }
+    }
+}
+
+impl<'a, S: BuildHasher + Default> FromIterator<&'a str> for AnagramMap<S> {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        let mut map = AnagramMap::with_hasher(0, S::default());
+        map.extend(iter);
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The same behaviour is expected from every hash table, so the tests are written once
+    // and expanded for each implementation
+    macro_rules! shared_map_tests {
+        ($module:ident, $map:ident) => {
+            mod $module {
+                use super::*;
+
+                #[test]
+                fn test_new_map_is_empty() {
+                    let map: $map<i32, i32> = $map::new(8);
+                    assert_eq!(map.len(), 0);
+                    assert!(map.is_empty());
+                    assert_eq!(map.get(&1), None);
+                    assert!(!map.contains_key(&1));
+                }
+
+                #[test]
+                fn test_insert_and_get() {
+                    let mut map = $map::new(8);
+                    assert_eq!(map.insert(1, "one"), None);
+                    assert_eq!(map.insert(2, "two"), None);
+
+                    assert_eq!(map.len(), 2);
+                    assert_eq!(map.get(&1), Some(&"one"));
+                    assert_eq!(map.get(&2), Some(&"two"));
+                    assert_eq!(map.get(&3), None);
+                }
+
+                #[test]
+                fn test_insert_existing_key_replaces_value() {
+                    let mut map = $map::new(8);
+                    map.insert("key", 1);
+                    assert_eq!(map.insert("key", 2), Some(1));
+                    assert_eq!(map.len(), 1);
+                    assert_eq!(map.get("key"), Some(&2));
+                }
+
+                #[test]
+                fn test_get_mut() {
+                    let mut map = $map::new(8);
+                    map.insert(String::from("a"), vec![1]);
+                    map.get_mut("a").unwrap().push(2);
+                    assert_eq!(map.get("a"), Some(&vec![1, 2]));
+                    assert_eq!(map.get_mut("b"), None);
+                }
+
+                #[test]
+                fn test_remove() {
+                    let mut map = $map::new(8);
+                    map.insert(1, 10);
+                    map.insert(2, 20);
+
+                    assert_eq!(map.remove(&1), Some(10));
+                    assert_eq!(map.remove(&1), None);
+                    assert_eq!(map.len(), 1);
+                    assert!(!map.contains_key(&1));
+                    assert!(map.contains_key(&2));
+                }
+
+                #[test]
+                fn test_collisions_in_single_bucket() {
+                    // a single bucket forces every key into the same chain
+                    let mut map = $map::new(1);
+                    for i in 0..50 {
+                        map.insert(i, i * 2);
+                    }
+                    assert_eq!(map.len(), 50);
+                    for i in 0..50 {
+                        assert_eq!(map.get(&i), Some(&(i * 2)));
+                    }
+                    assert_eq!(map.remove(&25), Some(50));
+                    assert_eq!(map.get(&25), None);
+                    assert_eq!(map.get(&26), Some(&52));
+                }
+
+                #[test]
+                fn test_new_with_zero_buckets() {
+                    let mut map = $map::new(0);
+                    assert_eq!(map.get(&1), None);
+                    assert_eq!(map.remove(&1), None);
+                    map.insert(1, 1);
+                    assert_eq!(map.get(&1), Some(&1));
+                    assert!(map.bucket_count() > 0);
+
+                    let mut map: $map<&str, i32> = $map::default();
+                    map.insert("a", 1);
+                    assert_eq!(map.get("a"), Some(&1));
+                }
+
+                #[test]
+                fn test_grows_to_respect_load_factor() {
+                    let mut map = $map::new(2);
+                    for i in 0..1000 {
+                        map.insert(i, i);
+                        assert!(map.load_factor() <= map.max_load_factor());
+                    }
+                    assert!(map.bucket_count() >= 1000);
+                    for i in 0..1000 {
+                        assert_eq!(map.get(&i), Some(&i));
+                    }
+                }
+
+                #[test]
+                fn test_with_capacity_does_not_rehash() {
+                    let mut map = $map::with_capacity(100);
+                    let buckets = map.bucket_count();
+                    assert!(map.capacity() >= 100);
+                    for i in 0..100 {
+                        map.insert(i, i);
+                    }
+                    assert_eq!(map.bucket_count(), buckets);
+                }
+
+                #[test]
+                fn test_reserve() {
+                    let mut map = $map::new(1);
+                    map.insert(0, 0);
+                    map.reserve(500);
+                    assert!(map.capacity() >= 501);
+                    let buckets = map.bucket_count();
+                    for i in 1..=500 {
+                        map.insert(i, i);
+                    }
+                    assert_eq!(map.bucket_count(), buckets);
+                    assert_eq!(map.get(&0), Some(&0));
+                }
+
+                #[test]
+                fn test_shrink_to_fit() {
+                    let mut map = $map::new(1024);
+                    for i in 0..10 {
+                        map.insert(i, i);
+                    }
+                    map.shrink_to_fit();
+                    assert!(map.bucket_count() < 1024);
+                    assert!(map.load_factor() <= map.max_load_factor());
+                    for i in 0..10 {
+                        assert_eq!(map.get(&i), Some(&i));
+                    }
+
+                    for i in 0..10 {
+                        map.remove(&i);
+                    }
+                    map.shrink_to_fit();
+                    assert_eq!(map.bucket_count(), 0);
+                    assert_eq!(map.load_factor(), 0.0);
+                    map.insert(1, 1);
+                    assert_eq!(map.get(&1), Some(&1));
+                }
+
+                #[test]
+                fn test_long_string_keys_do_not_overflow() {
+                    let mut map = $map::new(16);
+                    let key = "a".repeat(1000);
+                    map.insert(key.clone(), 1);
+                    assert_eq!(map.get(&key), Some(&1));
+                }
+
+                #[test]
+                fn test_map_with_horner_hasher() {
+                    let mut map = $map::with_hasher(8, BuildHornerHasher::default());
+                    for i in 0..100 {
+                        map.insert(i, i + 1);
+                    }
+                    for i in 0..100 {
+                        assert_eq!(map.get(&i), Some(&(i + 1)));
+                    }
+                    assert_eq!(map.remove(&50), Some(51));
+                }
+
+                #[test]
+                fn test_validate() {
+                    let mut map = $map::new(4);
+                    assert_eq!(map.validate(), Ok(()));
+                    for i in 0..200 {
+                        map.insert(i, i);
+                    }
+                    for i in (0..200).step_by(3) {
+                        map.remove(&i);
+                    }
+                    assert_eq!(map.validate(), Ok(()));
+                    map.shrink_to_fit();
+                    assert_eq!(map.validate(), Ok(()));
+
+                    map.len += 1;
+                    assert!(matches!(map.validate(), Err(InvariantError::WrongLength { .. })));
+                }
+
+                #[test]
+                fn test_random_state_maps_use_different_seeds() {
+                    let first: $map<&str, i32> = $map::new(8);
+                    let second: $map<&str, i32> = $map::new(8);
+                    assert_ne!(first.hasher().hash_one("word"), second.hasher().hash_one("word"));
+                }
+            }
+        };
+    }
+
+    // --- Tests shared by every map ---
+    shared_map_tests!(chained, HashMap);
+    shared_map_tests!(open_addressing, OpenAddressingMap);
+
+    // --- HashMap tests ---
+    #[test]
+    fn test_set_max_load_factor() {
+        let mut map = HashMap::new(4);
+        for i in 0..3 {
+            map.insert(i, i);
+        }
+        assert!(map.set_max_load_factor(0.0).is_err());
+        assert!(map.set_max_load_factor(f64::NAN).is_err());
+
+        map.set_max_load_factor(0.25).unwrap();
+        assert!(map.load_factor() <= 0.25);
+        assert!(map.bucket_count() >= 12);
+
+        map.set_max_load_factor(4.0).unwrap();
+        map.shrink_to_fit();
+        assert_eq!(map.bucket_count(), 1);
+        for i in 0..3 {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+    }
+
+    #[test]
+    fn test_validate_finds_misplaced_pairs() {
+        let mut map = HashMap::with_hasher(4, BuildHornerHasher::default());
+        map.insert(1, 1);
+        map.insert(2, 2);
+        // move every pair to the next bucket
+        map.buckets.rotate_right(1);
+        assert!(matches!(map.validate(), Err(InvariantError::WrongBucket { .. })));
+        map.buckets.rotate_left(1);
+
+        let index = map.hash(&1);
+        map.buckets[index].push(KeyValuePair { key: 1, value: 3 });
+        map.len += 1;
+        assert_eq!(map.validate(), Err(InvariantError::DuplicateKey { index }));
+    }
+
+    // --- OpenAddressingMap tests ---
+
+    // sends every key to the same home slot, the worst case for probing
+    #[derive(Default)]
+    struct ConstantHasher;
+
+    impl Hasher for ConstantHasher {
+        fn write(&mut self, _bytes: &[u8]) {}
+
+        fn finish(&self) -> u64 {
+            7
+        }
+    }
+
+    #[test]
+    fn test_open_addressing_set_max_load_factor() {
+        let mut map = OpenAddressingMap::new(4);
+        for i in 0..3 {
+            map.insert(i, i);
+        }
+        assert!(map.set_max_load_factor(0.0).is_err());
+        assert!(map.set_max_load_factor(1.0).is_err());
+        assert!(map.set_max_load_factor(f64::NAN).is_err());
+
+        map.set_max_load_factor(0.25).unwrap();
+        assert!(map.load_factor() <= 0.25);
+        assert!(map.bucket_count() >= 12);
+        for i in 0..3 {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+    }
+
+    #[test]
+    fn test_open_addressing_validate_probes() {
+        let mut map = OpenAddressingMap::with_hasher(8, BuildHasherDefault::<ConstantHasher>::default());
+        for i in 0..5 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.validate(), Ok(()));
+
+        // an empty slot in the middle of the run hides the pairs after it
+        let home = map.home(7);
+        let hole = (home + 2) % map.slots.len();
+        let taken = map.slots[hole].take();
+        map.len -= 1;
+        assert_eq!(map.validate(), Err(InvariantError::Unreachable { index: (hole + 1) % map.slots.len() }));
+        map.slots[hole] = taken;
+        map.len += 1;
+
+        map.slots[home].as_mut().unwrap().hash = 8;
+        assert_eq!(map.validate(), Err(InvariantError::WrongHash { index: home }));
+    }
+
+    #[test]
+    fn test_open_addressing_full_collisions() {
+        let mut map = OpenAddressingMap::with_hasher(4, BuildHasherDefault::<ConstantHasher>::default());
+        for i in 0..20 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.max_probe_distance(), 19);
+        assert_eq!(map.collision_stats().collisions, 19);
+
+        // removing from the middle of the run must not cut it in two
+        assert_eq!(map.remove(&5), Some(5));
+        assert_eq!(map.remove(&0), Some(0));
+        assert_eq!(map.max_probe_distance(), 17);
+        for i in (1..20).filter(|&i| i != 5) {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+        assert_eq!(map.get(&5), None);
+        assert_eq!(map.get(&0), None);
+    }
+
+    #[test]
+    fn test_open_addressing_remove_leaves_no_tombstones() {
+        let mut map = OpenAddressingMap::new(16);
+        for i in 0..1000 {
+            map.insert(i, i);
+        }
+        for i in 0..1000 {
+            assert_eq!(map.remove(&i), Some(i));
+        }
+        assert!(map.is_empty());
+        assert_eq!(map.iter().count(), 0);
+        assert_eq!(map.max_probe_distance(), 0);
+    }
+
+    #[test]
+    fn test_open_addressing_probe_distance_stays_small() {
+        let mut map = OpenAddressingMap::new(0);
+        for i in 0..10_000 {
+            map.insert(i, i);
+        }
+        for i in (0..10_000).step_by(3) {
+            map.remove(&i);
+        }
+        // Robin Hood keeps probe lengths logarithmic at this load factor
+        assert!(map.max_probe_distance() < 64, "probe distance {}", map.max_probe_distance());
+        for i in 0..10_000 {
+            assert_eq!(map.contains_key(&i), i % 3 != 0);
+        }
+    }
+
+    // --- Hashing strategy tests ---
+    #[test]
+    fn test_horner_hasher_is_deterministic() {
+        let builder = BuildHornerHasher::default();
+        assert_eq!(builder.hash_one("listen"), builder.hash_one("listen"));
+        assert_ne!(builder.hash_one("listen"), builder.hash_one("silent"));
+    }
+
+    #[test]
+    fn test_collision_stats() {
+        let empty: HashMap<i32, i32> = HashMap::new(4);
+        let stats = empty.collision_stats();
+        assert_eq!(stats.buckets, 4);
+        assert_eq!(stats.used_buckets, 0);
+        assert_eq!(stats.collisions, 0);
+        assert_eq!(stats.longest_chain, 0);
+        assert_eq!(stats.average_chain, 0.0);
+
+        // a huge load factor keeps all the keys in one bucket
+        let mut map = HashMap::new(1);
+        map.set_max_load_factor(100.0).unwrap();
+        for i in 0..10 {
+            map.insert(i, i);
+        }
+        let stats = map.collision_stats();
+        assert_eq!(stats.buckets, 1);
+        assert_eq!(stats.used_buckets, 1);
+        assert_eq!(stats.collisions, 9);
+        assert_eq!(stats.longest_chain, 10);
+        assert_eq!(stats.average_chain, 10.0);
+    }
+
+    #[test]
+    fn test_anagram_map_collision_stats_per_strategy() {
+        let words = ["listen", "silent", "enlist", "google", "rat", "tar", "art", "alone"];
+        let mut horner = AnagramMap::with_hasher(16, BuildHornerHasher::default());
+        let mut seeded = AnagramMap::new(16);
+        for word in words {
+            horner.insert(word);
+            seeded.insert(word);
+        }
+
+        // both see the same 4 groups, only their spread over the buckets may change
+        let horner_stats = horner.collision_stats();
+        let seeded_stats = seeded.collision_stats();
+        assert_eq!(horner_stats.used_buckets + horner_stats.collisions, 4);
+        assert_eq!(seeded_stats.used_buckets + seeded_stats.collisions, 4);
+        assert_eq!(horner.get_anagram_groups().len(), seeded.get_anagram_groups().len());
+    }
+
+    // --- AnagramMap tests ---
+    #[test]
+    fn test_anagram_groups() {
+        let mut map = AnagramMap::new(16);
+        for word in ["listen", "silent", "enlist", "google", "rat", "tar", "art", "alone"] {
+            map.insert(word);
+        }
+
+        let mut groups: Vec<Vec<String>> = map.get_anagram_groups().into_iter().cloned().collect();
+        for group in groups.iter_mut() {
+            group.sort();
+        }
+        groups.sort();
+
+        assert_eq!(groups, vec![
+            vec!["art", "rat", "tar"],
+            vec!["enlist", "listen", "silent"],
+        ]);
+    }
+
+    #[test]
+    fn test_anagram_groups_without_anagrams() {
+        let mut map = AnagramMap::new(4);
+        map.insert("one");
+        map.insert("two");
+        assert!(map.get_anagram_groups().is_empty());
+    }
+
+    #[test]
+    fn test_anagram_groups_ignore_case_by_default() {
+        let mut map = AnagramMap::new(8);
+        map.insert("Listen");
+        map.insert("silent");
+        assert_eq!(map.get_anagram_groups(), vec![&vec![String::from("Listen"), String::from("silent")]]);
+
+        let mut map = AnagramMap::with_normalization(8, Normalization::none());
+        map.insert("Listen");
+        map.insert("silent");
+        assert!(map.get_anagram_groups().is_empty());
+    }
+
+    #[test]
+    fn test_anagram_groups_letters_only() {
+        let mut map = AnagramMap::with_normalization(8, Normalization::letters_only());
+        map.insert("Dormitory");
+        map.insert("dirty room!");
+        map.insert("dirty-room");
+        assert_eq!(map.get_anagram_groups().len(), 1);
+        assert_eq!(map.get_anagram_groups()[0].len(), 3);
+    }
+
+    // --- Normalization tests ---
+    #[test]
+    fn test_canonical_key_does_not_panic_on_non_letters() {
+        let normalization = Normalization::default();
+        assert_eq!(normalization.canonical_key("a1!"), "!1a");
+        assert_eq!(normalization.canonical_key(""), "");
+        assert_eq!(normalization.canonical_key("123 ..."), " ...123");
+    }
+
+    #[test]
+    fn test_canonical_key_folds_non_ascii_case() {
+        let normalization = Normalization::default();
+        assert_eq!(normalization.canonical_key("Été"), normalization.canonical_key("étÉ"));
+        assert_eq!(normalization.canonical_key("ΛΟΓΟΣ"), normalization.canonical_key("λογοσ"));
+    }
+
+    #[test]
+    fn test_canonical_key_ignores_whitespace_and_punctuation() {
+        let normalization = Normalization {
+            ignore_whitespace: true,
+            ignore_punctuation: true,
+            ..Normalization::default()
+        };
+        assert_eq!(normalization.canonical_key("a gentleman"), normalization.canonical_key("elegant man"));
+        assert_eq!(normalization.canonical_key("it's"), normalization.canonical_key("tis"));
+        assert_eq!(normalization.canonical_key("¡olé!"), "loé");
+    }
+
+    #[test]
+    fn test_canonical_key_graphemes_keep_accents_attached() {
+        let chars = Normalization::default();
+        let graphemes = Normalization { graphemes: true, ..Normalization::default() };
+
+        // "e" + combining acute accent, the accent follows the letter it belongs to
+        let decomposed = "cafe\u{301}";
+        let rearranged = "e\u{301}fac";
+        assert_eq!(graphemes.canonical_key(decomposed), graphemes.canonical_key(rearranged));
+        assert_eq!(graphemes.canonical_key(decomposed), "ace\u{301}f");
+
+        // sorting raw chars moves the accent away from its letter
+        assert_eq!(chars.canonical_key(decomposed), "acef\u{301}");
+
+        // the accent moved to another letter is a different word
+        assert_ne!(graphemes.canonical_key("cafe\u{301}"), graphemes.canonical_key("ca\u{301}fe"));
+    }
+
+    #[test]
+    fn test_anagrams_of() {
+        let map: AnagramMap = ["listen", "silent", "enlist", "google"].into_iter().collect();
+        assert_eq!(map.anagrams_of("listen"), vec!["silent", "enlist"]);
+        assert_eq!(map.anagrams_of("tinsel"), vec!["listen", "silent", "enlist"]);
+        assert_eq!(map.anagrams_of("google"), Vec::<&str>::new());
+        assert_eq!(map.anagrams_of("missing"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_group_count_and_word_count() {
+        let mut map = AnagramMap::new(8);
+        assert_eq!(map.group_count(), 0);
+        assert_eq!(map.word_count(), 0);
+
+        map.extend(["rat", "tar", "art", "listen", "silent", "alone"]);
+        assert_eq!(map.group_count(), 2);
+        assert_eq!(map.word_count(), 6);
+    }
+
+    #[test]
+    fn test_largest_groups() {
+        let map: AnagramMap = ["listen", "silent", "rat", "tar", "art", "evil", "vile", "live", "veil", "alone"]
+            .into_iter()
+            .collect();
+
+        let largest = map.largest_groups(2);
+        assert_eq!(largest, vec![
+            &vec![String::from("evil"), String::from("vile"), String::from("live"), String::from("veil")],
+            &vec![String::from("rat"), String::from("tar"), String::from("art")],
+        ]);
+        assert_eq!(map.largest_groups(10).len(), 3);
+        assert!(map.largest_groups(0).is_empty());
+    }
+
+    #[test]
+    fn test_remove_word() {
+        let mut map: AnagramMap = ["rat", "tar", "alone"].into_iter().collect();
+        assert!(!map.remove_word("art"));
+        assert!(!map.remove_word("missing"));
+
+        assert!(map.remove_word("rat"));
+        assert_eq!(map.anagrams_of("rat"), vec!["tar"]);
+        assert_eq!(map.group_count(), 0);
+
+        assert!(map.remove_word("alone"));
+        assert!(!map.remove_word("alone"));
+        assert_eq!(map.word_count(), 1);
+    }
+
+    #[test]
+    fn test_insert_lines() {
+        let input = "listen\nsilent\n\n  enlist  \r\ngoogle\n";
+        let mut map = AnagramMap::new(8);
+        assert_eq!(map.insert_lines(input.as_bytes()).unwrap(), 4);
+        assert_eq!(map.anagrams_of("google"), Vec::<&str>::new());
+        assert_eq!(map.anagrams_of("tinsel"), vec!["listen", "silent", "enlist"]);
+    }
+}
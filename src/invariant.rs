@@ -0,0 +1,110 @@
+use std::error::Error;
+use std::fmt;
+
+// What validate() found broken in a structure.
+// Positions are in order indexes for the trees and slot or bucket indexes for the array based
+// structures, so reporting a violation doesn't need T: Debug.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvariantError {
+    // the value at this in order position is not greater than the one before it
+    OutOfOrder { index: usize },
+    // the parent pointer of the node at this in order position is not its parent
+    WrongParent { index: usize },
+    WrongHeight { index: usize, stored: usize, actual: usize },
+    // the subtree size kept for rank and select
+    WrongSize { index: usize, stored: usize, actual: usize },
+    Unbalanced { index: usize, balance_factor: i32 },
+    // red-black trees: the root must be black, a red node can't have a red child and every path
+    // down from a node must cross the same number of black nodes
+    RedRoot,
+    RedWithRedChild { index: usize },
+    BlackHeight { index: usize, left: usize, right: usize },
+    // B-trees: a node with too few or too many keys, an internal node that doesn't have one more
+    // child than keys and a leaf that is not as deep as the others. index is the in order
+    // position of the first value under the node
+    KeyCount { index: usize, len: usize, min: usize, max: usize },
+    ChildCount { index: usize, keys: usize, children: usize },
+    LeafDepth { index: usize, depth: usize, expected: usize },
+    // a treap node with a higher priority than its parent
+    Priority { index: usize },
+    // a heap parent that is greater than its child
+    HeapOrder { parent: usize, child: usize },
+    IndexOutOfBounds { field: &'static str, index: usize, capacity: usize },
+    // the empty flag is set but head and tail don't meet
+    EmptyFlagMismatch { head: usize, tail: usize },
+    // values are stored but there is no room for any
+    NoCapacity,
+    WrongLength { stored: usize, actual: usize },
+    // the tail pointer is not the last node
+    WrongTail,
+    // following next pointers comes back to a node already visited, index is the position where
+    // the loop starts
+    Cycle { index: usize },
+    // an arena link that points at a removed value, index is the position of the link: the head
+    // or root is 0 and the others count in list or in order position
+    Dangling { index: usize },
+    // an arena slot that is free but missing from the free list, or on the free list but not free
+    FreeList { index: usize },
+    // the prev pointer of the node at this position is not the node before it
+    WrongPrev { index: usize },
+    // the pair in this bucket hashes to another one
+    WrongBucket { index: usize, expected: usize },
+    DuplicateKey { index: usize },
+    // the hash kept in the slot is not the hash of its key
+    WrongHash { index: usize },
+    // a lookup starting at the home slot would stop before reaching this slot
+    Unreachable { index: usize },
+    Overloaded { len: usize, buckets: usize },
+}
+
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantError::OutOfOrder { index } => write!(f, "value at in-order position {index} is not greater than the one before it"),
+            InvariantError::WrongParent { index } => write!(f, "node {index} has a wrong parent pointer"),
+            InvariantError::WrongHeight { index, stored, actual } => {
+                write!(f, "node {index} stores height {stored} but its height is {actual}")
+            },
+            InvariantError::WrongSize { index, stored, actual } => {
+                write!(f, "node {index} stores size {stored} but its subtree has {actual} nodes")
+            },
+            InvariantError::Unbalanced { index, balance_factor } => {
+                write!(f, "node {index} has balance factor {balance_factor}")
+            },
+            InvariantError::RedRoot => write!(f, "the root is red"),
+            InvariantError::RedWithRedChild { index } => write!(f, "red node {index} has a red child"),
+            InvariantError::BlackHeight { index, left, right } => {
+                write!(f, "node {index} has {left} black nodes down the left and {right} down the right")
+            },
+            InvariantError::KeyCount { index, len, min, max } => {
+                write!(f, "node at {index} holds {len} keys, outside {min}..={max}")
+            },
+            InvariantError::ChildCount { index, keys, children } => {
+                write!(f, "node at {index} has {keys} keys but {children} children")
+            },
+            InvariantError::LeafDepth { index, depth, expected } => {
+                write!(f, "leaf at {index} is at depth {depth} but the other leaves are at {expected}")
+            },
+            InvariantError::Priority { index } => write!(f, "node {index} has a higher priority than its parent"),
+            InvariantError::HeapOrder { parent, child } => write!(f, "heap value {parent} is greater than its child {child}"),
+            InvariantError::IndexOutOfBounds { field, index, capacity } => {
+                write!(f, "{field} is {index} but the capacity is {capacity}")
+            },
+            InvariantError::EmptyFlagMismatch { head, tail } => write!(f, "marked empty but head {head} and tail {tail} differ"),
+            InvariantError::NoCapacity => write!(f, "holds values without any capacity"),
+            InvariantError::WrongLength { stored, actual } => write!(f, "stored length {stored} but holds {actual} values"),
+            InvariantError::Cycle { index } => write!(f, "the list loops back to node {index}"),
+            InvariantError::Dangling { index } => write!(f, "link {index} points at a removed value"),
+            InvariantError::FreeList { index } => write!(f, "slot {index} is misplaced on the free list"),
+            InvariantError::WrongTail => write!(f, "tail is not the last node"),
+            InvariantError::WrongPrev { index } => write!(f, "node {index} has a wrong prev pointer"),
+            InvariantError::WrongBucket { index, expected } => write!(f, "pair in bucket {index} belongs to bucket {expected}"),
+            InvariantError::DuplicateKey { index } => write!(f, "bucket {index} holds a repeated key"),
+            InvariantError::WrongHash { index } => write!(f, "slot {index} stores a hash that doesn't match its key"),
+            InvariantError::Unreachable { index } => write!(f, "slot {index} can't be reached from its home slot"),
+            InvariantError::Overloaded { len, buckets } => write!(f, "{len} pairs in {buckets} buckets is over the max load factor"),
+        }
+    }
+}
+
+impl Error for InvariantError {}
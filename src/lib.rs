@@ -1,5 +1,7 @@
 pub mod linked_list_with_box;
 pub mod linked_list_with_pointer;
+pub mod doubly_linked_list;
+pub mod linked_list_with_arena;
 
 pub mod stack_with_fixed_array;
 pub mod stack_with_vector;
@@ -10,5 +12,19 @@ pub mod circular_queue;
 
 pub mod binary_search_tree;
 pub mod avl_tree;
+pub mod avl_map;
+pub mod red_black_tree;
+pub mod b_tree;
+pub mod splay_tree;
+pub mod treap;
+pub mod binary_search_tree_with_arena;
+pub mod set_ops;
+mod tree_fmt;
+mod box_tree;
 
 pub mod hashing;
+
+pub mod arena;
+pub mod xorshift;
+
+pub mod invariant;
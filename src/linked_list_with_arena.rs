@@ -0,0 +1,416 @@
+use std::collections::HashMap;
+use crate::arena::{Arena, Index};
+use crate::invariant::InvariantError;
+
+struct Node<T> {
+    val: T,
+    next: Option<Index>,
+}
+
+// Singly linked list with its nodes in an Arena instead of one allocation per node.
+// The links are arena indices, so there is no unsafe, and the index a push returns keeps
+// pointing at that value until it is removed, whatever happens to the rest of the list.
+// No Drop impl is needed: the arena drops the values and frees the whole Vec at once.
+pub struct LinkedList<T> {
+    nodes: Arena<Node<T>>,
+    head: Option<Index>,
+    tail: Option<Index>,
+}
+
+impl<T> Default for LinkedList<T> {
+    fn default() -> Self {
+        LinkedList { nodes: Arena::new(), head: None, tail: None }
+    }
+}
+
+impl<T> From<Vec<T>> for LinkedList<T> {
+    fn from(value: Vec<T>) -> Self {
+        let mut list = LinkedList::with_capacity(value.len());
+        list.extend(value);
+        list
+    }
+}
+
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = LinkedList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for LinkedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.push_right(val);
+        }
+    }
+}
+
+pub struct Iter<'a, T> {
+    nodes: &'a Arena<Node<T>>,
+    next: Option<Index>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = &self.nodes[self.next?];
+        self.next = node.next;
+        self.remaining -= 1;
+        Some(&node.val)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a LinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> LinkedList<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // room for capacity nodes before the arena has to grow
+    pub fn with_capacity(capacity: usize) -> Self {
+        LinkedList { nodes: Arena::with_capacity(capacity), head: None, tail: None }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { nodes: &self.nodes, next: self.head, remaining: self.nodes.len() }
+    }
+
+    pub fn push_left(&mut self, val: T) -> Index {
+        let index = self.nodes.insert(Node { val, next: self.head });
+        self.head = Some(index);
+        if self.tail.is_none() {
+            self.tail = Some(index);
+        }
+        index
+    }
+
+    pub fn push_right(&mut self, val: T) -> Index {
+        let index = self.nodes.insert(Node { val, next: None });
+        match self.tail {
+            Some(tail) => self.nodes[tail].next = Some(index),
+            None => self.head = Some(index),
+        }
+        self.tail = Some(index);
+        index
+    }
+
+    // O(1) with the index of the node before, gives the value back if that node was removed
+    pub fn insert_after(&mut self, at: Index, val: T) -> Result<Index, T> {
+        let Some(next) = self.nodes.get(at).map(|node| node.next) else {
+            return Err(val);
+        };
+
+        let index = self.nodes.insert(Node { val, next });
+        self.nodes[at].next = Some(index);
+        if self.tail == Some(at) {
+            self.tail = Some(index);
+        }
+        Ok(index)
+    }
+
+    pub fn pop_left(&mut self) -> Option<T> {
+        let node = self.nodes.remove(self.head?)?;
+        self.head = node.next;
+        if self.head.is_none() {
+            self.tail = None;
+        }
+        Some(node.val)
+    }
+
+    // O(n): the list only links forward, so the node before has to be found by walking
+    pub fn remove(&mut self, index: Index) -> Option<T> {
+        if !self.nodes.contains(index) {
+            return None;
+        }
+        if self.head == Some(index) {
+            return self.pop_left();
+        }
+
+        let mut prev = self.head?;
+        while self.nodes[prev].next != Some(index) {
+            prev = self.nodes[prev].next?;
+        }
+        let node = self.nodes.remove(index)?;
+        self.nodes[prev].next = node.next;
+        if self.tail == Some(index) {
+            self.tail = Some(prev);
+        }
+        Some(node.val)
+    }
+
+    pub fn peek_left(&self) -> Option<&T> {
+        self.get(self.head?)
+    }
+
+    pub fn peek_right(&self) -> Option<&T> {
+        self.get(self.tail?)
+    }
+
+    // None once the value at index was removed, even if its slot holds a new node
+    pub fn get(&self, index: Index) -> Option<&T> {
+        self.nodes.get(index).map(|node| &node.val)
+    }
+
+    pub fn get_mut(&mut self, index: Index) -> Option<&mut T> {
+        self.nodes.get_mut(index).map(|node| &mut node.val)
+    }
+
+    // keeps the arena memory for the next pushes
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.head = None;
+        self.tail = None;
+    }
+
+    // Every link must point at a live node without looping, tail must be the last node and
+    // every node in the arena must be in the list
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        self.nodes.validate()?;
+
+        let mut positions = HashMap::new();
+        let mut last = None;
+        let mut current = self.head;
+        while let Some(index) = current {
+            if let Some(&start) = positions.get(&index) {
+                return Err(InvariantError::Cycle { index: start });
+            }
+            let Some(node) = self.nodes.get(index) else {
+                return Err(InvariantError::Dangling { index: positions.len() });
+            };
+            positions.insert(index, positions.len());
+            last = current;
+            current = node.next;
+        }
+
+        if last != self.tail {
+            return Err(InvariantError::WrongTail);
+        }
+        if positions.len() != self.nodes.len() {
+            return Err(InvariantError::WrongLength { stored: self.nodes.len(), actual: positions.len() });
+        }
+        Ok(())
+    }
+}
+
+impl<T: PartialEq> LinkedList<T> {
+    pub fn find(&self, val: &T) -> Option<&T> {
+        self.iter().find(|&v| v == val)
+    }
+
+    pub fn contains(&self, val: &T) -> bool {
+        self.iter().any(|v| v == val)
+    }
+
+    // index of the first node holding val
+    pub fn index_of(&self, val: &T) -> Option<Index> {
+        let mut current = self.head;
+        while let Some(index) = current {
+            let node = &self.nodes[index];
+            if node.val == *val {
+                return Some(index);
+            }
+            current = node.next;
+        }
+        None
+    }
+
+    pub fn delete(&mut self, val: &T) -> Option<T> {
+        let index = self.index_of(val)?;
+        self.remove(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(list: &LinkedList<i32>) -> Vec<i32> {
+        list.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_new_list_is_empty() {
+        let list: LinkedList<i32> = LinkedList::new();
+        assert!(list.is_empty());
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.peek_left(), None);
+        assert_eq!(list.peek_right(), None);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_push_and_pop() {
+        let mut list = LinkedList::new();
+        list.push_right(2);
+        list.push_left(1);
+        list.push_right(3);
+        assert_eq!(values(&list), vec![1, 2, 3]);
+        assert_eq!(list.peek_left(), Some(&1));
+        assert_eq!(list.peek_right(), Some(&3));
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.validate(), Ok(()));
+
+        assert_eq!(list.pop_left(), Some(1));
+        assert_eq!(list.pop_left(), Some(2));
+        assert_eq!(list.pop_left(), Some(3));
+        assert_eq!(list.pop_left(), None);
+        assert!(list.is_empty());
+        assert_eq!(list.validate(), Ok(()));
+
+        list.push_right(4);
+        assert_eq!(list.peek_left(), Some(&4));
+        assert_eq!(list.peek_right(), Some(&4));
+    }
+
+    #[test]
+    fn test_indices_survive_other_mutations() {
+        let mut list = LinkedList::new();
+        let a = list.push_right("a");
+        let b = list.push_right("b");
+        let c = list.push_left("c");
+        list.extend(["d", "e"]);
+        assert_eq!(list.pop_left(), Some("c"));
+        assert_eq!(list.remove(a), Some("a"));
+        list.push_left("f");
+
+        // f reused a freed slot but the removed indices stay dead
+        assert_eq!(list.get(a), None);
+        assert_eq!(list.get(c), None);
+        assert_eq!(list.get(b), Some(&"b"));
+        *list.get_mut(b).unwrap() = "B";
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec!["f", "B", "d", "e"]);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_insert_after() {
+        let mut list = LinkedList::from(vec![1, 3]);
+        let first = list.index_of(&1).unwrap();
+        let two = list.insert_after(first, 2).unwrap();
+        let last = list.index_of(&3).unwrap();
+        list.insert_after(last, 4).unwrap();
+        assert_eq!(values(&list), vec![1, 2, 3, 4]);
+        assert_eq!(list.peek_right(), Some(&4));
+        assert_eq!(list.validate(), Ok(()));
+
+        list.remove(two);
+        assert_eq!(list.insert_after(two, 5), Err(5));
+        assert_eq!(values(&list), vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut list = LinkedList::new();
+        let indices: Vec<Index> = (0..5).map(|val| list.push_right(val)).collect();
+        assert_eq!(list.remove(indices[4]), Some(4));
+        assert_eq!(list.peek_right(), Some(&3));
+        assert_eq!(list.remove(indices[2]), Some(2));
+        assert_eq!(list.remove(indices[0]), Some(0));
+        assert_eq!(list.remove(indices[0]), None);
+        assert_eq!(values(&list), vec![1, 3]);
+        assert_eq!(list.validate(), Ok(()));
+
+        // a node added after is found even in a reused slot
+        let new = list.push_right(5);
+        assert_eq!(list.remove(new), Some(5));
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_find_and_delete() {
+        let mut list = LinkedList::from(vec![1, 2, 3, 2]);
+        assert_eq!(list.find(&2), Some(&2));
+        assert!(list.contains(&3));
+        assert!(!list.contains(&4));
+        assert_eq!(list.delete(&2), Some(2));
+        assert_eq!(list.delete(&5), None);
+        assert_eq!(values(&list), vec![1, 3, 2]);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_iter() {
+        let list: LinkedList<i32> = (1..=4).collect();
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.len(), 3);
+        assert_eq!((&list).into_iter().sum::<i32>(), 10);
+    }
+
+    #[test]
+    fn test_clear_reuses_the_arena() {
+        let mut list = LinkedList::with_capacity(4);
+        let old = list.push_right(1);
+        list.extend([2, 3, 4]);
+        list.clear();
+        assert!(list.is_empty());
+        assert_eq!(list.validate(), Ok(()));
+
+        list.extend([5, 6, 7, 8]);
+        assert_eq!(list.nodes.capacity(), 4);
+        assert_eq!(list.get(old), None);
+        assert_eq!(values(&list), vec![5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_drop_values() {
+        use std::rc::Rc;
+        let alive = Rc::new(());
+        let mut list: LinkedList<Rc<()>> = (0..4).map(|_| alive.clone()).collect();
+        drop(list.pop_left());
+        assert_eq!(Rc::strong_count(&alive), 4);
+        drop(list);
+        assert_eq!(Rc::strong_count(&alive), 1);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut list = LinkedList::new();
+        let indices: Vec<Index> = (0..3).map(|val| list.push_right(val)).collect();
+        assert_eq!(list.validate(), Ok(()));
+
+        list.tail = Some(indices[1]);
+        assert_eq!(list.validate(), Err(InvariantError::WrongTail));
+        list.tail = Some(indices[2]);
+
+        list.nodes[indices[2]].next = Some(indices[1]);
+        assert_eq!(list.validate(), Err(InvariantError::Cycle { index: 1 }));
+        list.nodes[indices[2]].next = None;
+
+        // a node left in the arena but not linked
+        list.nodes[indices[0]].next = Some(indices[2]);
+        assert_eq!(list.validate(), Err(InvariantError::WrongLength { stored: 3, actual: 2 }));
+
+        // a link to a removed node
+        list.nodes.remove(indices[1]);
+        list.nodes[indices[0]].next = Some(indices[1]);
+        assert_eq!(list.validate(), Err(InvariantError::Dangling { index: 1 }));
+    }
+}
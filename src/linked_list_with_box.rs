@@ -1,4 +1,7 @@
-use std::ptr;
+use std::cmp::Ordering;
+use std::mem;
+use std::ptr::{self, NonNull};
+use crate::invariant::InvariantError;
 
 type Link<T> = Option<Box<Node<T>>>;
 
@@ -7,9 +10,13 @@ struct Node<T> {
     next: Link<T>,
 }
 
+// tail points at the last node for the O(1) push_right and peek_right.
+// Moving a Box invalidates pointers into it, so tail is taken again from a live borrow every time
+// the last box moves: after it's put in place, and after any relinking that moves it.
 pub struct LinkedList<T> {
     head: Link<T>,
-    tail: *mut Node<T>,
+    tail: Option<NonNull<Node<T>>>,
+    len: usize,
 }
 
 impl<T> Drop for LinkedList<T> {
@@ -25,7 +32,8 @@ impl<T> Default for LinkedList<T> {
     fn default() -> Self {
         LinkedList {
             head: None,
-            tail: ptr::null_mut()
+            tail: None,
+            len: 0,
         }
     }
 }
@@ -40,42 +48,196 @@ impl<T> From<Vec<T>> for LinkedList<T> {
     }
 }
 
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = LinkedList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for LinkedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for v in iter {
+            self.push_right(v);
+        }
+    }
+}
+
+// Borrowing iterator, it follows the boxes so no unsafe is needed
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next?;
+        self.next = node.next.as_deref();
+        Some(&node.val)
+    }
+}
+
+pub struct IterMut<'a, T> {
+    next: Option<&'a mut Node<T>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // take the reference out so the node can be split into its value and its next link
+        let node = self.next.take()?;
+        self.next = node.next.as_deref_mut();
+        Some(&mut node.val)
+    }
+}
+
+// Owning iterator, pops from the left
+pub struct IntoIter<T> {
+    list: LinkedList<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_left()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T> IntoIterator for LinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a LinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut LinkedList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
 impl<T> LinkedList<T> {
     pub fn new() -> Self {
         Self::default()
     }
 
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { next: self.head.as_deref() }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { next: self.head.as_deref_mut() }
+    }
+
+    pub fn peek_left(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.val)
+    }
+
+    // tail points into the last box, which lives as long as the list borrow
+    pub fn peek_right(&self) -> Option<&T> {
+        self.tail.map(|node| unsafe { &(*node.as_ptr()).val })
+    }
+
+    pub fn pop_left(&mut self) -> Option<T> {
+        let mut node = self.head.take()?;
+        // if the second node is the last one its box just moved into head
+        self.head = node.next.take();
+        self.tail_from_head();
+        self.len -= 1;
+        Some(node.val)
+    }
+
     pub fn push_right(&mut self, val: T) {
-        let mut new_boxed_node = Box::new(Node {
+        let new_boxed_node = Box::new(Node {
             val,
             next: None,
         });
 
-        let new_boxed_node_ptr: *mut Node<T> = &mut *new_boxed_node;
+        let last_link = match self.tail {
+            None => &mut self.head,
+            Some(tail) => unsafe { &mut (*tail.as_ptr()).next },
+        };
+        // the box is in its place now, take the pointer from there
+        let new_node = last_link.insert(new_boxed_node);
+        self.tail = Some(NonNull::from(&mut **new_node));
+        self.len += 1;
+    }
 
-        if self.head.is_none() {
-            self.head = Some(new_boxed_node);
-        } else {
-            unsafe { (*self.tail).next = Some(new_boxed_node) };
+    pub fn push_left(&mut self, val: T) {
+        // if it is already None will get None
+        let next = self.head.take();
+        self.head = Some(Box::new(Node { val, next }));
+
+        // the old head moved under the new one, it may have been the last node
+        let head = self.head.as_deref_mut().unwrap();
+        match head.next.as_deref_mut() {
+            None => self.tail = Some(NonNull::from(head)),
+            Some(next) if next.next.is_none() => self.tail = Some(NonNull::from(next)),
+            Some(_) => {},
         }
-        
-        self.tail = new_boxed_node_ptr;
+        self.len += 1;
     }
 
-    pub fn push_left(&mut self, val: T) {
-        let new_boxed_node = Box::new(Node {
-            val,
-            next: self.head.take(), // if it is already None will get None
-        });
+    // after head moved: if it is the last node tail has to point at it again
+    fn tail_from_head(&mut self) {
+        match self.head.as_deref_mut() {
+            None => self.tail = None,
+            Some(head) if head.next.is_none() => self.tail = Some(NonNull::from(head)),
+            Some(_) => {},
+        }
+    }
+}
 
-        self.head = Some(new_boxed_node);
+impl<T> LinkedList<T> {
+    // tail must point at the last node, or be null when the list is empty, and len must count
+    // the nodes
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        let mut last: *const Node<T> = ptr::null();
+        let mut count = 0;
+        let mut current_link = self.head.as_deref();
+        while let Some(node) = current_link {
+            last = node;
+            count += 1;
+            current_link = node.next.as_deref();
+        }
 
-        if self.tail.is_null() {
-            // just put a node in head so unwrap is safe.
-            // Double deref because is getting a ref with "as_mut" and to deref the Box pointer
-            let new_boxed_node_ptr: *mut Node<T> = &mut **self.head.as_mut().unwrap();
-            self.tail = new_boxed_node_ptr;
+        if !ptr::eq(last, self.tail.map_or(ptr::null(), |tail| tail.as_ptr())) {
+            return Err(InvariantError::WrongTail);
+        }
+        if count != self.len {
+            return Err(InvariantError::WrongLength { stored: self.len, actual: count });
         }
+        Ok(())
     }
 }
 
@@ -92,36 +254,151 @@ impl<T: PartialEq> LinkedList<T> {
         None
     }
 
-    // take-inspect-replace logic
+    // Walks the links without taking the boxes out, only the removed node's next box moves
     pub fn delete(&mut self, val: &T) -> Option<T> {
-        // previous ptr to update the tail if necessary
-        let mut prev_node_ptr: *mut Node<T> = ptr::null_mut();
         let mut current_link = &mut self.head;
+        while current_link.as_ref().is_some_and(|node| node.val != *val) {
+            current_link = &mut current_link.as_mut().unwrap().next;
+        }
 
-        // dont borrow, take ownership. Take out of the list to manipulate
-        while let Some(mut current_node) = current_link.take() {
-            if current_node.val == *val {
-                *current_link = current_node.next.take();
-                
-                let deleted_node_ptr: *const Node<T> = &*current_node;
-                if ptr::eq(self.tail, deleted_node_ptr) {
-                    self.tail = prev_node_ptr;
-                }
+        let mut deleted_node = current_link.take()?;
+        *current_link = deleted_node.next.take();
+        // the last node was either removed or moved up one link, find it again
+        let tail_moved = current_link.as_ref().is_none_or(|node| node.next.is_none());
+        if tail_moved {
+            self.reset_tail();
+        }
+        self.len -= 1;
+        Some(deleted_node.val)
+    }
+}
+
+// Cuts the chain after `at` nodes and returns the second half, the chain must be that long
+fn split_link<T>(link: &mut Link<T>, at: usize) -> Link<T> {
+    let mut current = link;
+    for _ in 0..at {
+        current = &mut current.as_mut().expect("the chain is shorter than the split point").next;
+    }
+    current.take()
+}
+
+// Merges two sorted chains by moving the boxes, on ties the left node goes first so merging is
+// stable
+fn merge_links<T, F: FnMut(&T, &T) -> Ordering>(mut left: Link<T>, mut right: Link<T>, compare: &mut F) -> Link<T> {
+    let mut head = None;
+    let mut last = &mut head;
+    while let (Some(l), Some(r)) = (left.as_ref(), right.as_ref()) {
+        let side = if compare(&r.val, &l.val) == Ordering::Less { &mut right } else { &mut left };
+        let mut node = side.take().unwrap();
+        *side = node.next.take();
+        last = &mut last.insert(node).next;
+    }
+    *last = if left.is_some() { left } else { right };
+    head
+}
+
+// Top down merge sort on a chain of len nodes, only the recursion takes extra space
+fn sort_link<T, F: FnMut(&T, &T) -> Ordering>(mut head: Link<T>, len: usize, compare: &mut F) -> Link<T> {
+    if len < 2 {
+        return head;
+    }
+    let half = len / 2;
+    let right = split_link(&mut head, half);
+    let left = sort_link(head, half, compare);
+    let right = sort_link(right, len - half, compare);
+    merge_links(left, right, compare)
+}
 
-                return Some(current_node.val);
+// In place algorithms, they relink the boxes and never move a value
+impl<T> LinkedList<T> {
+    pub fn reverse(&mut self) {
+        let mut reversed: Link<T> = None;
+        let mut current = self.head.take();
+        while let Some(mut node) = current {
+            current = mem::replace(&mut node.next, reversed);
+            reversed = Some(node);
+        }
+        self.head = reversed;
+        // every box moved, the old head is last now
+        self.reset_tail();
+    }
+
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        // the list is left empty while the chain is sorted, if compare panics the nodes are
+        // dropped with the chain and the list is still valid
+        let len = mem::replace(&mut self.len, 0);
+        self.tail = None;
+        self.head = sort_link(self.head.take(), len, &mut compare);
+        self.len = len;
+        self.reset_tail();
+    }
+
+    pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut key: F) {
+        self.sort_by(|a, b| key(a).cmp(&key(b)));
+    }
+
+    // Removes consecutive values for which same(value, kept value) is true, keeping the first of
+    // each run, like Vec::dedup_by
+    pub fn dedup_by<F: FnMut(&mut T, &mut T) -> bool>(&mut self, mut same: F) {
+        let Some(mut node) = self.head.as_deref_mut() else {
+            return;
+        };
+        loop {
+            while let Some(next) = node.next.as_deref_mut() && same(&mut next.val, &mut node.val) {
+                let mut removed = node.next.take().unwrap();
+                node.next = removed.next.take();
+                self.len -= 1;
+                // fix the tail before the removed box is dropped, in case dropping its value panics
+                if node.next.is_none() {
+                    self.tail = Some(NonNull::from(&mut *node));
+                }
             }
+            if node.next.is_none() {
+                self.tail = Some(NonNull::from(node));
+                return;
+            }
+            node = node.next.as_deref_mut().unwrap();
+        }
+    }
 
-            // update previous pointer
-            prev_node_ptr = &mut *current_node;
-            
-            // put back on the list
-            *current_link = Some(current_node);
+    pub fn dedup_by_key<K: PartialEq, F: FnMut(&mut T) -> K>(&mut self, mut key: F) {
+        self.dedup_by(|a, b| key(a) == key(b));
+    }
 
-            // update to the next
-            current_link = &mut current_link.as_mut().unwrap().next;
+    // walks to the last node, after relinking that moved its box
+    fn reset_tail(&mut self) {
+        self.tail = None;
+        let mut current = self.head.as_deref_mut();
+        while let Some(node) = current {
+            if node.next.is_none() {
+                self.tail = Some(NonNull::from(node));
+                break;
+            }
+            current = node.next.as_deref_mut();
         }
+    }
+}
 
-        None
+impl<T: Ord> LinkedList<T> {
+    // stable, equal values keep their order
+    pub fn sort(&mut self) {
+        self.sort_by(T::cmp);
+    }
+
+    // Merges another sorted list into this one in O(n + m), values from self go before equal
+    // values from other
+    pub fn merge(&mut self, mut other: Self) {
+        other.tail = None;
+        self.head = merge_links(self.head.take(), other.head.take(), &mut T::cmp);
+        self.len += mem::take(&mut other.len);
+        // merging moved the boxes, the walk is O(n + m) like the merge
+        self.reset_tail();
+    }
+}
+
+impl<T: PartialEq> LinkedList<T> {
+    pub fn dedup(&mut self) {
+        self.dedup_by(|a, b| a == b);
     }
 }
 
@@ -134,7 +411,7 @@ mod tests {
     fn test_new_list_is_empty() {
         let list: LinkedList<i32> = LinkedList::new();
         assert!(list.head.is_none());
-        assert!(list.tail.is_null());
+        assert!(list.tail.is_none());
     }
 
     // --- From<Vec<T>> tests ---
@@ -143,7 +420,7 @@ mod tests {
         let list = LinkedList::from(vec![1, 2, 3]);
         assert_eq!(list.head.as_ref().unwrap().val, 1);
         unsafe {
-            assert_eq!((*list.tail).val, 3);
+            assert_eq!(list.tail.unwrap().as_ref().val, 3);
         }
         assert_eq!(list.find(&1), Some(&1));
         assert_eq!(list.find(&2), Some(&2));
@@ -154,7 +431,7 @@ mod tests {
     fn test_from_empty_vec() {
         let list = LinkedList::from(Vec::<i32>::new());
         assert!(list.head.is_none());
-        assert!(list.tail.is_null());
+        assert!(list.tail.is_none());
     }
 
     // --- Push operation tests ---
@@ -164,7 +441,7 @@ mod tests {
         list.push_left(1);
         assert_eq!(list.head.as_ref().unwrap().val, 1);
         unsafe {
-            assert_eq!((*list.tail).val, 1);
+            assert_eq!(list.tail.unwrap().as_ref().val, 1);
         }
     }
 
@@ -175,7 +452,7 @@ mod tests {
         list.push_left(2);
         assert_eq!(list.head.as_ref().unwrap().val, 2);
         unsafe {
-            assert_eq!((*list.tail).val, 1);
+            assert_eq!(list.tail.unwrap().as_ref().val, 1);
         }
     }
 
@@ -185,7 +462,7 @@ mod tests {
         list.push_right(1);
         assert_eq!(list.head.as_ref().unwrap().val, 1);
         unsafe {
-            assert_eq!((*list.tail).val, 1);
+            assert_eq!(list.tail.unwrap().as_ref().val, 1);
         }
     }
 
@@ -196,7 +473,7 @@ mod tests {
         list.push_right(2);
         assert_eq!(list.head.as_ref().unwrap().val, 1);
         unsafe {
-            assert_eq!((*list.tail).val, 2);
+            assert_eq!(list.tail.unwrap().as_ref().val, 2);
         }
     }
 
@@ -249,7 +526,7 @@ mod tests {
         let mut list = LinkedList::from(vec![1]);
         assert_eq!(list.delete(&1), Some(1));
         assert!(list.head.is_none());
-        assert!(list.tail.is_null());
+        assert!(list.tail.is_none());
     }
 
     #[test]
@@ -265,7 +542,7 @@ mod tests {
         let mut list = LinkedList::from(vec![1, 2, 3]);
         assert_eq!(list.delete(&3), Some(3));
         unsafe {
-            assert_eq!((*list.tail).val, 2);
+            assert_eq!(list.tail.unwrap().as_ref().val, 2);
         }
         assert_eq!(list.find(&3), None);
     }
@@ -288,17 +565,237 @@ mod tests {
         assert_eq!(list.delete(&1), Some(1)); // 0, 2
         assert_eq!(list.head.as_ref().unwrap().val, 0);
         unsafe {
-            assert_eq!((*list.tail).val, 2);
+            assert_eq!(list.tail.unwrap().as_ref().val, 2);
         }
         list.push_right(3); // 0, 2, 3
         assert_eq!(list.find(&3), Some(&3));
         unsafe {
-            assert_eq!((*list.tail).val, 3);
+            assert_eq!(list.tail.unwrap().as_ref().val, 3);
         }
         assert_eq!(list.delete(&0), Some(0)); // 2, 3
         assert_eq!(list.head.as_ref().unwrap().val, 2);
     }
 
+    // --- Pop and peek tests ---
+    #[test]
+    fn test_pop_left() {
+        let mut list = LinkedList::from(vec![1, 2, 3]);
+        assert_eq!(list.pop_left(), Some(1));
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.pop_left(), Some(2));
+        assert_eq!(list.pop_left(), Some(3));
+        assert_eq!(list.pop_left(), None);
+        assert!(list.is_empty());
+        assert!(list.tail.is_none());
+
+        // the list works again after being emptied
+        list.push_right(4);
+        assert_eq!(list.peek_left(), Some(&4));
+        assert_eq!(list.peek_right(), Some(&4));
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut list = LinkedList::new();
+        assert_eq!(list.peek_left(), None);
+        assert_eq!(list.peek_right(), None);
+        list.push_right(2);
+        list.push_left(1);
+        list.push_right(3);
+        assert_eq!(list.peek_left(), Some(&1));
+        assert_eq!(list.peek_right(), Some(&3));
+        list.delete(&3);
+        assert_eq!(list.peek_right(), Some(&2));
+    }
+
+    // --- Length tests ---
+    #[test]
+    fn test_len_follows_every_operation() {
+        let mut list = LinkedList::new();
+        assert_eq!(list.len(), 0);
+        list.push_right(1);
+        list.push_left(0);
+        list.extend([2, 3]);
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.delete(&5), None);
+        assert_eq!(list.delete(&2), Some(2));
+        assert_eq!(list.pop_left(), Some(0));
+        assert_eq!(list.len(), 2);
+        assert!(!list.is_empty());
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    // --- Iterator tests ---
+    #[test]
+    fn test_iter() {
+        let list = LinkedList::from(vec![1, 2, 3]);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+        assert_eq!((&list).into_iter().sum::<i32>(), 6);
+        assert_eq!(LinkedList::<i32>::new().iter().next(), None);
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        let mut list = LinkedList::from(vec![1, 2, 3]);
+        for val in list.iter_mut() {
+            *val *= 10;
+        }
+        for val in &mut list {
+            *val += 1;
+        }
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![11, 21, 31]);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let list = LinkedList::from(vec![String::from("a"), String::from("b")]);
+        let mut iter = list.into_iter();
+        assert_eq!(iter.size_hint(), (2, Some(2)));
+        assert_eq!(iter.next(), Some(String::from("a")));
+        assert_eq!(iter.collect::<Vec<_>>(), vec![String::from("b")]);
+    }
+
+    #[test]
+    fn test_from_iter_and_extend() {
+        let mut list: LinkedList<i32> = (1..=3).collect();
+        list.extend(vec![4, 5]);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+
+        let mut list: LinkedList<i32> = std::iter::empty().collect();
+        assert!(list.is_empty());
+        list.extend(Some(1));
+        assert_eq!(list.peek_right(), Some(&1));
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    // --- In place algorithm tests ---
+    fn collect(list: &LinkedList<i32>) -> Vec<i32> {
+        list.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_reverse() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        list.reverse();
+        assert!(list.is_empty());
+        assert!(list.tail.is_none());
+
+        list.push_right(1);
+        list.reverse();
+        assert_eq!(list.validate(), Ok(()));
+
+        list.extend([2, 3, 4]);
+        list.reverse();
+        assert_eq!(collect(&list), vec![4, 3, 2, 1]);
+        assert_eq!(list.peek_right(), Some(&1));
+        assert_eq!(list.validate(), Ok(()));
+
+        // the tail is usable right away
+        list.push_right(0);
+        assert_eq!(collect(&list), vec![4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut list = LinkedList::from(vec![5, 1, 4, 1, 5, 9, 2, 6, 5, 3]);
+        list.sort();
+        assert_eq!(collect(&list), vec![1, 1, 2, 3, 4, 5, 5, 5, 6, 9]);
+        assert_eq!(list.len(), 10);
+        assert_eq!(list.validate(), Ok(()));
+        list.push_right(10);
+        assert_eq!(list.peek_right(), Some(&10));
+
+        let mut list: LinkedList<i32> = LinkedList::new();
+        list.sort();
+        assert_eq!(list.validate(), Ok(()));
+        list.push_right(1);
+        list.sort();
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_sort_is_stable() {
+        let mut list = LinkedList::from(vec![(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd'), (0, 'e'), (2, 'f')]);
+        list.sort_by_key(|&(key, _)| key);
+        let order: String = list.iter().map(|&(_, tag)| tag).collect();
+        assert_eq!(order, "ebdacf");
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_sort_moves_no_values() {
+        let mut list: LinkedList<i32> = (0..50).rev().collect();
+        let before: Vec<*const i32> = list.iter().map(|val| val as *const i32).collect();
+        list.sort();
+        let mut after: Vec<*const i32> = list.iter().map(|val| val as *const i32).collect();
+        // the same boxes in the opposite order
+        after.reverse();
+        assert_eq!(before, after);
+        assert_eq!(collect(&list), (0..50).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_dedup() {
+        let mut list = LinkedList::from(vec![1, 1, 2, 3, 3, 3, 1, 4, 4]);
+        list.dedup();
+        assert_eq!(collect(&list), vec![1, 2, 3, 1, 4]);
+        assert_eq!(list.len(), 5);
+        assert_eq!(list.peek_right(), Some(&4));
+        assert_eq!(list.validate(), Ok(()));
+
+        let mut list = LinkedList::from(vec![7, 7, 7]);
+        list.dedup();
+        assert_eq!(collect(&list), vec![7]);
+        assert_eq!(list.validate(), Ok(()));
+
+        let mut list: LinkedList<i32> = LinkedList::new();
+        list.dedup();
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_dedup_by_key() {
+        let mut list = LinkedList::from(vec![10, 11, 20, 25, 31, 12, 19]);
+        list.dedup_by_key(|val| *val / 10);
+        assert_eq!(collect(&list), vec![10, 20, 31, 12]);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut list = LinkedList::from(vec![1, 4, 6]);
+        list.merge(LinkedList::from(vec![2, 3, 7, 8]));
+        assert_eq!(collect(&list), vec![1, 2, 3, 4, 6, 7, 8]);
+        assert_eq!(list.len(), 7);
+        assert_eq!(list.validate(), Ok(()));
+
+        // the tail stays in self when its last value is the biggest
+        let mut list = LinkedList::from(vec![1, 9]);
+        list.merge(LinkedList::from(vec![2, 3]));
+        assert_eq!(list.peek_right(), Some(&9));
+        assert_eq!(list.validate(), Ok(()));
+
+        let mut list = LinkedList::new();
+        list.merge(LinkedList::from(vec![1, 2]));
+        assert_eq!(list.validate(), Ok(()));
+        list.merge(LinkedList::new());
+        assert_eq!(collect(&list), vec![1, 2]);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_merge_is_stable() {
+        // equal values can't be told apart, so follow the boxes
+        let mut list = LinkedList::from(vec![1, 2]);
+        let other = LinkedList::from(vec![1, 2]);
+        let mine: Vec<*const i32> = list.iter().map(|val| val as *const i32).collect();
+        let theirs: Vec<*const i32> = other.iter().map(|val| val as *const i32).collect();
+        list.merge(other);
+        let merged: Vec<*const i32> = list.iter().map(|val| val as *const i32).collect();
+        assert_eq!(merged, vec![mine[0], theirs[0], mine[1], theirs[1]]);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
     // --- Drop implementation tests ---
     #[test]
     fn test_drop_on_list_with_items() {
@@ -325,4 +822,64 @@ mod tests {
 
         assert_eq!(*drop_count.borrow(), 3);
     }
+
+    #[test]
+    fn test_into_iter_drops_the_rest() {
+        use std::rc::Rc;
+        let alive = Rc::new(());
+        let list: LinkedList<Rc<()>> = (0..4).map(|_| alive.clone()).collect();
+        let mut iter = list.into_iter();
+        let first = iter.next();
+        drop(iter);
+        assert_eq!(Rc::strong_count(&alive), 2);
+        drop(first);
+        assert_eq!(Rc::strong_count(&alive), 1);
+    }
+
+    #[test]
+    fn test_tail_follows_moved_boxes() {
+        // each step moves the box of the last node to another link
+        let mut list = LinkedList::from(vec![1, 2]);
+        list.pop_left();
+        assert_eq!(list.peek_right(), Some(&2));
+        list.push_left(0);
+        assert_eq!(list.peek_right(), Some(&2));
+        list.push_right(3);
+        list.delete(&2);
+        assert_eq!(list.peek_right(), Some(&3));
+        list.delete(&3);
+        assert_eq!(list.peek_right(), Some(&0));
+        list.push_right(4);
+        assert_eq!(collect(&list), vec![0, 4]);
+        assert_eq!(list.validate(), Ok(()));
+
+        let mut list = LinkedList::new();
+        list.push_left(1);
+        list.push_left(0);
+        list.push_right(2);
+        assert_eq!(collect(&list), vec![0, 1, 2]);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut list = LinkedList::from(vec![1, 2, 3]);
+        assert_eq!(list.validate(), Ok(()));
+        list.delete(&3);
+        assert_eq!(list.validate(), Ok(()));
+        list.delete(&1);
+        list.delete(&2);
+        assert_eq!(list.validate(), Ok(()));
+        list.push_left(4);
+        assert_eq!(list.validate(), Ok(()));
+
+        list.push_right(5);
+        list.tail = list.head.as_deref_mut().map(NonNull::from);
+        assert_eq!(list.validate(), Err(InvariantError::WrongTail));
+        list.tail = list.head.as_mut().unwrap().next.as_deref_mut().map(NonNull::from);
+
+        list.len = 7;
+        assert_eq!(list.validate(), Err(InvariantError::WrongLength { stored: 7, actual: 2 }));
+        list.len = 2;
+    }
 }
@@ -1,6 +1,9 @@
 use std::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use std::cmp::Ordering;
 use std::marker::PhantomData;
+use std::mem;
 use std::ptr;
+use crate::invariant::InvariantError;
 
 struct Node<T> {
     val: T,
@@ -8,10 +11,18 @@ struct Node<T> {
 }
 
 pub struct LinkedList<T> {
-	head: *mut Node<T>,
-	tail: *mut Node<T>,
+    head: *mut Node<T>,
+    tail: *mut Node<T>,
+    len: usize,
+    // the list owns the nodes and their values even if it only holds raw pointers to them,
+    // this tells the drop check that dropping the list drops values of type T
+    _marker: PhantomData<Box<Node<T>>>,
 }
 
+// the nodes are only reachable through the list, so it can move between threads like a Vec
+unsafe impl<T: Send> Send for LinkedList<T> {}
+unsafe impl<T: Sync> Sync for LinkedList<T> {}
+
 impl<T> Drop for LinkedList<T> {
     fn drop(&mut self) {
         let mut current = self.head;
@@ -19,18 +30,45 @@ impl<T> Drop for LinkedList<T> {
         while !current.is_null() {
             let next_node = unsafe { (*current).next };
 
-            let layout = Layout::new::<Node<T>>();
-
-            unsafe { dealloc(current as *mut u8, layout) };
+            // the value has to be dropped before freeing the memory, dealloc alone would leak
+            // whatever the value owns (a String buffer, a Vec...)
+            unsafe {
+                ptr::drop_in_place(&mut (*current).val);
+                dealloc(current as *mut u8, Layout::new::<Node<T>>());
+            }
 
             current = next_node;
         }
     }
 }
 
+// allocates a node with the global allocator and moves the value in
+fn new_node<T>(val: T, next: *mut Node<T>) -> *mut Node<T> {
+    let layout = Layout::new::<Node<T>>();
+    let new_node_ptr = unsafe { alloc(layout) as *mut Node<T> };
+
+    // if some error happen during allocation
+    if new_node_ptr.is_null() {
+        handle_alloc_error(layout);
+    }
+
+    unsafe { ptr::write(new_node_ptr, Node { val, next }) };
+    new_node_ptr
+}
+
+// Moves the value out and frees the node, which must be unlinked already.
+// Safety: node must come from new_node and not be used after this
+unsafe fn free_node<T>(node: *mut Node<T>) -> T {
+    unsafe {
+        let value = ptr::read(&(*node).val);
+        dealloc(node as *mut u8, Layout::new::<Node<T>>());
+        value
+    }
+}
+
 impl<T> Default for LinkedList<T> {
     fn default() -> Self {
-        LinkedList { head: ptr::null_mut(), tail: ptr::null_mut() }
+        LinkedList { head: ptr::null_mut(), tail: ptr::null_mut(), len: 0, _marker: PhantomData }
     }
 }
 
@@ -80,53 +118,62 @@ impl<'a,T> Iterator for Iter<'a,T> {
     }
 }
 
+// Same as Iter but hands out mutable references
+pub struct IterMut<'a, T> {
+    next: *mut Node<T>,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next.is_null() {
+            return None;
+        }
+        unsafe {
+            // every node is visited once, so no two references to the same value are handed out
+            let node = &mut *self.next;
+            self.next = node.next;
+            Some(&mut node.val)
+        }
+    }
+}
+
 impl<T> LinkedList<T> {
     pub fn new() -> Self {
         Self::default()
     }
 
-    // to get the iterator struct
-    pub fn iter<'a>(&self) -> Iter<'a,T> {
-        Iter { next: self.head, _marker: PhantomData }
+    pub fn len(&self) -> usize {
+        self.len
     }
 
-    pub fn push_left(&mut self, val: T) {
-        let layout = Layout::new::<Node<T>>();
-        let new_node_ptr = unsafe { alloc(layout) as *mut Node<T> };
+    pub fn is_empty(&self) -> bool {
+        self.head.is_null()
+    }
 
-        // if some error happen during allocation
-        if new_node_ptr.is_null() {
-            handle_alloc_error(layout);
-        }
+    // to get the iterator struct, it borrows the list so the list outlives it
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { next: self.head, _marker: PhantomData }
+    }
 
-        unsafe {
-            ptr::write(new_node_ptr, Node {
-                val,
-                next: self.head,
-            });
-        }
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { next: self.head, _marker: PhantomData }
+    }
 
+    pub fn push_left(&mut self, val: T) {
+        let new_node_ptr = new_node(val, self.head);
         self.head = new_node_ptr;
 
         if self.tail.is_null() {
             self.tail = new_node_ptr;
         }
+        self.len += 1;
     }
 
     pub fn push_right(&mut self, val: T) {
-        let layout = Layout::new::<Node<T>>();
-        let new_node_ptr = unsafe { alloc(layout) as *mut Node<T> };
-
-        if new_node_ptr.is_null() {
-            handle_alloc_error(layout);
-        }
-
-        unsafe {
-            ptr::write(new_node_ptr, Node {
-                val,
-                next: ptr::null_mut(),
-            });
-        }
+        let new_node_ptr = new_node(val, ptr::null_mut());
 
         if let Some(tail_node) = unsafe { self.tail.as_mut() } {
             tail_node.next = new_node_ptr;
@@ -135,6 +182,94 @@ impl<T> LinkedList<T> {
         }
 
         self.tail = new_node_ptr;
+        self.len += 1;
+    }
+
+    pub fn pop_left(&mut self) -> Option<T> {
+        if self.head.is_null() {
+            return None;
+        }
+
+        let node = self.head;
+        unsafe {
+            self.head = (*node).next;
+            if self.head.is_null() { // If list is now empty
+                self.tail = ptr::null_mut();
+            }
+            self.len -= 1;
+            Some(free_node(node))
+        }
+    }
+
+    // O(n): the list only links forward, so finding the node before the tail means walking it
+    pub fn pop_right(&mut self) -> Option<T> {
+        if self.head == self.tail {
+            return self.pop_left(); // empty or a single node
+        }
+
+        let node = self.tail;
+        unsafe {
+            let mut current = self.head;
+            while (*current).next != node {
+                current = (*current).next;
+            }
+            (*current).next = ptr::null_mut();
+            self.tail = current;
+            self.len -= 1;
+            Some(free_node(node))
+        }
+    }
+
+    pub fn peek_left(&self) -> Option<&T> {
+        unsafe { self.head.as_ref().map(|node| &node.val) }
+    }
+
+    pub fn peek_right(&self) -> Option<&T> {
+        unsafe { self.tail.as_ref().map(|node| &node.val) }
+    }
+
+    // tail must point at the last node, or be null when the list is empty, and len must count
+    // the nodes
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        // walking a looped list would never end, so look for the loop first
+        if let Some(index) = self.cycle_start() {
+            return Err(InvariantError::Cycle { index });
+        }
+
+        let mut last: *mut Node<T> = ptr::null_mut();
+        let mut count = 0;
+        let mut current = self.head;
+        while !current.is_null() {
+            last = current;
+            count += 1;
+            current = unsafe { (*current).next };
+        }
+
+        if last != self.tail {
+            return Err(InvariantError::WrongTail);
+        }
+        if count != self.len {
+            return Err(InvariantError::WrongLength { stored: self.len, actual: count });
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T> IntoIterator for &'a LinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut LinkedList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
     }
 }
 
@@ -159,17 +294,7 @@ impl<T: PartialEq> LinkedList<T> {
         unsafe {
             // Case 1: Handle the head node
             if (*self.head).val == *val {
-                let node_to_delete = self.head;
-                let value = ptr::read(&(*node_to_delete).val);
-
-                self.head = (*node_to_delete).next;
-                if self.head.is_null() { // If list is now empty
-                    self.tail = ptr::null_mut();
-                }
-
-                let layout = Layout::new::<Node<T>>();
-                dealloc(node_to_delete as *mut u8, layout);
-                return Ok(value);
+                return self.pop_left().ok_or(DeleteError::NotFound);
             }
 
             let mut current = self.head;
@@ -177,17 +302,14 @@ impl<T: PartialEq> LinkedList<T> {
                 let next_node_ptr = (*current).next;
                 if (*next_node_ptr).val == *val {
                     let node_to_delete = next_node_ptr;
-                    let value = ptr::read(&(*node_to_delete).val);
-
                     (*current).next = (*node_to_delete).next;
 
                     if self.tail == node_to_delete {
                         self.tail = current;
                     }
 
-                    let layout = Layout::new::<Node<T>>();
-                    dealloc(node_to_delete as *mut u8, layout);
-                    return Ok(value);
+                    self.len -= 1;
+                    return Ok(free_node(node_to_delete));
                 }
                 current = (*current).next;
             }
@@ -197,6 +319,612 @@ impl<T: PartialEq> LinkedList<T> {
     }
 }
 
+// Merges two sorted null terminated chains by relinking, on ties the left node goes first so
+// merging is stable.
+// Safety: both chains must be made of live nodes and not share any
+unsafe fn merge_chains<T, F: FnMut(&T, &T) -> Ordering>(
+    mut left: *mut Node<T>,
+    mut right: *mut Node<T>,
+    compare: &mut F,
+) -> *mut Node<T> {
+    let mut head = ptr::null_mut();
+    // the link the next picked node goes into, the head first and then the next of the last node
+    let mut slot: *mut *mut Node<T> = &raw mut head;
+    unsafe {
+        while !left.is_null() && !right.is_null() {
+            let side = if compare(&(*right).val, &(*left).val) == Ordering::Less { &mut right } else { &mut left };
+            let node = *side;
+            *side = (*node).next;
+            *slot = node;
+            slot = &raw mut (*node).next;
+        }
+        *slot = if left.is_null() { right } else { left };
+    }
+    head
+}
+
+// Top down merge sort on a chain of len nodes, only the recursion takes extra space.
+// Safety: same as merge_chains, and the chain must have len nodes
+unsafe fn sort_chain<T, F: FnMut(&T, &T) -> Ordering>(head: *mut Node<T>, len: usize, compare: &mut F) -> *mut Node<T> {
+    if len < 2 {
+        return head;
+    }
+    let half = len / 2;
+    unsafe {
+        let mut last_left = head;
+        for _ in 1..half {
+            last_left = (*last_left).next;
+        }
+        let right = (*last_left).next;
+        (*last_left).next = ptr::null_mut();
+
+        let left = sort_chain(head, half, compare);
+        let right = sort_chain(right, len - half, compare);
+        merge_chains(left, right, compare)
+    }
+}
+
+// In place algorithms, they relink the nodes and never move a value
+impl<T> LinkedList<T> {
+    pub fn reverse(&mut self) {
+        let mut reversed = ptr::null_mut();
+        let mut current = self.head;
+        while !current.is_null() {
+            unsafe {
+                let next = (*current).next;
+                (*current).next = reversed;
+                reversed = current;
+                current = next;
+            }
+        }
+        self.tail = self.head;
+        self.head = reversed;
+    }
+
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        // the list is left empty while the chain is sorted, if compare panics the nodes leak
+        // but the list is still valid
+        let head = mem::replace(&mut self.head, ptr::null_mut());
+        let len = mem::replace(&mut self.len, 0);
+        self.tail = ptr::null_mut();
+
+        let mut current = unsafe { sort_chain(head, len, &mut compare) };
+        self.head = current;
+        self.len = len;
+        while !current.is_null() {
+            self.tail = current;
+            current = unsafe { (*current).next };
+        }
+    }
+
+    pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut key: F) {
+        self.sort_by(|a, b| key(a).cmp(&key(b)));
+    }
+
+    // Removes consecutive values for which same(value, kept value) is true, keeping the first of
+    // each run, like Vec::dedup_by
+    pub fn dedup_by<F: FnMut(&mut T, &mut T) -> bool>(&mut self, mut same: F) {
+        let mut current = self.head;
+        while !current.is_null() {
+            unsafe {
+                let mut next = (*current).next;
+                while !next.is_null() && same(&mut (*next).val, &mut (*current).val) {
+                    // unlink before dropping so a panicking drop leaves the list whole
+                    (*current).next = (*next).next;
+                    self.len -= 1;
+                    if self.tail == next {
+                        self.tail = current;
+                    }
+                    drop(free_node(next));
+                    next = (*current).next;
+                }
+                current = next;
+            }
+        }
+    }
+
+    pub fn dedup_by_key<K: PartialEq, F: FnMut(&mut T) -> K>(&mut self, mut key: F) {
+        self.dedup_by(|a, b| key(a) == key(b));
+    }
+
+    // Floyd's tortoise and hare: the hare moves two nodes per step and the tortoise one, they
+    // only meet if the list loops. The safe API can't build a loop, this is for checking lists
+    // whose pointers were changed by hand
+    pub fn has_cycle(&self) -> bool {
+        self.meeting_point().is_some()
+    }
+
+    // position of the first node inside the loop, if there is one
+    fn cycle_start(&self) -> Option<usize> {
+        let meeting = self.meeting_point()?;
+
+        // the head is as far from the loop start as the meeting point is, going forward
+        let mut from_head = self.head;
+        let mut from_meeting = meeting;
+        let mut index = 0;
+        while from_head != from_meeting {
+            unsafe {
+                from_head = (*from_head).next;
+                from_meeting = (*from_meeting).next;
+            }
+            index += 1;
+        }
+        Some(index)
+    }
+
+    fn meeting_point(&self) -> Option<*mut Node<T>> {
+        let mut slow = self.head;
+        let mut fast = self.head;
+        unsafe {
+            while !fast.is_null() && !(*fast).next.is_null() {
+                slow = (*slow).next;
+                fast = (*(*fast).next).next;
+                if slow == fast {
+                    return Some(slow);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<T: Ord> LinkedList<T> {
+    // stable, equal values keep their order
+    pub fn sort(&mut self) {
+        self.sort_by(T::cmp);
+    }
+
+    // Merges another sorted list into this one in O(n + m), values from self go before equal
+    // values from other
+    pub fn merge(&mut self, mut other: Self) {
+        // the last node is whichever tail holds the bigger value, other's on a tie
+        let tail = match unsafe { (self.tail.as_ref(), other.tail.as_ref()) } {
+            (Some(mine), Some(theirs)) if mine.val > theirs.val => self.tail,
+            (_, Some(_)) => other.tail,
+            _ => self.tail,
+        };
+
+        self.head = unsafe { merge_chains(self.head, other.head, &mut T::cmp) };
+        self.tail = tail;
+        self.len += other.len;
+
+        // other doesn't own the nodes anymore, empty it so its drop frees nothing
+        other.head = ptr::null_mut();
+        other.tail = ptr::null_mut();
+        other.len = 0;
+    }
+}
+
+impl<T: PartialEq> LinkedList<T> {
+    pub fn dedup(&mut self) {
+        self.dedup_by(|a, b| a == b);
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    // counts how many values were dropped, shared by the drop tests
+    struct DropCounter<'a> {
+        val: i32,
+        count: &'a RefCell<i32>,
+    }
+
+    impl<'a> Drop for DropCounter<'a> {
+        fn drop(&mut self) {
+            *self.count.borrow_mut() += 1;
+        }
+    }
+
+    impl PartialEq for DropCounter<'_> {
+        fn eq(&self, other: &Self) -> bool {
+            self.val == other.val
+        }
+    }
+
+    fn counters<'a>(values: &[i32], count: &'a RefCell<i32>) -> Vec<DropCounter<'a>> {
+        values.iter().map(|&val| DropCounter { val, count }).collect()
+    }
+
+    fn values(list: &LinkedList<i32>) -> Vec<i32> {
+        list.iter().copied().collect()
+    }
+
+    // --- Constructor tests ---
+    #[test]
+    fn test_new_list_is_empty() {
+        let list: LinkedList<i32> = LinkedList::new();
+        assert!(list.head.is_null());
+        assert!(list.tail.is_null());
+        assert!(list.is_empty());
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.iter().next(), None);
+    }
+
+    // --- From tests ---
+    #[test]
+    fn test_from_vec() {
+        let list = LinkedList::from(vec![1, 2, 3]);
+        assert_eq!(values(&list), vec![1, 2, 3]);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.peek_left(), Some(&1));
+        assert_eq!(list.peek_right(), Some(&3));
+    }
+
+    #[test]
+    fn test_from_empty_vec() {
+        let list = LinkedList::from(Vec::<i32>::new());
+        assert!(list.is_empty());
+        assert!(list.tail.is_null());
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let list: LinkedList<i32> = (1..=4).collect();
+        assert_eq!(values(&list), vec![1, 2, 3, 4]);
+        assert_eq!(list.len(), 4);
+    }
+
+    // --- Push operation tests ---
+    #[test]
+    fn test_push_left_to_empty_list() {
+        let mut list = LinkedList::new();
+        list.push_left(1);
+        assert_eq!(list.head, list.tail);
+        assert_eq!(list.peek_left(), Some(&1));
+        assert_eq!(list.len(), 1);
+    }
+
+    #[test]
+    fn test_push_left_updates_head() {
+        let mut list = LinkedList::new();
+        list.push_left(1);
+        list.push_left(2);
+        assert_eq!(list.peek_left(), Some(&2));
+        assert_eq!(list.peek_right(), Some(&1));
+    }
+
+    #[test]
+    fn test_push_right_updates_tail() {
+        let mut list = LinkedList::new();
+        list.push_right(1);
+        list.push_right(2);
+        assert_eq!(list.peek_left(), Some(&1));
+        assert_eq!(list.peek_right(), Some(&2));
+        assert_eq!(list.len(), 2);
+    }
+
+    // --- Pop operation tests ---
+    #[test]
+    fn test_pop_left() {
+        let mut list = LinkedList::from(vec![1, 2, 3]);
+        assert_eq!(list.pop_left(), Some(1));
+        assert_eq!(list.pop_left(), Some(2));
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.pop_left(), Some(3));
+        assert_eq!(list.pop_left(), None);
+        assert!(list.head.is_null());
+        assert!(list.tail.is_null());
+        assert_eq!(list.len(), 0);
+    }
+
+    #[test]
+    fn test_pop_right() {
+        let mut list = LinkedList::from(vec![1, 2, 3]);
+        assert_eq!(list.pop_right(), Some(3));
+        assert_eq!(list.peek_right(), Some(&2));
+        assert_eq!(list.pop_right(), Some(2));
+        assert_eq!(list.pop_right(), Some(1));
+        assert_eq!(list.pop_right(), None);
+        assert!(list.head.is_null());
+        assert!(list.tail.is_null());
+    }
+
+    #[test]
+    fn test_push_after_emptying() {
+        let mut list = LinkedList::from(vec![1]);
+        assert_eq!(list.pop_right(), Some(1));
+        list.push_right(2);
+        list.push_left(1);
+        assert_eq!(values(&list), vec![1, 2]);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    // --- Iterator tests ---
+    #[test]
+    fn test_iter_mut() {
+        let mut list = LinkedList::from(vec![1, 2, 3]);
+        for val in list.iter_mut() {
+            *val *= 10;
+        }
+        for val in &mut list {
+            *val += 1;
+        }
+        assert_eq!(values(&list), vec![11, 21, 31]);
+        assert_eq!((&list).into_iter().count(), 3);
+        assert_eq!(LinkedList::<i32>::new().iter_mut().next(), None);
+    }
+
+    // --- Find operation tests ---
+    #[test]
+    fn test_find() {
+        let list = LinkedList::from(vec![1, 2, 3]);
+        assert_eq!(list.find(&1), Some(&1));
+        assert_eq!(list.find(&2), Some(&2));
+        assert_eq!(list.find(&3), Some(&3));
+        assert_eq!(list.find(&4), None);
+        assert!(list.contains(&2));
+        assert!(!LinkedList::new().contains(&2));
+    }
+
+    // --- Delete operation tests ---
+    #[test]
+    fn test_delete_from_empty_list() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        assert!(matches!(list.delete(&1), Err(DeleteError::NotFound)));
+    }
+
+    #[test]
+    fn test_delete_head_middle_and_tail() {
+        let mut list = LinkedList::from(vec![1, 2, 3, 4]);
+        assert!(matches!(list.delete(&5), Err(DeleteError::NotFound)));
+        assert!(matches!(list.delete(&1), Ok(1)));
+        assert_eq!(list.peek_left(), Some(&2));
+        assert!(matches!(list.delete(&3), Ok(3)));
+        assert!(matches!(list.delete(&4), Ok(4)));
+        assert_eq!(list.peek_right(), Some(&2));
+        assert_eq!(list.len(), 1);
+        assert!(matches!(list.delete(&2), Ok(2)));
+        assert!(list.is_empty());
+        assert!(list.tail.is_null());
+    }
+
+    // --- Mixed operation tests ---
+    #[test]
+    fn test_interleaved_operations() {
+        let mut list = LinkedList::new();
+        list.push_right(1);
+        list.push_right(2);
+        list.push_left(0); // 0, 1, 2
+        assert!(list.delete(&1).is_ok()); // 0, 2
+        list.push_right(3); // 0, 2, 3
+        assert_eq!(list.pop_right(), Some(3)); // 0, 2
+        assert_eq!(list.pop_left(), Some(0)); // 2
+        list.push_left(1); // 1, 2
+        assert_eq!(values(&list), vec![1, 2]);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    // --- In place algorithm tests ---
+    fn addresses<T>(list: &LinkedList<T>) -> Vec<*const T> {
+        list.iter().map(|val| val as *const T).collect()
+    }
+
+    #[test]
+    fn test_reverse() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        list.reverse();
+        assert_eq!(list.validate(), Ok(()));
+
+        list.push_right(1);
+        list.reverse();
+        assert_eq!(list.validate(), Ok(()));
+
+        for val in [2, 3, 4] {
+            list.push_right(val);
+        }
+        list.reverse();
+        assert_eq!(values(&list), vec![4, 3, 2, 1]);
+        assert_eq!(list.peek_right(), Some(&1));
+        assert_eq!(list.validate(), Ok(()));
+        list.push_right(0);
+        assert_eq!(list.pop_right(), Some(0));
+        assert_eq!(list.pop_right(), Some(1));
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut list = LinkedList::from(vec![5, 1, 4, 1, 5, 9, 2, 6, 5, 3]);
+        list.sort();
+        assert_eq!(values(&list), vec![1, 1, 2, 3, 4, 5, 5, 5, 6, 9]);
+        assert_eq!(list.len(), 10);
+        assert_eq!(list.validate(), Ok(()));
+
+        let mut list: LinkedList<i32> = LinkedList::new();
+        list.sort();
+        assert_eq!(list.validate(), Ok(()));
+        list.push_left(1);
+        list.sort();
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_sort_is_stable_and_moves_no_values() {
+        let mut list = LinkedList::from(vec![(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd'), (0, 'e'), (2, 'f')]);
+        let before = addresses(&list);
+        list.sort_by_key(|&(key, _)| key);
+        let order: String = list.iter().map(|&(_, tag)| tag).collect();
+        assert_eq!(order, "ebdacf");
+
+        // same nodes, relinked in the sorted order
+        let order = [4, 1, 3, 0, 2, 5];
+        assert_eq!(addresses(&list), order.iter().map(|&i| before[i]).collect::<Vec<_>>());
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_dedup() {
+        let mut list = LinkedList::from(vec![1, 1, 2, 3, 3, 3, 1, 4, 4]);
+        list.dedup();
+        assert_eq!(values(&list), vec![1, 2, 3, 1, 4]);
+        assert_eq!(list.len(), 5);
+        assert_eq!(list.peek_right(), Some(&4));
+        assert_eq!(list.validate(), Ok(()));
+
+        let mut list = LinkedList::from(vec![10, 11, 20, 25, 31, 12, 19]);
+        list.dedup_by_key(|val| *val / 10);
+        assert_eq!(values(&list), vec![10, 20, 31, 12]);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut list = LinkedList::from(vec![1, 4, 6]);
+        list.merge(LinkedList::from(vec![2, 3, 7, 8]));
+        assert_eq!(values(&list), vec![1, 2, 3, 4, 6, 7, 8]);
+        assert_eq!(list.len(), 7);
+        assert_eq!(list.validate(), Ok(()));
+
+        let mut list = LinkedList::from(vec![1, 9]);
+        list.merge(LinkedList::from(vec![2, 3]));
+        assert_eq!(list.peek_right(), Some(&9));
+        assert_eq!(list.validate(), Ok(()));
+
+        let mut list = LinkedList::new();
+        list.merge(LinkedList::from(vec![1, 2]));
+        list.merge(LinkedList::new());
+        assert_eq!(values(&list), vec![1, 2]);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_merge_is_stable() {
+        let mut list = LinkedList::from(vec![1, 2]);
+        let other = LinkedList::from(vec![1, 2]);
+        let mine = addresses(&list);
+        let theirs = addresses(&other);
+        list.merge(other);
+        assert_eq!(addresses(&list), vec![mine[0], theirs[0], mine[1], theirs[1]]);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    // --- Cycle detection tests ---
+    #[test]
+    fn test_has_cycle() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        assert!(!list.has_cycle());
+        list.push_right(1);
+        assert!(!list.has_cycle());
+        list.push_right(2);
+        list.push_right(3);
+        assert!(!list.has_cycle());
+
+        // a node pointing at itself
+        unsafe { (*list.tail).next = list.tail };
+        assert!(list.has_cycle());
+        assert_eq!(list.validate(), Err(InvariantError::Cycle { index: 2 }));
+
+        // the tail pointing back at the second node
+        unsafe { (*list.tail).next = (*list.head).next };
+        assert!(list.has_cycle());
+        assert_eq!(list.validate(), Err(InvariantError::Cycle { index: 1 }));
+
+        // the whole list as a ring
+        unsafe { (*list.tail).next = list.head };
+        assert_eq!(list.validate(), Err(InvariantError::Cycle { index: 0 }));
+
+        // break the loop again so the list can be dropped
+        unsafe { (*list.tail).next = ptr::null_mut() };
+        assert!(!list.has_cycle());
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    // --- Drop tests ---
+    #[test]
+    fn test_drop_on_list_with_items() {
+        let drop_count = RefCell::new(0);
+        {
+            let mut list = LinkedList::new();
+            for counter in counters(&[1, 2, 3], &drop_count) {
+                list.push_right(counter);
+            }
+            assert_eq!(*drop_count.borrow(), 0);
+        } // list drops here
+
+        assert_eq!(*drop_count.borrow(), 3);
+    }
+
+    #[test]
+    fn test_popped_values_are_dropped_once() {
+        let drop_count = RefCell::new(0);
+        let mut list: LinkedList<DropCounter> = counters(&[1, 2, 3, 4], &drop_count).into_iter().collect();
+
+        let left = list.pop_left().unwrap();
+        let right = list.pop_right().unwrap();
+        assert_eq!((left.val, right.val), (1, 4));
+        // moved out, not dropped yet
+        assert_eq!(*drop_count.borrow(), 0);
+        drop(left);
+        drop(right);
+        assert_eq!(*drop_count.borrow(), 2);
+
+        drop(list);
+        assert_eq!(*drop_count.borrow(), 4);
+    }
+
+    #[test]
+    fn test_deleted_values_are_dropped_once() {
+        let drop_count = RefCell::new(0);
+        let mut list: LinkedList<DropCounter> = counters(&[1, 2, 3], &drop_count).into_iter().collect();
+
+        let probe = DropCounter { val: 2, count: &drop_count };
+        let deleted = list.delete(&probe);
+        assert!(matches!(deleted, Ok(DropCounter { val: 2, .. })));
+        assert_eq!(*drop_count.borrow(), 0);
+        drop(deleted);
+        drop(probe);
+        assert_eq!(*drop_count.borrow(), 2);
+
+        drop(list);
+        assert_eq!(*drop_count.borrow(), 4);
+    }
+
+    #[test]
+    fn test_drop_owned_heap_values() {
+        // what used to leak: the Strings' buffers were never freed
+        let mut list = LinkedList::new();
+        for word in ["a", "bb", "ccc"] {
+            list.push_right(word.repeat(1000));
+        }
+        assert_eq!(list.pop_left().map(|word| word.len()), Some(1000));
+        assert_eq!(list.iter().map(String::len).sum::<usize>(), 5000);
+    }
+
+    #[test]
+    fn test_dedup_drops_removed_values_once() {
+        let drop_count = RefCell::new(0);
+        let mut list: LinkedList<DropCounter> = counters(&[1, 1, 2, 2, 2, 3], &drop_count).into_iter().collect();
+        list.dedup();
+        assert_eq!(*drop_count.borrow(), 3);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.validate(), Ok(()));
+        drop(list);
+        assert_eq!(*drop_count.borrow(), 6);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut list = LinkedList::from(vec![1, 2, 3]);
+        assert_eq!(list.validate(), Ok(()));
+        assert!(list.delete(&3).is_ok());
+        assert_eq!(list.validate(), Ok(()));
+        assert!(list.delete(&1).is_ok());
+        assert!(list.delete(&2).is_ok());
+        assert_eq!(list.validate(), Ok(()));
+
+        list.push_left(4);
+        list.push_left(5);
+        assert_eq!(list.validate(), Ok(()));
+        let tail = list.tail;
+        list.tail = list.head;
+        assert_eq!(list.validate(), Err(InvariantError::WrongTail));
+        list.tail = tail;
+
+        list.len = 5;
+        assert_eq!(list.validate(), Err(InvariantError::WrongLength { stored: 5, actual: 2 }));
+        list.len = 2;
+    }
 }
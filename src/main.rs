@@ -1,45 +1,1051 @@
-type Link<T> = Option<Box<Node<T>>>;
+use std::env;
+use std::fs::File;
+use std::hint::black_box;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process;
+use std::time::{Duration, Instant};
 
-#[derive(Debug, PartialEq)]
-pub struct Node<T> {
-    val: T,
-    left: Link<T>,
-    right: Link<T>,
-}
+use adt::avl_tree::AvlTree;
+use adt::b_tree::BTree;
+use adt::binary_search_tree::BinarySearchTree;
+use adt::binary_search_tree_with_arena;
+use adt::circular_queue::CircularQueue;
+use adt::doubly_linked_list;
+use adt::hashing::{AnagramMap, HashMap, OpenAddressingMap};
+use adt::linked_list_with_arena;
+use adt::linked_list_with_box;
+use adt::linked_list_with_pointer;
+use adt::priority_queue::PriorityQueue;
+use adt::queue::Queue;
+use adt::red_black_tree::RedBlackTree;
+use adt::splay_tree::SplayTree;
+use adt::stack_with_fixed_array;
+use adt::stack_with_vector;
+use adt::treap::Treap;
+use adt::xorshift::Xorshift;
 
-pub struct BinarySearchTree<T> {
-    root: Link<T>,
-}
+const USAGE: &str = "\
+usage:
+    adt anagrams <file> [--top <n>]   group the words of a file (one per line) by anagram
+    adt bench <structure|all> [<n>]   time n operations on a structure (default 100000)
+    adt rotations [<n>]               count the rotations avl and rb do on the same n values
+    adt skewed [<n>]                  time n lookups aimed mostly at a few hot values
+    adt repl <structure>              drive a structure interactively
+
+structures: stack, fixed-stack, queue, circular-queue, priority-queue, list-box,
+            list-pointer, list-double, list-arena, bst, bst-arena, avl, rb, btree, splay,
+            treap, hashmap, open-addressing";
+
+const STRUCTURES: [&str; 18] = [
+    "stack",
+    "fixed-stack",
+    "queue",
+    "circular-queue",
+    "priority-queue",
+    "list-box",
+    "list-pointer",
+    "list-double",
+    "list-arena",
+    "bst",
+    "bst-arena",
+    "avl",
+    "rb",
+    "btree",
+    "splay",
+    "treap",
+    "hashmap",
+    "open-addressing",
+];
+
+const FIXED_STACK_SIZE: usize = 16;
+
+const UNSUPPORTED: &str = "not supported by this structure";
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let result = match args.as_slice() {
+        ["anagrams", file] => anagrams(file, None),
+        ["anagrams", file, "--top", n] => parse_number(n).and_then(|n| anagrams(file, Some(n))),
+        ["bench", structure] => bench(structure, 100_000),
+        ["bench", structure, n] => parse_number(n).and_then(|n| bench(structure, n)),
+        ["rotations"] => rotations(100_000),
+        ["rotations", n] => parse_number(n).and_then(rotations),
+        ["skewed"] => skewed(100_000),
+        ["skewed", n] => parse_number(n).and_then(skewed),
+        ["repl", structure] => repl(structure),
+        _ => Err(String::from(USAGE)),
+    };
+
+    if let Err(message) = result {
+        eprintln!("{message}");
+        process::exit(1);
+    }
+}
+
+fn parse_number(text: &str) -> Result<usize, String> {
+    text.parse().map_err(|_| format!("not a number: {text}"))
 }
 
-impl<T> Default for BinarySearchTree<T> {
-    fn default() -> Self {
-        Self { root: None }
+// --- anagrams ---
+
+fn anagrams(path: &str, top: Option<usize>) -> Result<(), String> {
+    let file = File::open(path).map_err(|err| format!("cannot open {path}: {err}"))?;
+    let mut map = AnagramMap::new(1024);
+    let words = map
+        .insert_lines(BufReader::new(file))
+        .map_err(|err| format!("cannot read {path}: {err}"))?;
+
+    let groups = map.largest_groups(top.unwrap_or(usize::MAX));
+    for group in groups.iter() {
+        println!("{}", group.join(" "));
     }
+    eprintln!("{} words, {} groups of anagrams", words, map.group_count());
+    Ok(())
+}
+
+// --- bench ---
+
+// pseudo random values, the same ones on every run so the numbers can be compared
+fn workload(n: usize) -> Vec<i64> {
+    let mut rng = Xorshift::new(0x2545_F491_4F6C_DD1D);
+    (0..n).map(|_| (rng.next_u64() >> 1) as i64).collect()
+}
+
+fn timed(label: &str, n: usize, run: impl FnOnce()) {
+    let start = Instant::now();
+    run();
+    let elapsed = start.elapsed();
+    // a Duration only divides by u32, n can be bigger
+    let per_op = if n == 0 { Duration::ZERO } else { elapsed.div_f64(n as f64) };
+    println!("    {label:<8} {elapsed:>12.3?} total {per_op:>10.1?} per op");
 }
 
-impl<T> Drop for BinarySearchTree<T> {
-    fn drop(&mut self) {
-        let mut stack = Vec::new();
-        if let Some(root) = self.root.take() {
-            stack.push(root);
+fn bench(structure: &str, n: usize) -> Result<(), String> {
+    if structure == "all" {
+        for structure in STRUCTURES {
+            bench(structure, n)?;
         }
+        return Ok(());
+    }
 
-        while let Some(mut node) = stack.pop() {
-            if let Some(left_child) = node.left.take() {
-                stack.push(left_child);
-            }
-            if let Some(right_child) = node.right.take() {
-                stack.push(right_child);
+    let values = workload(n);
+    println!("{structure} ({n} values)");
+
+    match structure {
+        "stack" => {
+            let mut stack = stack_with_vector::Stack::new();
+            timed("push", n, || values.iter().for_each(|&v| stack.push(v)));
+            timed("pop", n, || (0..n).for_each(|_| { black_box(stack.pop()); }));
+        },
+        "fixed-stack" => {
+            // a fixed array can't hold the whole workload, so fill and empty it over and over
+            let mut stack: stack_with_fixed_array::Stack<i64, FIXED_STACK_SIZE> = stack_with_fixed_array::Stack::new();
+            timed("push+pop", n, || {
+                for &v in values.iter() {
+                    if stack.is_full() {
+                        while stack.pop().is_some() {}
+                    }
+                    stack.push(v).unwrap();
+                }
+            });
+        },
+        "queue" => {
+            let mut queue = Queue::new();
+            timed("enqueue", n, || values.iter().for_each(|&v| queue.enqueue(v)));
+            timed("dequeue", n, || (0..n).for_each(|_| { black_box(queue.dequeue()); }));
+        },
+        "circular-queue" => {
+            let mut queue = CircularQueue::new();
+            timed("enqueue", n, || values.iter().for_each(|&v| queue.enqueue(v)));
+            timed("dequeue", n, || (0..n).for_each(|_| { black_box(queue.dequeue()); }));
+        },
+        "priority-queue" => {
+            let mut queue = PriorityQueue::new();
+            timed("insert", n, || values.iter().for_each(|&v| queue.insert(v)));
+            timed("pop", n, || (0..n).for_each(|_| { black_box(queue.pop()); }));
+        },
+        "list-box" => {
+            // find and delete are linear, so only a slice of the workload is searched
+            let searched = n.min(1000);
+            let mut list = linked_list_with_box::LinkedList::new();
+            timed("push", n, || values.iter().for_each(|&v| list.push_right(v)));
+            timed("find", searched, || values[..searched].iter().for_each(|v| { black_box(list.find(v)); }));
+            timed("delete", searched, || values[..searched].iter().for_each(|v| { black_box(list.delete(v)); }));
+        },
+        "list-pointer" => {
+            let searched = n.min(1000);
+            let mut list = linked_list_with_pointer::LinkedList::new();
+            timed("push", n, || values.iter().for_each(|&v| list.push_right(v)));
+            timed("find", searched, || values[..searched].iter().for_each(|v| { black_box(list.find(v)); }));
+            timed("delete", searched, || values[..searched].iter().for_each(|v| { black_box(list.delete(v).is_ok()); }));
+        },
+        "list-double" => {
+            let mut list = doubly_linked_list::LinkedList::new();
+            timed("push", n, || values.iter().for_each(|&v| list.push_back(v)));
+            timed("pop back", n, || (0..n).for_each(|_| { black_box(list.pop_back()); }));
+            list.extend(values.iter().copied());
+            // one pass removing every other value, each removal is O(1) at the cursor
+            timed("remove", n / 2, || {
+                let mut cursor = list.cursor_front_mut();
+                while cursor.index().is_some() {
+                    black_box(cursor.remove_current());
+                    cursor.move_next();
+                }
+            });
+        },
+        "list-arena" => {
+            let searched = n.min(1000);
+            let mut list = linked_list_with_arena::LinkedList::new();
+            timed("push", n, || values.iter().for_each(|&v| { list.push_right(v); }));
+            timed("find", searched, || values[..searched].iter().for_each(|v| { black_box(list.find(v)); }));
+            timed("delete", searched, || values[..searched].iter().for_each(|v| { black_box(list.delete(v)); }));
+        },
+        "bst" => {
+            let mut tree = BinarySearchTree::new();
+            timed("insert", n, || values.iter().for_each(|&v| tree.insert(v)));
+            timed("search", n, || values.iter().for_each(|v| { black_box(tree.search(v)); }));
+            timed("delete", n, || values.iter().for_each(|v| { black_box(tree.delete(v)); }));
+        },
+        "bst-arena" => {
+            let mut tree = binary_search_tree_with_arena::BinarySearchTree::new();
+            timed("insert", n, || values.iter().for_each(|&v| { tree.insert(v); }));
+            timed("search", n, || values.iter().for_each(|v| { black_box(tree.search(v)); }));
+            timed("delete", n, || values.iter().for_each(|v| { black_box(tree.delete(v)); }));
+        },
+        "avl" => {
+            let mut tree = AvlTree::new();
+            timed("insert", n, || values.iter().for_each(|&v| tree.insert(v)));
+            timed("search", n, || values.iter().for_each(|v| { black_box(tree.search(v)); }));
+            timed("delete", n, || values.iter().for_each(|v| { black_box(tree.delete(v)); }));
+        },
+        "rb" => {
+            let mut tree = RedBlackTree::new();
+            timed("insert", n, || values.iter().for_each(|&v| tree.insert(v)));
+            timed("search", n, || values.iter().for_each(|v| { black_box(tree.search(v)); }));
+            timed("delete", n, || values.iter().for_each(|v| { black_box(tree.delete(v)); }));
+        },
+        "btree" => {
+            let mut tree: BTree<i64> = BTree::new();
+            timed("insert", n, || values.iter().for_each(|&v| tree.insert(v)));
+            timed("search", n, || values.iter().for_each(|v| { black_box(tree.search(v)); }));
+            timed("delete", n, || values.iter().for_each(|v| { black_box(tree.delete(v)); }));
+        },
+        "splay" => {
+            let mut tree = SplayTree::new();
+            timed("insert", n, || values.iter().for_each(|&v| tree.insert(v)));
+            timed("search", n, || values.iter().for_each(|v| { black_box(tree.search(v)); }));
+            timed("delete", n, || values.iter().for_each(|v| { black_box(tree.delete(v)); }));
+        },
+        "treap" => {
+            let mut tree = Treap::new();
+            timed("insert", n, || values.iter().for_each(|&v| tree.insert(v)));
+            timed("search", n, || values.iter().for_each(|v| { black_box(tree.search(v)); }));
+            timed("delete", n, || values.iter().for_each(|v| { black_box(tree.delete(v)); }));
+        },
+        "hashmap" => {
+            let mut map = HashMap::new(0);
+            timed("insert", n, || values.iter().for_each(|&v| { map.insert(v, v); }));
+            timed("get", n, || values.iter().for_each(|v| { black_box(map.get(v)); }));
+            timed("remove", n, || values.iter().for_each(|v| { black_box(map.remove(v)); }));
+        },
+        "open-addressing" => {
+            let mut map = OpenAddressingMap::new(0);
+            timed("insert", n, || values.iter().for_each(|&v| { map.insert(v, v); }));
+            timed("get", n, || values.iter().for_each(|v| { black_box(map.get(v)); }));
+            timed("remove", n, || values.iter().for_each(|v| { black_box(map.remove(v)); }));
+        },
+        _ => return Err(format!("unknown structure: {structure}\n\n{USAGE}")),
+    }
+    Ok(())
+}
+
+// --- rotations ---
+
+// Rotations done by each balanced tree inserting every value and then deleting them in the same
+// order: (tree, insert rotations, delete rotations)
+fn count_rotations(values: &[i64]) -> [(&'static str, usize, usize); 2] {
+    let mut avl = AvlTree::new();
+    let avl_inserts = values.iter().map(|&v| avl.insert_counted(v)).sum();
+    let avl_deletes = values.iter().map(|v| avl.delete_counted(v).1).sum();
+
+    let mut rb = RedBlackTree::new();
+    let rb_inserts = values.iter().map(|&v| rb.insert_counted(v)).sum();
+    let rb_deletes = values.iter().map(|v| rb.delete_counted(v).1).sum();
+
+    [("avl", avl_inserts, avl_deletes), ("rb", rb_inserts, rb_deletes)]
+}
+
+fn rotations(n: usize) -> Result<(), String> {
+    let values = workload(n);
+    println!("rotations ({n} values)");
+    for (tree, inserts, deletes) in count_rotations(&values) {
+        let per_op = |total: usize| if n == 0 { 0.0 } else { total as f64 / n as f64 };
+        println!(
+            "    {tree:<4} insert {inserts:>9} total {:>6.3} per op    delete {deletes:>9} total {:>6.3} per op",
+            per_op(inserts),
+            per_op(deletes),
+        );
+    }
+    Ok(())
+}
+
+// --- skewed ---
+
+// Lookups where nine out of ten go to the first 1% of the values, the kind of traffic where a
+// self adjusting tree should pay off
+fn skewed_lookups(values: &[i64], n: usize) -> Vec<i64> {
+    if values.is_empty() {
+        return Vec::new();
+    }
+    let hot = (values.len() / 100).max(1);
+    let mut rng = Xorshift::new(0x9E37_79B9_7F4A_7C15);
+    (0..n)
+        .map(|_| {
+            let pick = (rng.next_u64() >> 1) as usize;
+            let range = if pick.is_multiple_of(10) { values.len() } else { hot };
+            values[(pick / 10) % range]
+        })
+        .collect()
+}
+
+fn skewed(n: usize) -> Result<(), String> {
+    let values = workload(n);
+    let lookups = skewed_lookups(&values, n);
+    println!("skewed lookups ({n} values, {n} lookups, 90% on 1% of the values)");
+
+    let mut avl = AvlTree::new();
+    values.iter().for_each(|&v| avl.insert(v));
+    timed("avl", n, || lookups.iter().for_each(|v| { black_box(avl.search(v)); }));
+
+    let rb: RedBlackTree<i64> = values.iter().copied().collect();
+    timed("rb", n, || lookups.iter().for_each(|v| { black_box(rb.search(v)); }));
+
+    let mut splay: SplayTree<i64> = values.iter().copied().collect();
+    timed("splay", n, || lookups.iter().for_each(|v| { black_box(splay.search(v)); }));
+
+    let treap: Treap<i64> = values.iter().copied().collect();
+    timed("treap", n, || lookups.iter().for_each(|v| { black_box(treap.search(v)); }));
+    Ok(())
+}
+
+// --- repl ---
+
+// The REPL commands every structure understands. Each one maps them to its own operations,
+// push is insert/enqueue and pop removes what the structure would hand out next
+trait Driver {
+    fn push(&mut self, val: i64) -> Result<(), &'static str>;
+
+    fn pop(&mut self) -> Result<Option<i64>, &'static str> {
+        Err(UNSUPPORTED)
+    }
+
+    fn peek(&self) -> Result<Option<i64>, &'static str> {
+        Err(UNSUPPORTED)
+    }
+
+    // &mut self because looking a value up can reorganize the structure, the splay tree does
+    fn find(&mut self, _val: i64) -> Result<bool, &'static str> {
+        Err(UNSUPPORTED)
+    }
+
+    fn delete(&mut self, _val: i64) -> Result<bool, &'static str> {
+        Err(UNSUPPORTED)
+    }
+
+    fn print(&self) -> String;
+
+    // the shape of the structure, for the trees
+    fn draw(&self) -> Result<String, &'static str> {
+        Err(UNSUPPORTED)
+    }
+}
+
+impl Driver for stack_with_vector::Stack<i64> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        stack_with_vector::Stack::push(self, val);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Option<i64>, &'static str> {
+        Ok(stack_with_vector::Stack::pop(self))
+    }
+
+    fn peek(&self) -> Result<Option<i64>, &'static str> {
+        Ok(stack_with_vector::Stack::peek(self).copied())
+    }
+
+    fn print(&self) -> String {
+        format!("{} values, top: {:?}", self.len(), stack_with_vector::Stack::peek(self))
+    }
+}
+
+impl Driver for stack_with_fixed_array::Stack<i64, FIXED_STACK_SIZE> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        stack_with_fixed_array::Stack::push(self, val)
+    }
+
+    fn pop(&mut self) -> Result<Option<i64>, &'static str> {
+        Ok(stack_with_fixed_array::Stack::pop(self))
+    }
+
+    fn peek(&self) -> Result<Option<i64>, &'static str> {
+        Ok(stack_with_fixed_array::Stack::peek(self).copied())
+    }
+
+    fn print(&self) -> String {
+        format!("{}/{} values, top: {:?}", self.len(), self.capacity(), stack_with_fixed_array::Stack::peek(self))
+    }
+}
+
+impl Driver for Queue<i64> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        self.enqueue(val);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Option<i64>, &'static str> {
+        Ok(self.dequeue())
+    }
+
+    fn peek(&self) -> Result<Option<i64>, &'static str> {
+        Ok(Queue::peek(self).copied())
+    }
+
+    fn print(&self) -> String {
+        format!("{} values, front: {:?}", self.len(), Queue::peek(self))
+    }
+}
+
+impl Driver for CircularQueue<i64> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        self.enqueue(val);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Option<i64>, &'static str> {
+        Ok(self.dequeue())
+    }
+
+    fn peek(&self) -> Result<Option<i64>, &'static str> {
+        Ok(CircularQueue::peek(self).copied())
+    }
+
+    fn print(&self) -> String {
+        format!("{}/{} values, front: {:?}", self.len(), self.capacity(), CircularQueue::peek(self))
+    }
+}
+
+impl Driver for PriorityQueue<i64> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        self.insert(val);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Option<i64>, &'static str> {
+        Ok(PriorityQueue::pop(self))
+    }
+
+    fn print(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+impl Driver for linked_list_with_box::LinkedList<i64> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        self.push_right(val);
+        Ok(())
+    }
+
+    // values come out in the order they were pushed, like a queue
+    fn pop(&mut self) -> Result<Option<i64>, &'static str> {
+        Ok(self.pop_left())
+    }
+
+    fn peek(&self) -> Result<Option<i64>, &'static str> {
+        Ok(self.peek_left().copied())
+    }
+
+    fn find(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(linked_list_with_box::LinkedList::find(self, &val).is_some())
+    }
+
+    fn delete(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(linked_list_with_box::LinkedList::delete(self, &val).is_some())
+    }
+
+    fn print(&self) -> String {
+        format!("{:?} len {}", self.iter().collect::<Vec<_>>(), self.len())
+    }
+}
+
+impl Driver for linked_list_with_pointer::LinkedList<i64> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        self.push_right(val);
+        Ok(())
+    }
+
+    // values come out in the order they were pushed, like a queue
+    fn pop(&mut self) -> Result<Option<i64>, &'static str> {
+        Ok(self.pop_left())
+    }
+
+    fn peek(&self) -> Result<Option<i64>, &'static str> {
+        Ok(self.peek_left().copied())
+    }
+
+    fn find(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(self.contains(&val))
+    }
+
+    fn delete(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(linked_list_with_pointer::LinkedList::delete(self, &val).is_ok())
+    }
+
+    fn print(&self) -> String {
+        format!("{:?} len {}", self.iter().collect::<Vec<_>>(), self.len())
+    }
+}
+
+impl Driver for linked_list_with_arena::LinkedList<i64> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        self.push_right(val);
+        Ok(())
+    }
+
+    // values come out in the order they were pushed, like a queue
+    fn pop(&mut self) -> Result<Option<i64>, &'static str> {
+        Ok(self.pop_left())
+    }
+
+    fn peek(&self) -> Result<Option<i64>, &'static str> {
+        Ok(self.peek_left().copied())
+    }
+
+    fn find(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(self.contains(&val))
+    }
+
+    fn delete(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(linked_list_with_arena::LinkedList::delete(self, &val).is_some())
+    }
+
+    fn print(&self) -> String {
+        format!("{:?} len {}", self.iter().collect::<Vec<_>>(), self.len())
+    }
+}
+
+impl Driver for doubly_linked_list::LinkedList<i64> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        self.push_back(val);
+        Ok(())
+    }
+
+    // values come out in the order they were pushed, like a queue
+    fn pop(&mut self) -> Result<Option<i64>, &'static str> {
+        Ok(self.pop_front())
+    }
+
+    fn peek(&self) -> Result<Option<i64>, &'static str> {
+        Ok(self.front().copied())
+    }
+
+    fn find(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(self.contains(&val))
+    }
+
+    fn delete(&mut self, val: i64) -> Result<bool, &'static str> {
+        let mut cursor = self.cursor_front_mut();
+        while let Some(&mut current) = cursor.current() {
+            if current == val {
+                return Ok(cursor.remove_current().is_some());
             }
+            cursor.move_next();
         }
+        Ok(false)
+    }
+
+    fn print(&self) -> String {
+        format!("{self:?} len {}", self.len())
     }
 }
 
-impl<T> BinarySearchTree<T> {
-    pub fn new() -> Self {
-        Self::default()
+impl Driver for BinarySearchTree<i64> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        self.insert(val);
+        Ok(())
+    }
+
+    // pop hands out the smallest value, like a priority queue
+    fn pop(&mut self) -> Result<Option<i64>, &'static str> {
+        let min = self.min().copied();
+        Ok(min.and_then(|min| BinarySearchTree::delete(self, &min)))
+    }
+
+    fn peek(&self) -> Result<Option<i64>, &'static str> {
+        Ok(self.min().copied())
+    }
+
+    fn find(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(self.search(&val).is_some())
+    }
+
+    fn delete(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(BinarySearchTree::delete(self, &val).is_some())
+    }
+
+    fn print(&self) -> String {
+        format!("{:?}", self.iter().collect::<Vec<_>>())
+    }
+
+    fn draw(&self) -> Result<String, &'static str> {
+        Ok(self.to_string().trim_end().to_string())
+    }
+}
+
+impl Driver for binary_search_tree_with_arena::BinarySearchTree<i64> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        self.insert(val);
+        Ok(())
+    }
+
+    // pop hands out the smallest value, like a priority queue
+    fn pop(&mut self) -> Result<Option<i64>, &'static str> {
+        let min = self.min().copied();
+        Ok(min.and_then(|min| binary_search_tree_with_arena::BinarySearchTree::delete(self, &min)))
+    }
+
+    fn peek(&self) -> Result<Option<i64>, &'static str> {
+        Ok(self.min().copied())
+    }
+
+    fn find(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(self.contains(&val))
+    }
+
+    fn delete(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(binary_search_tree_with_arena::BinarySearchTree::delete(self, &val).is_some())
+    }
+
+    fn print(&self) -> String {
+        format!("{:?}", self.iter().collect::<Vec<_>>())
+    }
+
+    fn draw(&self) -> Result<String, &'static str> {
+        Ok(self.to_string().trim_end().to_string())
+    }
+}
+
+impl Driver for AvlTree<i64> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        self.insert(val);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Option<i64>, &'static str> {
+        let min = self.min().copied();
+        Ok(min.and_then(|min| AvlTree::delete(self, &min)))
+    }
+
+    fn peek(&self) -> Result<Option<i64>, &'static str> {
+        Ok(self.min().copied())
+    }
+
+    fn find(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(self.contains(&val))
+    }
+
+    fn delete(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(AvlTree::delete(self, &val).is_some())
+    }
+
+    fn print(&self) -> String {
+        format!("{:?} height {}", self.iter().collect::<Vec<_>>(), self.height())
+    }
+
+    fn draw(&self) -> Result<String, &'static str> {
+        Ok(self.to_string().trim_end().to_string())
+    }
+}
+
+impl Driver for RedBlackTree<i64> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        self.insert(val);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Option<i64>, &'static str> {
+        let min = self.min().copied();
+        Ok(min.and_then(|min| RedBlackTree::delete(self, &min)))
+    }
+
+    fn peek(&self) -> Result<Option<i64>, &'static str> {
+        Ok(self.min().copied())
+    }
+
+    fn find(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(self.contains(&val))
+    }
+
+    fn delete(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(RedBlackTree::delete(self, &val).is_some())
+    }
+
+    fn print(&self) -> String {
+        format!("{:?}", self.iter().collect::<Vec<_>>())
+    }
+
+    fn draw(&self) -> Result<String, &'static str> {
+        Ok(self.to_string().trim_end().to_string())
+    }
+}
+
+impl Driver for BTree<i64> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        self.insert(val);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Option<i64>, &'static str> {
+        let min = self.min().copied();
+        Ok(min.and_then(|min| BTree::delete(self, &min)))
+    }
+
+    fn peek(&self) -> Result<Option<i64>, &'static str> {
+        Ok(self.min().copied())
+    }
+
+    fn find(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(self.contains(&val))
+    }
+
+    fn delete(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(BTree::delete(self, &val).is_some())
+    }
+
+    fn print(&self) -> String {
+        format!("{:?} height {}", self.iter().collect::<Vec<_>>(), self.height())
+    }
+}
+
+impl Driver for SplayTree<i64> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        self.insert(val);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Option<i64>, &'static str> {
+        let min = self.min().copied();
+        Ok(min.and_then(|min| SplayTree::delete(self, &min)))
+    }
+
+    fn peek(&self) -> Result<Option<i64>, &'static str> {
+        Ok(self.min().copied())
+    }
+
+    fn find(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(self.contains(&val))
+    }
+
+    fn delete(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(SplayTree::delete(self, &val).is_some())
+    }
+
+    fn print(&self) -> String {
+        format!("{:?} height {}", self.iter().collect::<Vec<_>>(), self.height())
+    }
+}
+
+impl Driver for Treap<i64> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        self.insert(val);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Option<i64>, &'static str> {
+        let min = self.min().copied();
+        Ok(min.and_then(|min| Treap::delete(self, &min)))
+    }
+
+    fn peek(&self) -> Result<Option<i64>, &'static str> {
+        Ok(self.min().copied())
+    }
+
+    fn find(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(self.contains(&val))
+    }
+
+    fn delete(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(Treap::delete(self, &val).is_some())
+    }
+
+    fn print(&self) -> String {
+        format!("{:?} height {}", self.iter().collect::<Vec<_>>(), self.height())
+    }
+}
+
+impl Driver for HashMap<i64, ()> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        self.insert(val, ());
+        Ok(())
+    }
+
+    fn find(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(self.contains_key(&val))
+    }
+
+    fn delete(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(self.remove(&val).is_some())
+    }
+
+    fn print(&self) -> String {
+        let mut keys: Vec<&i64> = self.iter().map(|(key, _)| key).collect();
+        keys.sort();
+        format!("{keys:?} load factor {:.2}", self.load_factor())
+    }
+}
+
+impl Driver for OpenAddressingMap<i64, ()> {
+    fn push(&mut self, val: i64) -> Result<(), &'static str> {
+        self.insert(val, ());
+        Ok(())
+    }
+
+    fn find(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(self.contains_key(&val))
+    }
+
+    fn delete(&mut self, val: i64) -> Result<bool, &'static str> {
+        Ok(self.remove(&val).is_some())
+    }
+
+    fn print(&self) -> String {
+        let mut keys: Vec<&i64> = self.iter().map(|(key, _)| key).collect();
+        keys.sort();
+        format!("{keys:?} load factor {:.2}", self.load_factor())
+    }
+}
+
+fn new_driver(structure: &str) -> Option<Box<dyn Driver>> {
+    let driver: Box<dyn Driver> = match structure {
+        "stack" => Box::new(stack_with_vector::Stack::<i64>::new()),
+        "fixed-stack" => Box::new(stack_with_fixed_array::Stack::<i64, FIXED_STACK_SIZE>::new()),
+        "queue" => Box::new(Queue::<i64>::new()),
+        "circular-queue" => Box::new(CircularQueue::<i64>::new()),
+        "priority-queue" => Box::new(PriorityQueue::<i64>::new()),
+        "list-box" => Box::new(linked_list_with_box::LinkedList::<i64>::new()),
+        "list-pointer" => Box::new(linked_list_with_pointer::LinkedList::<i64>::new()),
+        "list-double" => Box::new(doubly_linked_list::LinkedList::<i64>::new()),
+        "list-arena" => Box::new(linked_list_with_arena::LinkedList::<i64>::new()),
+        "bst" => Box::new(BinarySearchTree::<i64>::new()),
+        "bst-arena" => Box::new(binary_search_tree_with_arena::BinarySearchTree::<i64>::new()),
+        "avl" => Box::new(AvlTree::<i64>::new()),
+        "rb" => Box::new(RedBlackTree::<i64>::new()),
+        "btree" => Box::new(BTree::<i64>::new()),
+        "splay" => Box::new(SplayTree::<i64>::new()),
+        "treap" => Box::new(Treap::<i64>::new()),
+        "hashmap" => Box::new(HashMap::<i64, ()>::new(0)),
+        "open-addressing" => Box::new(OpenAddressingMap::<i64, ()>::new(0)),
+        _ => return None,
+    };
+    Some(driver)
+}
+
+const REPL_HELP: &str = "\
+commands: push <n>, pop, peek, find <n>, delete <n>, print, draw, help, quit";
+
+// runs one line of the REPL, returns what to print
+fn execute(driver: &mut dyn Driver, line: &str) -> Result<String, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let number = |text: &str| text.parse::<i64>().map_err(|_| format!("not a number: {text}"));
+
+    let output = match words.as_slice() {
+        ["push", val] => driver.push(number(val)?).map(|_| String::from("ok")),
+        ["pop"] => driver.pop().map(|val| format!("{val:?}")),
+        ["peek"] => driver.peek().map(|val| format!("{val:?}")),
+        ["find", val] => driver.find(number(val)?).map(|found| found.to_string()),
+        ["delete", val] => driver.delete(number(val)?).map(|deleted| deleted.to_string()),
+        ["print"] => Ok(driver.print()),
+        ["draw"] => driver.draw(),
+        ["help"] => Ok(String::from(REPL_HELP)),
+        _ => return Err(format!("unknown command: {line}\n{REPL_HELP}")),
+    };
+    output.map_err(String::from)
+}
+
+fn repl(structure: &str) -> Result<(), String> {
+    let mut driver = new_driver(structure).ok_or_else(|| format!("unknown structure: {structure}\n\n{USAGE}"))?;
+    println!("{structure}: {REPL_HELP}");
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("> ");
+        io::stdout().flush().map_err(|err| err.to_string())?;
+
+        let Some(line) = lines.next() else {
+            return Ok(()); // end of input
+        };
+        let line = line.map_err(|err| err.to_string())?;
+        let line = line.trim();
+
+        match line {
+            "" => continue,
+            "quit" | "exit" => return Ok(()),
+            _ => match execute(driver.as_mut(), line) {
+                Ok(output) => println!("{output}"),
+                Err(message) => println!("error: {message}"),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_structure_has_a_driver() {
+        for structure in STRUCTURES {
+            assert!(new_driver(structure).is_some(), "no driver for {structure}");
+        }
+        assert!(new_driver("unknown").is_none());
+    }
+
+    #[test]
+    fn test_execute_stack_commands() {
+        let mut driver = new_driver("stack").unwrap();
+        assert_eq!(execute(driver.as_mut(), "push 3"), Ok(String::from("ok")));
+        assert_eq!(execute(driver.as_mut(), "push 4"), Ok(String::from("ok")));
+        assert_eq!(execute(driver.as_mut(), "peek"), Ok(String::from("Some(4)")));
+        assert_eq!(execute(driver.as_mut(), "pop"), Ok(String::from("Some(4)")));
+        assert_eq!(execute(driver.as_mut(), "print"), Ok(String::from("1 values, top: Some(3)")));
+    }
+
+    #[test]
+    fn test_execute_tree_commands() {
+        let mut driver = new_driver("bst").unwrap();
+        for line in ["push 5", "push 2", "push 8"] {
+            execute(driver.as_mut(), line).unwrap();
+        }
+        assert_eq!(execute(driver.as_mut(), "find 2"), Ok(String::from("true")));
+        assert_eq!(execute(driver.as_mut(), "delete 2"), Ok(String::from("true")));
+        assert_eq!(execute(driver.as_mut(), "pop"), Ok(String::from("Some(5)")));
+        assert_eq!(execute(driver.as_mut(), "print"), Ok(String::from("[8]")));
+
+        let mut driver = new_driver("avl").unwrap();
+        for line in ["push 5", "push 2", "push 8"] {
+            execute(driver.as_mut(), line).unwrap();
+        }
+        assert_eq!(execute(driver.as_mut(), "draw"), Ok(String::from("/-- 8 (h=1, bf=0)\n5 (h=2, bf=0)\n\\-- 2 (h=1, bf=0)")));
+    }
+
+    #[test]
+    fn test_execute_bst_arena_commands() {
+        // the arena tree answers like the box one
+        let mut driver = new_driver("bst-arena").unwrap();
+        for line in ["push 5", "push 2", "push 8"] {
+            execute(driver.as_mut(), line).unwrap();
+        }
+        assert_eq!(execute(driver.as_mut(), "draw"), Ok(String::from("/-- 8\n5\n\\-- 2")));
+        assert_eq!(execute(driver.as_mut(), "delete 2"), Ok(String::from("true")));
+        assert_eq!(execute(driver.as_mut(), "pop"), Ok(String::from("Some(5)")));
+        assert_eq!(execute(driver.as_mut(), "print"), Ok(String::from("[8]")));
+    }
+
+    #[test]
+    fn test_execute_list_commands() {
+        let mut driver = new_driver("list-box").unwrap();
+        for line in ["push 1", "push 2", "push 3"] {
+            execute(driver.as_mut(), line).unwrap();
+        }
+        assert_eq!(execute(driver.as_mut(), "peek"), Ok(String::from("Some(1)")));
+        assert_eq!(execute(driver.as_mut(), "pop"), Ok(String::from("Some(1)")));
+        assert_eq!(execute(driver.as_mut(), "print"), Ok(String::from("[2, 3] len 2")));
+
+        let mut driver = new_driver("list-double").unwrap();
+        for line in ["push 1", "push 2", "push 3"] {
+            execute(driver.as_mut(), line).unwrap();
+        }
+        assert_eq!(execute(driver.as_mut(), "delete 2"), Ok(String::from("true")));
+        assert_eq!(execute(driver.as_mut(), "delete 2"), Ok(String::from("false")));
+        assert_eq!(execute(driver.as_mut(), "pop"), Ok(String::from("Some(1)")));
+        assert_eq!(execute(driver.as_mut(), "print"), Ok(String::from("[3] len 1")));
+    }
+
+    #[test]
+    fn test_execute_list_arena_commands() {
+        let mut driver = new_driver("list-arena").unwrap();
+        for line in ["push 1", "push 2", "push 3"] {
+            execute(driver.as_mut(), line).unwrap();
+        }
+        assert_eq!(execute(driver.as_mut(), "delete 2"), Ok(String::from("true")));
+        assert_eq!(execute(driver.as_mut(), "pop"), Ok(String::from("Some(1)")));
+        assert_eq!(execute(driver.as_mut(), "print"), Ok(String::from("[3] len 1")));
+    }
+
+    #[test]
+    fn test_execute_errors() {
+        let mut driver = new_driver("queue").unwrap();
+        assert!(execute(driver.as_mut(), "push x").is_err());
+        assert!(execute(driver.as_mut(), "jump").is_err());
+        assert_eq!(execute(driver.as_mut(), "find 1"), Err(String::from(UNSUPPORTED)));
+
+        let mut driver = new_driver("fixed-stack").unwrap();
+        for i in 0..FIXED_STACK_SIZE {
+            execute(driver.as_mut(), &format!("push {i}")).unwrap();
+        }
+        assert_eq!(execute(driver.as_mut(), "push 1"), Err(String::from("Stack is full.")));
+    }
+
+    #[test]
+    fn test_bench_runs_every_structure() {
+        assert!(bench("all", 100).is_ok());
+        assert!(bench("unknown", 100).is_err());
+    }
+
+    #[test]
+    fn test_count_rotations() {
+        let values = workload(1000);
+        let [avl, rb] = count_rotations(&values);
+        assert_eq!((avl.0, rb.0), ("avl", "rb"));
+        // a red-black insert rotates at most twice and a delete at most three times
+        assert!(rb.1 <= 2 * values.len());
+        assert!(rb.2 <= 3 * values.len());
+        assert!(avl.1 > 0 && avl.2 > 0);
+        // sorted input is where the trees rotate the most
+        let [avl, rb] = count_rotations(&[1, 2, 3]);
+        assert_eq!((avl.1, rb.1), (1, 1));
+    }
+
+    #[test]
+    fn test_skewed_lookups() {
+        let values: Vec<i64> = (0..1000).collect();
+        let lookups = skewed_lookups(&values, 10_000);
+        assert_eq!(lookups.len(), 10_000);
+        let hot = lookups.iter().filter(|&&val| val < 10).count();
+        assert!(hot > 8_500, "{hot} hot lookups");
+        assert!(skewed_lookups(&[], 10).is_empty());
+        assert!(skewed(100).is_ok());
+    }
+
+    #[test]
+    fn test_execute_splay_find_moves_to_root() {
+        let mut driver = new_driver("splay").unwrap();
+        for line in ["push 1", "push 2", "push 3"] {
+            execute(driver.as_mut(), line).unwrap();
+        }
+        assert_eq!(execute(driver.as_mut(), "print"), Ok(String::from("[1, 2, 3] height 3")));
+        assert_eq!(execute(driver.as_mut(), "find 1"), Ok(String::from("true")));
+        assert_eq!(execute(driver.as_mut(), "print"), Ok(String::from("[1, 2, 3] height 3")));
+        assert_eq!(execute(driver.as_mut(), "find 2"), Ok(String::from("true")));
+        assert_eq!(execute(driver.as_mut(), "print"), Ok(String::from("[1, 2, 3] height 2")));
+    }
+
+    #[test]
+    fn test_workload_is_deterministic() {
+        assert_eq!(workload(10), workload(10));
+        assert_eq!(workload(0), Vec::<i64>::new());
     }
 }
@@ -1,3 +1,5 @@
+use crate::invariant::InvariantError;
+
 #[derive(PartialEq, Eq, Debug)]
 pub struct PriorityQueue<T> {
 	vals: Vec<T>,
@@ -76,6 +78,17 @@ impl<T: PartialOrd> PriorityQueue<T> {
         }
     }
 
+    // Checks the heap order: no value is greater than its children
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        for child in 1..self.vals.len() {
+            let parent = self.parent(child).unwrap();
+            if self.vals[parent] > self.vals[child] {
+                return Err(InvariantError::HeapOrder { parent, child });
+            }
+        }
+        Ok(())
+    }
+
     pub fn pop(&mut self) -> Option<T> { // extract_min
         if self.vals.len() <= 1 {
             return self.vals.pop();
@@ -164,5 +177,16 @@ mod tests {
         assert_eq!(pq.pop(), Some(20));
         assert_eq!(pq.pop(), None);
     }
-}
 
+    #[test]
+    fn test_validate() {
+        let mut pq = PriorityQueue::from_vec(vec![9, 4, 7, 1, 8, 2]);
+        assert_eq!(pq.validate(), Ok(()));
+        pq.pop();
+        pq.insert(0);
+        assert_eq!(pq.validate(), Ok(()));
+
+        let broken = PriorityQueue { vals: vec![1, 5, 3, 4] };
+        assert_eq!(broken.validate(), Err(InvariantError::HeapOrder { parent: 1, child: 3 }));
+    }
+}
@@ -1,4 +1,5 @@
 use std::ptr;
+use crate::invariant::InvariantError;
 
 type Link<T> = Option<Box<Node<T>>>;
 
@@ -70,6 +71,26 @@ impl<T> Queue<T> {
         self.len += 1;
     }
 
+    // Checks that len counts the nodes and tail points at the last one (null when empty)
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        let mut actual = 0;
+        let mut last: *const Node<T> = ptr::null();
+        let mut current_link = self.head.as_deref();
+        while let Some(node) = current_link {
+            actual += 1;
+            last = node;
+            current_link = node.next.as_deref();
+        }
+
+        if actual != self.len {
+            return Err(InvariantError::WrongLength { stored: self.len, actual });
+        }
+        if !ptr::eq(last, self.tail) {
+            return Err(InvariantError::WrongTail);
+        }
+        Ok(())
+    }
+
     pub fn dequeue(&mut self) -> Option<T> {
         let removed_node = self.head.take()?;
         self.head = removed_node.next;
@@ -86,7 +107,7 @@ impl<T> Queue<T> {
 
 #[cfg(test)]
 mod tests {
-    use super::Queue;
+    use super::*;
 
     #[test]
     fn test_new_queue_is_empty() {
@@ -174,4 +195,24 @@ mod tests {
         assert_eq!(q.dequeue(), Some(30));
         assert!(q.is_empty());
     }
+
+    #[test]
+    fn test_validate() {
+        let mut q = Queue::new();
+        assert_eq!(q.validate(), Ok(()));
+        q.enqueue(1);
+        q.enqueue(2);
+        q.dequeue();
+        assert_eq!(q.validate(), Ok(()));
+        q.dequeue();
+        assert_eq!(q.validate(), Ok(()));
+
+        q.enqueue(3);
+        q.len = 2;
+        assert_eq!(q.validate(), Err(InvariantError::WrongLength { stored: 2, actual: 1 }));
+        q.len = 1;
+        q.tail = ptr::null_mut();
+        assert_eq!(q.validate(), Err(InvariantError::WrongTail));
+        q.tail = &mut **q.head.as_mut().unwrap();
+    }
 }
@@ -0,0 +1,827 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::ptr;
+
+use crate::invariant::InvariantError;
+use crate::tree_fmt;
+
+// Red-black tree: every node is red or black, the root is black, a red node never has a red
+// child and every path from a node down to a missing child crosses the same number of black
+// nodes. That keeps the height under 2 * log2(n + 1), a bit taller than an AVL tree, but the
+// balancing is cheaper: at most two rotations per insert and three per delete.
+// It's the classic version with parent pointers (Cormen et al.), the nodes are raw pointers and
+// a null pointer plays the black nil leaf.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Black,
+}
+
+struct Node<T> {
+    val: T,
+    color: Color,
+    parent: *mut Node<T>,
+    left: *mut Node<T>,
+    right: *mut Node<T>,
+}
+
+pub struct RedBlackTree<T> {
+    root: *mut Node<T>,
+    len: usize,
+    _marker: PhantomData<Box<Node<T>>>, // the tree owns its nodes
+}
+
+// the nodes are only reachable through the tree, so it can move between threads like a Box
+unsafe impl<T: Send> Send for RedBlackTree<T> {}
+unsafe impl<T: Sync> Sync for RedBlackTree<T> {}
+
+impl<T> Default for RedBlackTree<T> {
+    fn default() -> Self {
+        RedBlackTree { root: ptr::null_mut(), len: 0, _marker: PhantomData }
+    }
+}
+
+impl<T> Drop for RedBlackTree<T> {
+    fn drop(&mut self) {
+        let mut stack = vec![self.root];
+        while let Some(node) = stack.pop() {
+            if node.is_null() {
+                continue;
+            }
+            let node = unsafe { Box::from_raw(node) };
+            stack.push(node.left);
+            stack.push(node.right);
+        }
+    }
+}
+
+// null is a black leaf
+fn is_red<T>(node: *const Node<T>) -> bool {
+    unsafe { node.as_ref().is_some_and(|node| node.color == Color::Red) }
+}
+
+// Safety for the helpers below: node must be a valid node of a tree with correct parent pointers
+unsafe fn leftmost<T>(mut node: *mut Node<T>) -> *mut Node<T> {
+    unsafe {
+        while !(*node).left.is_null() {
+            node = (*node).left;
+        }
+    }
+    node
+}
+
+unsafe fn rightmost<T>(mut node: *mut Node<T>) -> *mut Node<T> {
+    unsafe {
+        while !(*node).right.is_null() {
+            node = (*node).right;
+        }
+    }
+    node
+}
+
+// next node in order, the same walk as the binary search tree cursors
+unsafe fn next_node<T>(node: *mut Node<T>) -> *mut Node<T> {
+    unsafe {
+        if !(*node).right.is_null() {
+            return leftmost((*node).right);
+        }
+
+        let mut child = node;
+        let mut parent = (*node).parent;
+        while !parent.is_null() && (*parent).right == child {
+            child = parent;
+            parent = (*parent).parent;
+        }
+        parent
+    }
+}
+
+unsafe fn prev_node<T>(node: *mut Node<T>) -> *mut Node<T> {
+    unsafe {
+        if !(*node).left.is_null() {
+            return rightmost((*node).left);
+        }
+
+        let mut child = node;
+        let mut parent = (*node).parent;
+        while !parent.is_null() && (*parent).left == child {
+            child = parent;
+            parent = (*parent).parent;
+        }
+        parent
+    }
+}
+
+// In order iterator from both ends, also what range() returns.
+// front and back are the next nodes to hand out, both null once they cross.
+pub struct Iter<'a, T> {
+    front: *mut Node<T>,
+    back: *mut Node<T>,
+    _marker: PhantomData<&'a T>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    fn empty() -> Self {
+        Iter { front: ptr::null_mut(), back: ptr::null_mut(), _marker: PhantomData }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front.is_null() {
+            return None;
+        }
+
+        let node = self.front;
+        if self.front == self.back {
+            *self = Iter::empty();
+        } else {
+            self.front = unsafe { next_node(node) };
+        }
+        Some(unsafe { &(*node).val })
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.back.is_null() {
+            return None;
+        }
+
+        let node = self.back;
+        if self.front == self.back {
+            *self = Iter::empty();
+        } else {
+            self.back = unsafe { prev_node(node) };
+        }
+        Some(unsafe { &(*node).val })
+    }
+}
+
+impl<T> RedBlackTree<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        if self.root.is_null() {
+            return Iter::empty();
+        }
+        unsafe { Iter { front: leftmost(self.root), back: rightmost(self.root), _marker: PhantomData } }
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        self.iter().next()
+    }
+
+    pub fn max(&self) -> Option<&T> {
+        self.iter().next_back()
+    }
+
+    //     x                y
+    //    / \              / \
+    //   a   y     ->     x   c
+    //      / \          / \
+    //     b   c        a   b
+    // Safety: x must be a node of this tree with a right child
+    unsafe fn rotate_left(&mut self, x: *mut Node<T>) {
+        unsafe {
+            let y = (*x).right;
+            (*x).right = (*y).left;
+            if !(*y).left.is_null() {
+                (*(*y).left).parent = x;
+            }
+            (*y).parent = (*x).parent;
+            self.replace_child(x, y);
+            (*y).left = x;
+            (*x).parent = y;
+        }
+    }
+
+    // mirror of rotate_left, x must have a left child
+    unsafe fn rotate_right(&mut self, x: *mut Node<T>) {
+        unsafe {
+            let y = (*x).left;
+            (*x).left = (*y).right;
+            if !(*y).right.is_null() {
+                (*(*y).right).parent = x;
+            }
+            (*y).parent = (*x).parent;
+            self.replace_child(x, y);
+            (*y).right = x;
+            (*x).parent = y;
+        }
+    }
+
+    // points the link that held old (the root or a child link of old's parent) at new
+    unsafe fn replace_child(&mut self, old: *mut Node<T>, new: *mut Node<T>) {
+        unsafe {
+            let parent = (*old).parent;
+            if parent.is_null() {
+                self.root = new;
+            } else if (*parent).left == old {
+                (*parent).left = new;
+            } else {
+                (*parent).right = new;
+            }
+        }
+    }
+
+    // puts the subtree new where old was, old's own links are left as they are
+    unsafe fn transplant(&mut self, old: *mut Node<T>, new: *mut Node<T>) {
+        unsafe {
+            self.replace_child(old, new);
+            if !new.is_null() {
+                (*new).parent = (*old).parent;
+            }
+        }
+    }
+
+    // The new red node may have a red parent. Recolor while the uncle is red (moving the problem
+    // two levels up), otherwise one or two rotations end it. Returns the rotations done.
+    unsafe fn insert_fixup(&mut self, mut node: *mut Node<T>) -> usize {
+        let mut rotations = 0;
+        unsafe {
+            while is_red((*node).parent) {
+                let mut parent = (*node).parent;
+                // a red parent is never the root, so there is a grandparent
+                let grandparent = (*parent).parent;
+
+                if parent == (*grandparent).left {
+                    let uncle = (*grandparent).right;
+                    if is_red(uncle) {
+                        (*parent).color = Color::Black;
+                        (*uncle).color = Color::Black;
+                        (*grandparent).color = Color::Red;
+                        node = grandparent;
+                        continue;
+                    }
+                    // inner grandchild, turn it into an outer one first
+                    if node == (*parent).right {
+                        node = parent;
+                        self.rotate_left(node);
+                        rotations += 1;
+                        parent = (*node).parent;
+                    }
+                    (*parent).color = Color::Black;
+                    (*grandparent).color = Color::Red;
+                    self.rotate_right(grandparent);
+                    rotations += 1;
+                } else {
+                    let uncle = (*grandparent).left;
+                    if is_red(uncle) {
+                        (*parent).color = Color::Black;
+                        (*uncle).color = Color::Black;
+                        (*grandparent).color = Color::Red;
+                        node = grandparent;
+                        continue;
+                    }
+                    if node == (*parent).left {
+                        node = parent;
+                        self.rotate_right(node);
+                        rotations += 1;
+                        parent = (*node).parent;
+                    }
+                    (*parent).color = Color::Black;
+                    (*grandparent).color = Color::Red;
+                    self.rotate_left(grandparent);
+                    rotations += 1;
+                }
+            }
+            (*self.root).color = Color::Black;
+        }
+        rotations
+    }
+
+    // Unlinks node and frees it, returns its value and the rotations done.
+    // A node with two children is replaced by its successor, which takes its color.
+    unsafe fn delete_node(&mut self, node: *mut Node<T>) -> (T, usize) {
+        unsafe {
+            let mut removed_color = (*node).color;
+            // x is what moved into the place of the removed black, it may be null
+            let x;
+            let x_parent;
+
+            if (*node).left.is_null() {
+                x = (*node).right;
+                x_parent = (*node).parent;
+                self.transplant(node, x);
+            } else if (*node).right.is_null() {
+                x = (*node).left;
+                x_parent = (*node).parent;
+                self.transplant(node, x);
+            } else {
+                let successor = leftmost((*node).right);
+                removed_color = (*successor).color;
+                x = (*successor).right;
+                if (*successor).parent == node {
+                    x_parent = successor;
+                } else {
+                    x_parent = (*successor).parent;
+                    self.transplant(successor, x);
+                    (*successor).right = (*node).right;
+                    (*(*successor).right).parent = successor;
+                }
+                self.transplant(node, successor);
+                (*successor).left = (*node).left;
+                (*(*successor).left).parent = successor;
+                (*successor).color = (*node).color;
+            }
+
+            self.len -= 1;
+            let rotations = if removed_color == Color::Black {
+                self.delete_fixup(x, x_parent)
+            } else {
+                0
+            };
+            let node = Box::from_raw(node);
+            (node.val, rotations)
+        }
+    }
+
+    // A black node went away, so the paths through x are one black short.
+    // Push the missing black up while the sibling and its children are black, otherwise borrow it
+    // from the sibling's side with up to three rotations. Returns the rotations done.
+    unsafe fn delete_fixup(&mut self, mut x: *mut Node<T>, mut parent: *mut Node<T>) -> usize {
+        let mut rotations = 0;
+        unsafe {
+            while x != self.root && !is_red(x) {
+                // x is short one black, so its sibling can't be null
+                if x == (*parent).left {
+                    let mut sibling = (*parent).right;
+                    if is_red(sibling) {
+                        (*sibling).color = Color::Black;
+                        (*parent).color = Color::Red;
+                        self.rotate_left(parent);
+                        rotations += 1;
+                        sibling = (*parent).right;
+                    }
+                    if !is_red((*sibling).left) && !is_red((*sibling).right) {
+                        (*sibling).color = Color::Red;
+                        x = parent;
+                        parent = (*x).parent;
+                        continue;
+                    }
+                    if !is_red((*sibling).right) {
+                        (*(*sibling).left).color = Color::Black;
+                        (*sibling).color = Color::Red;
+                        self.rotate_right(sibling);
+                        rotations += 1;
+                        sibling = (*parent).right;
+                    }
+                    (*sibling).color = (*parent).color;
+                    (*parent).color = Color::Black;
+                    (*(*sibling).right).color = Color::Black;
+                    self.rotate_left(parent);
+                    rotations += 1;
+                } else {
+                    let mut sibling = (*parent).left;
+                    if is_red(sibling) {
+                        (*sibling).color = Color::Black;
+                        (*parent).color = Color::Red;
+                        self.rotate_right(parent);
+                        rotations += 1;
+                        sibling = (*parent).left;
+                    }
+                    if !is_red((*sibling).left) && !is_red((*sibling).right) {
+                        (*sibling).color = Color::Red;
+                        x = parent;
+                        parent = (*x).parent;
+                        continue;
+                    }
+                    if !is_red((*sibling).left) {
+                        (*(*sibling).right).color = Color::Black;
+                        (*sibling).color = Color::Red;
+                        self.rotate_left(sibling);
+                        rotations += 1;
+                        sibling = (*parent).left;
+                    }
+                    (*sibling).color = (*parent).color;
+                    (*parent).color = Color::Black;
+                    (*(*sibling).left).color = Color::Black;
+                    self.rotate_right(parent);
+                    rotations += 1;
+                }
+                // the black was borrowed, everything is fixed
+                x = self.root;
+            }
+
+            if !x.is_null() {
+                (*x).color = Color::Black;
+            }
+        }
+        rotations
+    }
+}
+
+impl<T: Ord> RedBlackTree<T> {
+    fn find(&self, val: &T) -> *mut Node<T> {
+        let mut current = self.root;
+        unsafe {
+            while !current.is_null() {
+                match val.cmp(&(*current).val) {
+                    Ordering::Equal => break,
+                    Ordering::Less => current = (*current).left,
+                    Ordering::Greater => current = (*current).right,
+                }
+            }
+        }
+        current
+    }
+
+    pub fn search(&self, val: &T) -> Option<&T> {
+        unsafe { self.find(val).as_ref().map(|node| &node.val) }
+    }
+
+    pub fn contains(&self, val: &T) -> bool {
+        !self.find(val).is_null()
+    }
+
+    // a value that is already in the tree is left as it is
+    pub fn insert(&mut self, val: T) {
+        self.insert_counted(val);
+    }
+
+    // Same as insert but also returns how many rotations it took.
+    // For comparing the balancing work with other trees on the same inputs.
+    pub fn insert_counted(&mut self, val: T) -> usize {
+        let mut parent = ptr::null_mut();
+        let mut link: *mut *mut Node<T> = &mut self.root;
+
+        unsafe {
+            while !(*link).is_null() {
+                parent = *link;
+                match val.cmp(&(*parent).val) {
+                    Ordering::Less => link = &mut (*parent).left,
+                    Ordering::Greater => link = &mut (*parent).right,
+                    Ordering::Equal => return 0,
+                }
+            }
+
+            let node = Box::into_raw(Box::new(Node {
+                val,
+                color: Color::Red,
+                parent,
+                left: ptr::null_mut(),
+                right: ptr::null_mut(),
+            }));
+            *link = node;
+            self.len += 1;
+            self.insert_fixup(node)
+        }
+    }
+
+    pub fn delete(&mut self, val: &T) -> Option<T> {
+        self.delete_counted(val).0
+    }
+
+    // delete and the number of rotations it took
+    pub fn delete_counted(&mut self, val: &T) -> (Option<T>, usize) {
+        let node = self.find(val);
+        if node.is_null() {
+            return (None, 0);
+        }
+        let (val, rotations) = unsafe { self.delete_node(node) };
+        (Some(val), rotations)
+    }
+
+    // values inside range in order, from both ends: tree.range(3..7), tree.range(10..).rev()
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> Iter<'_, T> {
+        let front = self.first_inside(range.start_bound());
+        let back = self.last_inside(range.end_bound());
+        if front.is_null() || back.is_null() {
+            return Iter::empty();
+        }
+        // an empty range like 5..5 leaves them crossed
+        if unsafe { (*front).val > (*back).val } {
+            return Iter::empty();
+        }
+        Iter { front, back, _marker: PhantomData }
+    }
+
+    // smallest node after the start bound
+    fn first_inside(&self, start: Bound<&T>) -> *mut Node<T> {
+        let mut found = ptr::null_mut();
+        let mut current = self.root;
+        unsafe {
+            while !current.is_null() {
+                let inside = match start {
+                    Bound::Included(start) => (*current).val >= *start,
+                    Bound::Excluded(start) => (*current).val > *start,
+                    Bound::Unbounded => true,
+                };
+                if inside {
+                    // maybe there is a smaller one on the left
+                    found = current;
+                    current = (*current).left;
+                } else {
+                    current = (*current).right;
+                }
+            }
+        }
+        found
+    }
+
+    // biggest node before the end bound
+    fn last_inside(&self, end: Bound<&T>) -> *mut Node<T> {
+        let mut found = ptr::null_mut();
+        let mut current = self.root;
+        unsafe {
+            while !current.is_null() {
+                let inside = match end {
+                    Bound::Included(end) => (*current).val <= *end,
+                    Bound::Excluded(end) => (*current).val < *end,
+                    Bound::Unbounded => true,
+                };
+                if inside {
+                    found = current;
+                    current = (*current).right;
+                } else {
+                    current = (*current).left;
+                }
+            }
+        }
+        found
+    }
+
+    // Checks every invariant: the red-black coloring rules, strictly increasing values in order,
+    // the parent pointers and the stored length
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        if is_red(self.root) {
+            return Err(InvariantError::RedRoot);
+        }
+
+        let mut next_index = 0;
+        let mut previous = None;
+        Self::check_node(self.root, ptr::null_mut(), &mut next_index, &mut previous)?;
+
+        if next_index != self.len {
+            return Err(InvariantError::WrongLength { stored: self.len, actual: next_index });
+        }
+        Ok(())
+    }
+
+    // in order walk of the subtree, returns its black height (the nil leaves count as one)
+    fn check_node<'a>(
+        node: *mut Node<T>,
+        parent: *mut Node<T>,
+        next_index: &mut usize,
+        previous: &mut Option<&'a T>,
+    ) -> Result<usize, InvariantError> {
+        let Some(node) = (unsafe { node.as_ref::<'a>() }) else {
+            return Ok(1);
+        };
+
+        let left = Self::check_node(node.left, node as *const Node<T> as *mut Node<T>, next_index, previous)?;
+        let index = *next_index;
+        *next_index += 1;
+
+        if node.parent != parent {
+            return Err(InvariantError::WrongParent { index });
+        }
+        if previous.is_some_and(|previous| *previous >= node.val) {
+            return Err(InvariantError::OutOfOrder { index });
+        }
+        *previous = Some(&node.val);
+        if node.color == Color::Red && (is_red(node.left) || is_red(node.right)) {
+            return Err(InvariantError::RedWithRedChild { index });
+        }
+
+        let right = Self::check_node(node.right, node as *const Node<T> as *mut Node<T>, next_index, previous)?;
+        if left != right {
+            return Err(InvariantError::BlackHeight { index, left, right });
+        }
+        Ok(left + usize::from(node.color == Color::Black))
+    }
+}
+
+fn children<T>(node: &Node<T>) -> (Option<&Node<T>>, Option<&Node<T>>) {
+    unsafe { (node.left.as_ref(), node.right.as_ref()) }
+}
+
+fn node_label<T: fmt::Display>(node: &Node<T>) -> String {
+    match node.color {
+        Color::Red => format!("{} (red)", node.val),
+        Color::Black => format!("{} (black)", node.val),
+    }
+}
+
+impl<T: fmt::Display> RedBlackTree<T> {
+    // Graphviz source, every node shows its value and color
+    pub fn to_dot(&self) -> String {
+        tree_fmt::to_dot("RedBlackTree", unsafe { self.root.as_ref() }, children, node_label)
+    }
+}
+
+// draws the tree sideways, root on the left and bigger values above, with the colors
+impl<T: fmt::Display> fmt::Display for RedBlackTree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        tree_fmt::sideways(f, unsafe { self.root.as_ref() }, children, node_label)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a RedBlackTree<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for RedBlackTree<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = RedBlackTree::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<T: Ord> Extend<T> for RedBlackTree<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(val);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xorshift::shuffled;
+    use std::rc::Rc;
+
+    fn height<T>(node: *mut Node<T>) -> usize {
+        unsafe { node.as_ref().map_or(0, |node| 1 + height(node.left).max(height(node.right))) }
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let mut tree: RedBlackTree<i32> = RedBlackTree::new();
+        assert!(tree.is_empty());
+        assert_eq!(tree.min(), None);
+        assert_eq!(tree.iter().next(), None);
+        assert_eq!(tree.delete(&1), None);
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_insert_and_search() {
+        let mut tree = RedBlackTree::new();
+        for val in [10, 5, 15, 3, 7, 12, 18, 7] {
+            tree.insert(val);
+        }
+        assert_eq!(tree.len(), 7);
+        assert_eq!(tree.search(&7), Some(&7));
+        assert_eq!(tree.search(&8), None);
+        assert!(tree.contains(&18));
+        assert_eq!(tree.min(), Some(&3));
+        assert_eq!(tree.max(), Some(&18));
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_sorted_inserts_stay_balanced() {
+        let mut tree = RedBlackTree::new();
+        for val in 0..1024 {
+            tree.insert(val);
+            assert_eq!(tree.validate(), Ok(()));
+        }
+        // at most 2 * log2(n + 1)
+        assert!(height(tree.root) <= 20);
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), (0..1024).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_iter_both_ends() {
+        let tree: RedBlackTree<i32> = (1..=6).collect();
+        let mut iter = tree.iter();
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&6));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next(), Some(&4));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+        assert_eq!((&tree).into_iter().rev().count(), 6);
+    }
+
+    #[test]
+    fn test_delete_keeps_invariants() {
+        let values = shuffled(500);
+        let mut tree: RedBlackTree<u64> = values.iter().copied().collect();
+        assert_eq!(tree.validate(), Ok(()));
+
+        for (removed, val) in shuffled(500).iter().rev().enumerate() {
+            assert_eq!(tree.delete(val), Some(*val));
+            assert_eq!(tree.delete(val), None);
+            assert_eq!(tree.len(), 500 - removed - 1);
+            assert_eq!(tree.validate(), Ok(()));
+        }
+        assert!(tree.is_empty());
+        assert!(tree.root.is_null());
+    }
+
+    #[test]
+    fn test_mixed_operations() {
+        let mut tree = RedBlackTree::new();
+        let mut expected = std::collections::BTreeSet::new();
+        for (step, val) in shuffled(2000).into_iter().enumerate() {
+            let val = val % 300;
+            if step % 3 == 0 {
+                assert_eq!(tree.delete(&val), expected.take(&val));
+            } else {
+                tree.insert(val);
+                expected.insert(val);
+            }
+        }
+        assert_eq!(tree.validate(), Ok(()));
+        assert!(tree.iter().eq(expected.iter()));
+    }
+
+    #[test]
+    fn test_rotations_are_bounded() {
+        let mut tree = RedBlackTree::new();
+        for val in shuffled(1000) {
+            assert!(tree.insert_counted(val) <= 2);
+        }
+        for val in shuffled(1000).iter().step_by(2) {
+            let (deleted, rotations) = tree.delete_counted(val);
+            assert_eq!(deleted, Some(*val));
+            assert!(rotations <= 3);
+        }
+        assert_eq!(tree.delete_counted(&5000), (None, 0));
+        assert_eq!(tree.insert_counted(1), 0); // already there
+    }
+
+    #[test]
+    fn test_range() {
+        let tree: RedBlackTree<i32> = (0..20).map(|val| val * 2).collect();
+        assert_eq!(tree.range(5..11).copied().collect::<Vec<_>>(), vec![6, 8, 10]);
+        assert_eq!(tree.range(6..=10).copied().collect::<Vec<_>>(), vec![6, 8, 10]);
+        assert_eq!(tree.range((Bound::Excluded(6), Bound::Unbounded)).count(), 16);
+        assert_eq!(tree.range(..4).rev().copied().collect::<Vec<_>>(), vec![2, 0]);
+        assert_eq!(tree.range(7..7).next(), None);
+        assert_eq!(tree.range(100..).next(), None);
+        assert_eq!(tree.range(..).count(), 20);
+    }
+
+    #[test]
+    fn test_drop_values() {
+        let alive = Rc::new(());
+        let mut tree = RedBlackTree::new();
+        for val in 0..50 {
+            tree.insert((val, alive.clone()));
+        }
+        let (removed, _) = tree.delete_counted(&(10, alive.clone()));
+        drop(removed);
+        assert_eq!(Rc::strong_count(&alive), 50);
+        drop(tree);
+        assert_eq!(Rc::strong_count(&alive), 1);
+    }
+
+    #[test]
+    fn test_validate_finds_broken_trees() {
+        let mut tree: RedBlackTree<i32> = (0..10).collect();
+        unsafe {
+            (*tree.root).color = Color::Red;
+            assert_eq!(tree.validate(), Err(InvariantError::RedRoot));
+            (*tree.root).color = Color::Black;
+
+            // painting a black leaf red changes the black height of one path only
+            let first = leftmost(tree.root);
+            assert_eq!((*first).color, Color::Black);
+            (*first).color = Color::Red;
+            assert!(matches!(tree.validate(), Err(InvariantError::BlackHeight { .. })));
+            (*first).color = Color::Black;
+
+            (*first).val = 100;
+            assert_eq!(tree.validate(), Err(InvariantError::OutOfOrder { index: 1 }));
+            (*first).val = 0;
+        }
+        tree.len = 3;
+        assert_eq!(tree.validate(), Err(InvariantError::WrongLength { stored: 3, actual: 10 }));
+        tree.len = 10;
+    }
+
+    #[test]
+    fn test_display() {
+        let tree: RedBlackTree<i32> = [2, 1, 3].into_iter().collect();
+        assert_eq!(tree.to_string(), "/-- 3 (red)\n2 (black)\n\\-- 1 (red)\n");
+        assert!(tree.to_dot().contains("[label=\"2 (black)\"]"));
+    }
+}
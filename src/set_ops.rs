@@ -0,0 +1,173 @@
+use std::cmp::Ordering;
+use std::iter::{ FusedIterator, Peekable };
+
+// Lazy set operations over two iterators that hand out values in increasing order without
+// duplicates, which is what the tree iterators do. Each step compares the two heads, so going
+// through the whole result is O(n + m) and nothing is allocated.
+// The trees build these from their own iter(), see BinarySearchTree::union or AvlTree::union.
+
+pub struct Union<'a, T: 'a, I: Iterator<Item = &'a T>> {
+    a: Peekable<I>,
+    b: Peekable<I>,
+}
+
+pub struct Intersection<'a, T: 'a, I: Iterator<Item = &'a T>> {
+    a: Peekable<I>,
+    b: Peekable<I>,
+}
+
+pub struct Difference<'a, T: 'a, I: Iterator<Item = &'a T>> {
+    a: Peekable<I>,
+    b: Peekable<I>,
+}
+
+pub struct SymmetricDifference<'a, T: 'a, I: Iterator<Item = &'a T>> {
+    a: Peekable<I>,
+    b: Peekable<I>,
+}
+
+// Compare the heads of both iterators.
+// Less means only a has a value left or a's head is the smaller one, Greater the same for b.
+// None when both are done.
+fn compare_heads<'a, T: Ord + 'a, I: Iterator<Item = &'a T>>(a: &mut Peekable<I>, b: &mut Peekable<I>) -> Option<Ordering> {
+    match (a.peek(), b.peek()) {
+        (None, None) => None,
+        (Some(_), None) => Some(Ordering::Less),
+        (None, Some(_)) => Some(Ordering::Greater),
+        (Some(x), Some(y)) => Some(x.cmp(y)),
+    }
+}
+
+impl<'a, T: 'a, I: Iterator<Item = &'a T>> Union<'a, T, I> {
+    pub(crate) fn new(a: I, b: I) -> Self {
+        Union { a: a.peekable(), b: b.peekable() }
+    }
+}
+
+impl<'a, T: Ord + 'a, I: Iterator<Item = &'a T>> Iterator for Union<'a, T, I> {
+    type Item = &'a T;
+
+    // values in both sets come out once, taken from a
+    fn next(&mut self) -> Option<Self::Item> {
+        match compare_heads(&mut self.a, &mut self.b)? {
+            Ordering::Less => self.a.next(),
+            Ordering::Greater => self.b.next(),
+            Ordering::Equal => {
+                self.b.next();
+                self.a.next()
+            },
+        }
+    }
+}
+
+impl<'a, T: 'a, I: Iterator<Item = &'a T>> Intersection<'a, T, I> {
+    pub(crate) fn new(a: I, b: I) -> Self {
+        Intersection { a: a.peekable(), b: b.peekable() }
+    }
+}
+
+impl<'a, T: Ord + 'a, I: Iterator<Item = &'a T>> Iterator for Intersection<'a, T, I> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // once one side is done nothing else can be in both
+            self.a.peek()?;
+            self.b.peek()?;
+            match compare_heads(&mut self.a, &mut self.b)? {
+                Ordering::Less => { self.a.next(); },
+                Ordering::Greater => { self.b.next(); },
+                Ordering::Equal => {
+                    self.b.next();
+                    return self.a.next();
+                },
+            }
+        }
+    }
+}
+
+impl<'a, T: 'a, I: Iterator<Item = &'a T>> Difference<'a, T, I> {
+    pub(crate) fn new(a: I, b: I) -> Self {
+        Difference { a: a.peekable(), b: b.peekable() }
+    }
+}
+
+impl<'a, T: Ord + 'a, I: Iterator<Item = &'a T>> Iterator for Difference<'a, T, I> {
+    type Item = &'a T;
+
+    // values of a that are not in b
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.a.peek()?;
+            match compare_heads(&mut self.a, &mut self.b)? {
+                Ordering::Less => return self.a.next(),
+                Ordering::Greater => { self.b.next(); },
+                Ordering::Equal => {
+                    self.a.next();
+                    self.b.next();
+                },
+            }
+        }
+    }
+}
+
+impl<'a, T: 'a, I: Iterator<Item = &'a T>> SymmetricDifference<'a, T, I> {
+    pub(crate) fn new(a: I, b: I) -> Self {
+        SymmetricDifference { a: a.peekable(), b: b.peekable() }
+    }
+}
+
+impl<'a, T: Ord + 'a, I: Iterator<Item = &'a T>> Iterator for SymmetricDifference<'a, T, I> {
+    type Item = &'a T;
+
+    // values in exactly one of the sets
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match compare_heads(&mut self.a, &mut self.b)? {
+                Ordering::Less => return self.a.next(),
+                Ordering::Greater => return self.b.next(),
+                Ordering::Equal => {
+                    self.a.next();
+                    self.b.next();
+                },
+            }
+        }
+    }
+}
+
+// once a merge returned None the side it needed is done, so it stays None
+impl<'a, T: Ord + 'a, I: Iterator<Item = &'a T>> FusedIterator for Union<'a, T, I> {}
+impl<'a, T: Ord + 'a, I: Iterator<Item = &'a T>> FusedIterator for Intersection<'a, T, I> {}
+impl<'a, T: Ord + 'a, I: Iterator<Item = &'a T>> FusedIterator for Difference<'a, T, I> {}
+impl<'a, T: Ord + 'a, I: Iterator<Item = &'a T>> FusedIterator for SymmetricDifference<'a, T, I> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run<'a, It: Iterator<Item = &'a i32>>(iter: It) -> Vec<i32> {
+        iter.copied().collect()
+    }
+
+    #[test]
+    fn test_merges() {
+        let a = [1, 3, 5, 7, 9];
+        let b = [2, 3, 4, 9, 10];
+        assert_eq!(run(Union::new(a.iter(), b.iter())), vec![1, 2, 3, 4, 5, 7, 9, 10]);
+        assert_eq!(run(Intersection::new(a.iter(), b.iter())), vec![3, 9]);
+        assert_eq!(run(Difference::new(a.iter(), b.iter())), vec![1, 5, 7]);
+        assert_eq!(run(Difference::new(b.iter(), a.iter())), vec![2, 4, 10]);
+        assert_eq!(run(SymmetricDifference::new(a.iter(), b.iter())), vec![1, 2, 4, 5, 7, 10]);
+    }
+
+    #[test]
+    fn test_merges_with_empty_side() {
+        let a = [1, 2];
+        let empty: [i32; 0] = [];
+        assert_eq!(run(Union::new(a.iter(), empty.iter())), vec![1, 2]);
+        assert_eq!(run(Intersection::new(a.iter(), empty.iter())), Vec::<i32>::new());
+        assert_eq!(run(Difference::new(a.iter(), empty.iter())), vec![1, 2]);
+        assert_eq!(run(Difference::new(empty.iter(), a.iter())), Vec::<i32>::new());
+        assert_eq!(run(SymmetricDifference::new(empty.iter(), a.iter())), vec![1, 2]);
+    }
+}
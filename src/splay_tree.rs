@@ -0,0 +1,420 @@
+use std::cmp::Ordering;
+
+use crate::box_tree::{self, BoxNode};
+use crate::invariant::InvariantError;
+
+pub use crate::box_tree::Iter;
+
+// Splay tree: a plain binary search tree that moves every node it finds (or the last one it
+// looked at) up to the root. Nothing keeps it balanced, a single operation can take O(n), but
+// any sequence of m operations takes O(m log n). Keys that are asked for often stay near the
+// root, which is the point for skewed lookups: the hot keys cost a few steps each.
+//
+// Because of that search moves nodes around too, so unlike the other trees it needs &mut self.
+
+type Link<T> = box_tree::Link<Node<T>>;
+
+struct Node<T> {
+    val: T,
+    left: Link<T>,
+    right: Link<T>,
+}
+
+impl<T> Node<T> {
+    fn leaf(val: T) -> Box<Self> {
+        Box::new(Node { val, left: None, right: None })
+    }
+}
+
+impl<T> BoxNode<T> for Node<T> {
+    fn val(&self) -> &T {
+        &self.val
+    }
+
+    fn left(&self) -> Option<&dyn BoxNode<T>> {
+        self.left.as_deref().map(|node| node as &dyn BoxNode<T>)
+    }
+
+    fn right(&self) -> Option<&dyn BoxNode<T>> {
+        self.right.as_deref().map(|node| node as &dyn BoxNode<T>)
+    }
+
+    fn take_children(&mut self) -> [Link<T>; 2] {
+        [self.left.take(), self.right.take()]
+    }
+}
+
+pub struct SplayTree<T> {
+    root: Link<T>,
+    len: usize,
+}
+
+impl<T> Default for SplayTree<T> {
+    fn default() -> Self {
+        SplayTree { root: None, len: 0 }
+    }
+}
+
+impl<T> Drop for SplayTree<T> {
+    fn drop(&mut self) {
+        box_tree::drop_nodes(self.root.take());
+    }
+}
+
+// Top down splay: walks from the root towards val, cutting the nodes it passes into a left tree
+// (everything smaller than val) and a right tree (everything bigger), rotating on the way when it
+// goes twice in the same direction. The last node reached becomes the root with the two trees
+// hung under it. Returns the new root, which holds val if it was in the tree.
+fn splay<T: Ord>(mut root: Box<Node<T>>, val: &T) -> Box<Node<T>> {
+    // cut nodes in the order they were cut, each one goes under the one before it
+    let mut smaller: Vec<Box<Node<T>>> = Vec::new();
+    let mut bigger: Vec<Box<Node<T>>> = Vec::new();
+
+    loop {
+        match val.cmp(&root.val) {
+            Ordering::Equal => break,
+            Ordering::Less => {
+                let Some(mut left) = root.left.take() else {
+                    break;
+                };
+                if *val < left.val {
+                    // zig-zig, rotate right before going on
+                    root.left = left.right.take();
+                    left.right = Some(root);
+                    root = left;
+                    let Some(next) = root.left.take() else {
+                        break;
+                    };
+                    bigger.push(root);
+                    root = next;
+                } else {
+                    bigger.push(root);
+                    root = left;
+                }
+            },
+            Ordering::Greater => {
+                let Some(mut right) = root.right.take() else {
+                    break;
+                };
+                if *val > right.val {
+                    root.right = right.left.take();
+                    right.left = Some(root);
+                    root = right;
+                    let Some(next) = root.right.take() else {
+                        break;
+                    };
+                    smaller.push(root);
+                    root = next;
+                } else {
+                    smaller.push(root);
+                    root = right;
+                }
+            },
+        }
+    }
+
+    // the last node cut into each side gets the subtree of the new root on that side
+    let mut left = root.left.take();
+    for mut node in smaller.into_iter().rev() {
+        node.right = left;
+        left = Some(node);
+    }
+    let mut right = root.right.take();
+    for mut node in bigger.into_iter().rev() {
+        node.left = right;
+        right = Some(node);
+    }
+    root.left = left;
+    root.right = right;
+    root
+}
+
+impl<T> SplayTree<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // number of levels, 0 for an empty tree. It changes with every access
+    pub fn height(&self) -> usize {
+        box_tree::height(&self.root)
+    }
+
+    // iterating doesn't splay, it only needs &self
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(&self.root, self.len)
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        self.iter().next()
+    }
+
+    pub fn max(&self) -> Option<&T> {
+        self.iter().next_back()
+    }
+
+    // the value at the root, the last one touched
+    pub fn root(&self) -> Option<&T> {
+        self.root.as_ref().map(|root| &root.val)
+    }
+}
+
+impl<T: Ord> SplayTree<T> {
+    // splays the root towards val, true when val ended up at the root
+    fn splay(&mut self, val: &T) -> bool {
+        match self.root.take() {
+            None => false,
+            Some(root) => {
+                let root = self.root.insert(splay(root, val));
+                root.val == *val
+            },
+        }
+    }
+
+    // a found value is moved to the root, a missing one moves its closest neighbour there
+    pub fn search(&mut self, val: &T) -> Option<&T> {
+        if self.splay(val) {
+            self.root()
+        } else {
+            None
+        }
+    }
+
+    pub fn contains(&mut self, val: &T) -> bool {
+        self.splay(val)
+    }
+
+    // the new value becomes the root: after the splay the old root is its neighbour, so it only
+    // has to take one side of it. A value already in the tree is left as it is
+    pub fn insert(&mut self, val: T) {
+        let Some(root) = self.root.take() else {
+            self.root = Some(Node::leaf(val));
+            self.len = 1;
+            return;
+        };
+
+        let mut root = splay(root, &val);
+        let mut node = Node::leaf(val);
+        match node.val.cmp(&root.val) {
+            Ordering::Equal => {
+                self.root = Some(root);
+                return;
+            },
+            Ordering::Less => {
+                node.left = root.left.take();
+                node.right = Some(root);
+            },
+            Ordering::Greater => {
+                node.right = root.right.take();
+                node.left = Some(root);
+            },
+        }
+        self.root = Some(node);
+        self.len += 1;
+    }
+
+    // Splays val to the root and takes it out. Splaying val again in the left subtree brings up
+    // its biggest value, which has no right child, so the right subtree goes there.
+    pub fn delete(&mut self, val: &T) -> Option<T> {
+        if !self.splay(val) {
+            return None;
+        }
+
+        let root = *self.root.take()?;
+        self.root = match root.left {
+            None => root.right,
+            Some(left) => {
+                let mut left = splay(left, val);
+                left.right = root.right;
+                Some(left)
+            },
+        };
+        self.len -= 1;
+        Some(root.val)
+    }
+
+    // Values strictly increasing in order and the stored length. There is no shape to check, any
+    // binary search tree is a valid splay tree.
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        let mut count = 0;
+        let mut previous: Option<&T> = None;
+        let mut stack = Vec::new();
+        let mut current = self.root.as_deref();
+
+        while current.is_some() || !stack.is_empty() {
+            while let Some(node) = current {
+                stack.push(node);
+                current = node.left.as_deref();
+            }
+            let Some(node) = stack.pop() else {
+                break;
+            };
+            if previous.is_some_and(|previous| *previous >= node.val) {
+                return Err(InvariantError::OutOfOrder { index: count });
+            }
+            previous = Some(&node.val);
+            count += 1;
+            current = node.right.as_deref();
+        }
+
+        if count != self.len {
+            return Err(InvariantError::WrongLength { stored: self.len, actual: count });
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SplayTree<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for SplayTree<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = SplayTree::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<T: Ord> Extend<T> for SplayTree<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(val);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xorshift::shuffled;
+    use std::collections::BTreeSet;
+    use std::rc::Rc;
+
+    fn values<T: Copy>(tree: &SplayTree<T>) -> Vec<T> {
+        tree.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let mut tree: SplayTree<i32> = SplayTree::new();
+        assert!(tree.is_empty());
+        assert_eq!(tree.search(&1), None);
+        assert_eq!(tree.delete(&1), None);
+        assert_eq!(tree.iter().next(), None);
+        assert_eq!(tree.height(), 0);
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_search_moves_to_root() {
+        let mut tree: SplayTree<i32> = [50, 30, 70, 20, 40, 60, 80].into_iter().collect();
+        assert_eq!(tree.search(&20), Some(&20));
+        assert_eq!(tree.root(), Some(&20));
+        assert_eq!(tree.search(&65), None);
+        // a miss brings up a neighbour
+        assert!(matches!(tree.root(), Some(60) | Some(70)));
+        assert!(tree.contains(&80));
+        assert_eq!(tree.root(), Some(&80));
+        assert_eq!(values(&tree), vec![20, 30, 40, 50, 60, 70, 80]);
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_insert_and_delete() {
+        let mut tree = SplayTree::new();
+        for val in shuffled(500) {
+            tree.insert(val);
+            assert_eq!(tree.root(), Some(&val));
+        }
+        tree.insert(7);
+        assert_eq!(tree.len(), 500);
+        assert_eq!(tree.validate(), Ok(()));
+        assert_eq!(values(&tree), (0..500).collect::<Vec<_>>());
+
+        for (removed, val) in shuffled(500).iter().rev().enumerate() {
+            assert_eq!(tree.delete(val), Some(*val));
+            assert_eq!(tree.delete(val), None);
+            assert_eq!(tree.len(), 500 - removed - 1);
+            assert_eq!(tree.validate(), Ok(()));
+        }
+        assert!(tree.root.is_none());
+    }
+
+    #[test]
+    fn test_mixed_operations() {
+        let mut tree = SplayTree::new();
+        let mut expected = BTreeSet::new();
+        for (step, val) in shuffled(3000).into_iter().enumerate() {
+            let val = val % 300;
+            match step % 4 {
+                0 => assert_eq!(tree.delete(&val), expected.take(&val)),
+                1 => assert_eq!(tree.search(&val), expected.get(&val)),
+                _ => {
+                    tree.insert(val);
+                    expected.insert(val);
+                },
+            }
+        }
+        assert_eq!(tree.validate(), Ok(()));
+        assert!(tree.iter().eq(expected.iter()));
+        assert!(tree.iter().rev().eq(expected.iter().rev()));
+    }
+
+    #[test]
+    fn test_sorted_access_halves_the_path() {
+        // sorted inserts leave a path, searching the deepest value roughly halves its depth
+        let mut tree: SplayTree<i32> = (0..1000).collect();
+        assert_eq!(tree.height(), 1000);
+        tree.search(&0);
+        assert!(tree.height() <= 502);
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_iter_both_ends() {
+        let tree: SplayTree<i32> = [4, 2, 6, 1, 3, 5, 7].into_iter().collect();
+        let mut iter = tree.iter();
+        assert_eq!(iter.len(), 7);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&7));
+        assert_eq!(iter.next_back(), Some(&6));
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.copied().collect::<Vec<_>>(), vec![2, 3, 4, 5]);
+        assert_eq!(tree.min(), Some(&1));
+        assert_eq!(tree.max(), Some(&7));
+    }
+
+    #[test]
+    fn test_drop_deep_tree() {
+        let alive = Rc::new(());
+        let mut tree = SplayTree::new();
+        for val in 0..100_000 {
+            tree.insert((val, alive.clone()));
+        }
+        drop(tree);
+        assert_eq!(Rc::strong_count(&alive), 1);
+    }
+
+    #[test]
+    fn test_validate_finds_broken_trees() {
+        let mut tree: SplayTree<i32> = (0..10).collect();
+        tree.root.as_mut().unwrap().val = -1;
+        assert_eq!(tree.validate(), Err(InvariantError::OutOfOrder { index: 9 }));
+        tree.root.as_mut().unwrap().val = 9;
+        tree.len = 3;
+        assert_eq!(tree.validate(), Err(InvariantError::WrongLength { stored: 3, actual: 10 }));
+    }
+}
@@ -1,3 +1,5 @@
+use crate::invariant::InvariantError;
+
 pub struct Stack<T, const N: usize> {
     elements: [T; N],
     top: usize,
@@ -35,6 +37,14 @@ impl<T: Default + Copy, const N: usize> Stack<T, N> {
         N
     }
 
+    // top is the number of values, it can't go past the array
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        if self.top > N {
+            return Err(InvariantError::IndexOutOfBounds { field: "top", index: self.top, capacity: N });
+        }
+        Ok(())
+    }
+
     pub fn peek(&self) -> Option<&T> {
         if self.is_empty() {
             return None;
@@ -68,7 +78,7 @@ impl<T: Default + Copy, const N: usize> Stack<T, N> {
 
 #[cfg(test)]
 mod tests {
-    use super::Stack;
+    use super::*;
 
     #[test]
     fn test_new_stack_is_empty() {
@@ -157,4 +167,17 @@ mod tests {
         assert_eq!(stack.peek(), Some(&4));
         assert!(stack.is_full());
     }
+
+    #[test]
+    fn test_validate() {
+        let mut stack: Stack<i32, 2> = Stack::new();
+        assert_eq!(stack.validate(), Ok(()));
+        stack.push(1).unwrap();
+        stack.push(2).unwrap();
+        assert!(stack.push(3).is_err());
+        assert_eq!(stack.validate(), Ok(()));
+
+        stack.top = 3;
+        assert_eq!(stack.validate(), Err(InvariantError::IndexOutOfBounds { field: "top", index: 3, capacity: 2 }));
+    }
 }
@@ -1,3 +1,5 @@
+use crate::invariant::InvariantError;
+
 pub struct Stack<T> {
     elements: Vec<T>,
 }
@@ -34,6 +36,12 @@ impl<T> Stack<T> {
     pub fn is_empty(&self) -> bool {
         self.elements.is_empty()
     }
+
+    // the Vec keeps all the state, there is nothing that can get out of sync.
+    // Here so every structure can be checked the same way
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,435 @@
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use crate::box_tree::{self, BoxNode};
+use crate::invariant::InvariantError;
+use crate::xorshift::Xorshift;
+
+pub use crate::box_tree::Iter;
+
+// Treap: a binary search tree on the values and a max heap on a random priority given to every
+// node. With random priorities the shape is the one of a tree built from a random insert order,
+// so the expected depth is O(log n) whatever order the values come in.
+//
+// Everything is built on two operations:
+// - split cuts a treap into the values below a key and the rest
+// - merge joins two treaps where every value of the first is smaller than the second
+// Both follow a single path down, O(log n) expected. Insert is a split and two merges, delete
+// merges the two children of the removed node.
+
+type Link<T> = box_tree::Link<Node<T>>;
+
+struct Node<T> {
+    val: T,
+    priority: u64,
+    left: Link<T>,
+    right: Link<T>,
+    size: usize,
+}
+
+impl<T> Node<T> {
+    // recompute size from the children, they must already be up to date
+    fn update(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+}
+
+impl<T> BoxNode<T> for Node<T> {
+    fn val(&self) -> &T {
+        &self.val
+    }
+
+    fn left(&self) -> Option<&dyn BoxNode<T>> {
+        self.left.as_deref().map(|node| node as &dyn BoxNode<T>)
+    }
+
+    fn right(&self) -> Option<&dyn BoxNode<T>> {
+        self.right.as_deref().map(|node| node as &dyn BoxNode<T>)
+    }
+
+    fn take_children(&mut self) -> [Link<T>; 2] {
+        [self.left.take(), self.right.take()]
+    }
+}
+
+fn size<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+// values < key on the left, values >= key on the right
+fn split<T: Ord>(link: Link<T>, key: &T) -> (Link<T>, Link<T>) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+
+    if node.val < *key {
+        let (left, right) = split(node.right.take(), key);
+        node.right = left;
+        node.update();
+        (Some(node), right)
+    } else {
+        let (left, right) = split(node.left.take(), key);
+        node.left = right;
+        node.update();
+        (left, Some(node))
+    }
+}
+
+// every value of left must be smaller than every value of right, the higher priority root wins
+fn merge<T>(left: Link<T>, right: Link<T>) -> Link<T> {
+    match (left, right) {
+        (None, right) => right,
+        (left, None) => left,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority > right.priority {
+                left.right = merge(left.right.take(), Some(right));
+                left.update();
+                Some(left)
+            } else {
+                right.left = merge(Some(left), right.left.take());
+                right.update();
+                Some(right)
+            }
+        },
+    }
+}
+
+pub struct Treap<T> {
+    root: Link<T>,
+    rng: Xorshift, // the priorities only need to look random
+}
+
+impl<T> Default for Treap<T> {
+    // Seeded from the random keys the standard library makes for its hash maps, so a caller
+    // can't pick an insert order that degenerates the tree
+    fn default() -> Self {
+        let seed = RandomState::new().build_hasher().finish();
+        Self::with_seed(seed)
+    }
+}
+
+impl<T> Drop for Treap<T> {
+    fn drop(&mut self) {
+        box_tree::drop_nodes(self.root.take());
+    }
+}
+
+impl<T> Treap<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the same seed gives the same priorities, and so the same shape, for the same inserts
+    pub fn with_seed(seed: u64) -> Self {
+        Treap { root: None, rng: Xorshift::new(seed) }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    // number of levels, 0 for an empty tree
+    pub fn height(&self) -> usize {
+        box_tree::height(&self.root)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(&self.root, self.len())
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        self.iter().next()
+    }
+
+    pub fn max(&self) -> Option<&T> {
+        self.iter().next_back()
+    }
+}
+
+impl<T: Ord> Treap<T> {
+    pub fn search(&self, val: &T) -> Option<&T> {
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            match val.cmp(&node.val) {
+                Ordering::Equal => return Some(&node.val),
+                Ordering::Less => current = node.left.as_deref(),
+                Ordering::Greater => current = node.right.as_deref(),
+            }
+        }
+        None
+    }
+
+    pub fn contains(&self, val: &T) -> bool {
+        self.search(val).is_some()
+    }
+
+    // a value that is already in the treap is left as it is
+    pub fn insert(&mut self, val: T) {
+        if self.contains(&val) {
+            return;
+        }
+
+        let node = Box::new(Node { priority: self.rng.next_u64(), val, left: None, right: None, size: 1 });
+        let (smaller, bigger) = split(self.root.take(), &node.val);
+        self.root = merge(merge(smaller, Some(node)), bigger);
+    }
+
+    pub fn delete(&mut self, val: &T) -> Option<T> {
+        if !self.contains(val) {
+            return None;
+        }
+
+        // every node on the way down loses one value from its subtree
+        let mut link = &mut self.root;
+        while link.as_ref().is_some_and(|node| node.val != *val) {
+            let node = link.as_mut()?;
+            node.size -= 1;
+            link = if *val < node.val { &mut node.left } else { &mut node.right };
+        }
+
+        let mut node = link.take()?;
+        *link = merge(node.left.take(), node.right.take());
+        Some(node.val)
+    }
+
+    // Moves every value >= key into a new treap, O(log n) expected.
+    // The new treap keeps drawing priorities from where this one is.
+    pub fn split_off(&mut self, key: &T) -> Self {
+        let (smaller, bigger) = split(self.root.take(), key);
+        self.root = smaller;
+        let mut other = Self::with_seed(self.rng.next_u64());
+        other.root = bigger;
+        other
+    }
+
+    // Joins a treap whose values are all bigger than the ones in this one, O(log n) expected.
+    // Panics if the values overlap, there is no cheap way to join them otherwise.
+    pub fn merge(&mut self, mut other: Self) {
+        if let (Some(max), Some(other_min)) = (self.max(), other.min()) {
+            assert!(max < other_min, "merge needs every value of other to be bigger");
+        }
+        self.root = merge(self.root.take(), other.root.take());
+    }
+
+    // Checks every invariant: strictly increasing values in order, no node with a higher
+    // priority than its parent and the subtree sizes
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        let mut next_index = 0;
+        let mut previous = None;
+        Self::check_node(&self.root, u64::MAX, &mut next_index, &mut previous)?;
+        Ok(())
+    }
+
+    // in order walk of the subtree, returns its size
+    fn check_node<'a>(
+        link: &'a Link<T>,
+        parent_priority: u64,
+        next_index: &mut usize,
+        previous: &mut Option<&'a T>,
+    ) -> Result<usize, InvariantError> {
+        let Some(node) = link else {
+            return Ok(0);
+        };
+
+        let left = Self::check_node(&node.left, node.priority, next_index, previous)?;
+        let index = *next_index;
+        *next_index += 1;
+
+        if previous.is_some_and(|previous| *previous >= node.val) {
+            return Err(InvariantError::OutOfOrder { index });
+        }
+        *previous = Some(&node.val);
+        if node.priority > parent_priority {
+            return Err(InvariantError::Priority { index });
+        }
+
+        let right = Self::check_node(&node.right, node.priority, next_index, previous)?;
+        let actual = left + right + 1;
+        if node.size != actual {
+            return Err(InvariantError::WrongSize { index, stored: node.size, actual });
+        }
+        Ok(actual)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Treap<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for Treap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut treap = Treap::new();
+        treap.extend(iter);
+        treap
+    }
+}
+
+impl<T: Ord> Extend<T> for Treap<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(val);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use std::rc::Rc;
+
+    fn values<T: Copy>(treap: &Treap<T>) -> Vec<T> {
+        treap.iter().copied().collect()
+    }
+
+    fn seeded(values: impl IntoIterator<Item = i32>) -> Treap<i32> {
+        let mut treap = Treap::with_seed(42);
+        treap.extend(values);
+        treap
+    }
+
+    #[test]
+    fn test_empty_treap() {
+        let mut treap: Treap<i32> = Treap::new();
+        assert!(treap.is_empty());
+        assert_eq!(treap.search(&1), None);
+        assert_eq!(treap.delete(&1), None);
+        assert_eq!(treap.iter().next(), None);
+        assert_eq!(treap.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_insert_search_delete() {
+        let mut treap = seeded([5, 3, 8, 1, 4, 7, 9, 3]);
+        assert_eq!(treap.len(), 7);
+        assert_eq!(values(&treap), vec![1, 3, 4, 5, 7, 8, 9]);
+        assert_eq!(treap.search(&4), Some(&4));
+        assert!(!treap.contains(&6));
+        assert_eq!(treap.delete(&5), Some(5));
+        assert_eq!(treap.delete(&5), None);
+        assert_eq!(treap.delete(&6), None);
+        assert_eq!(treap.len(), 6);
+        assert_eq!(treap.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_sorted_inserts_stay_shallow() {
+        let mut treap = seeded(0..10_000);
+        assert_eq!(treap.validate(), Ok(()));
+        // the expected depth is about 3 log2(n) = 40, a path would be 10000
+        assert!(treap.height() < 100, "height {}", treap.height());
+
+        for val in (0..10_000).step_by(2) {
+            assert_eq!(treap.delete(&val), Some(val));
+        }
+        assert_eq!(treap.len(), 5000);
+        assert_eq!(treap.validate(), Ok(()));
+        assert!(treap.iter().copied().eq((1..10_000).step_by(2)));
+    }
+
+    #[test]
+    fn test_mixed_operations() {
+        let mut treap = Treap::with_seed(7);
+        let mut expected = BTreeSet::new();
+        let mut rng = Xorshift::new(99);
+        for step in 0..3000 {
+            let val = rng.next_u64() % 300;
+            if step % 3 == 0 {
+                assert_eq!(treap.delete(&val), expected.take(&val));
+            } else {
+                treap.insert(val);
+                expected.insert(val);
+            }
+        }
+        assert_eq!(treap.validate(), Ok(()));
+        assert!(treap.iter().eq(expected.iter()));
+        assert!(treap.iter().rev().eq(expected.iter().rev()));
+    }
+
+    #[test]
+    fn test_same_seed_same_shape() {
+        let a = seeded([3, 1, 4, 5, 9, 2, 6]);
+        let b = seeded([3, 1, 4, 5, 9, 2, 6]);
+        assert_eq!(a.root.as_ref().map(|root| root.val), b.root.as_ref().map(|root| root.val));
+        assert_eq!(a.height(), b.height());
+        // zero is not a valid xorshift state
+        let mut zero = Treap::with_seed(0);
+        zero.extend([1, 2]);
+        assert_ne!(zero.root.as_ref().unwrap().priority, 0);
+    }
+
+    #[test]
+    fn test_split_off_and_merge() {
+        let mut treap = seeded(0..100);
+        let upper = treap.split_off(&60);
+        assert_eq!(values(&treap), (0..60).collect::<Vec<_>>());
+        assert_eq!(values(&upper), (60..100).collect::<Vec<_>>());
+        assert_eq!(treap.validate(), Ok(()));
+        assert_eq!(upper.validate(), Ok(()));
+
+        let mut empty = treap.split_off(&1000);
+        assert!(empty.is_empty());
+        empty.merge(Treap::new());
+        assert!(empty.is_empty());
+
+        treap.merge(upper);
+        assert_eq!(treap.len(), 100);
+        assert_eq!(values(&treap), (0..100).collect::<Vec<_>>());
+        assert_eq!(treap.validate(), Ok(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "bigger")]
+    fn test_merge_overlapping() {
+        let mut treap = seeded(0..10);
+        treap.merge(seeded(5..15));
+    }
+
+    #[test]
+    fn test_drop_values() {
+        let alive = Rc::new(());
+        let mut treap = Treap::with_seed(1);
+        for val in 0..50 {
+            treap.insert((val, alive.clone()));
+        }
+        drop(treap.delete(&(10, alive.clone())));
+        let upper = treap.split_off(&(25, alive.clone()));
+        assert_eq!(Rc::strong_count(&alive), 50);
+        drop(upper);
+        drop(treap);
+        assert_eq!(Rc::strong_count(&alive), 1);
+    }
+
+    #[test]
+    fn test_validate_finds_broken_treaps() {
+        let mut treap = seeded(0..10);
+        let root = treap.root.as_mut().unwrap();
+        root.size += 1;
+        assert!(matches!(treap.validate(), Err(InvariantError::WrongSize { .. })));
+        let root = treap.root.as_mut().unwrap();
+        root.size -= 1;
+
+        let child = root.left.as_mut().or(root.right.as_mut()).unwrap();
+        let priority = child.priority;
+        child.priority = u64::MAX;
+        assert!(matches!(treap.validate(), Err(InvariantError::Priority { .. })));
+        let root = treap.root.as_mut().unwrap();
+        let child = root.left.as_mut().or(root.right.as_mut()).unwrap();
+        child.priority = priority;
+
+        let old = std::mem::replace(&mut treap.root.as_mut().unwrap().val, 100);
+        assert!(matches!(treap.validate(), Err(InvariantError::OutOfOrder { .. })));
+        treap.root.as_mut().unwrap().val = old;
+        assert_eq!(treap.validate(), Ok(()));
+    }
+}
@@ -0,0 +1,174 @@
+use std::fmt;
+
+// Drawing helpers shared by the binary trees. They only need a way to get the children of a
+// node and its label, so every tree keeps its own node type.
+// Both walk with an explicit stack, a degenerate binary search tree can be as deep as it is long.
+
+// Graphviz dot source, render it with `dot -Tsvg tree.dot > tree.svg`.
+// A missing child next to an existing one gets an invisible node, otherwise dot would draw a
+// lone right child straight under its parent.
+pub(crate) fn to_dot<'a, N>(
+    name: &str,
+    root: Option<&'a N>,
+    children: impl Fn(&'a N) -> (Option<&'a N>, Option<&'a N>),
+    label: impl Fn(&'a N) -> String,
+) -> String {
+    let mut out = format!("digraph {name} {{\n    node [shape=circle];\n");
+
+    let mut next_id = 0;
+    let mut stack = Vec::new();
+    if let Some(root) = root {
+        stack.push((root, next_id));
+        next_id += 1;
+    }
+
+    while let Some((node, id)) = stack.pop() {
+        out += &format!("    n{id} [label=\"{}\"];\n", escape(&label(node)));
+
+        let (left, right) = children(node);
+        if left.is_none() && right.is_none() {
+            continue;
+        }
+        for child in [left, right] {
+            let child_id = next_id;
+            next_id += 1;
+            match child {
+                Some(child) => {
+                    out += &format!("    n{id} -> n{child_id};\n");
+                    stack.push((child, child_id));
+                },
+                None => {
+                    out += &format!("    n{child_id} [style=invis];\n");
+                    out += &format!("    n{id} -> n{child_id} [style=invis];\n");
+                },
+            }
+        }
+    }
+
+    out += "}\n";
+    out
+}
+
+// quotes and backslashes escaped, new lines turned into dot line breaks
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Root,
+    Left,
+    Right,
+}
+
+// The tree turned 90 degrees counter clockwise: the root on the left, right subtrees above
+// their parent and left subtrees below.
+//
+//     /-- 18
+// /-- 15
+// |   \-- 12
+// 10
+// \-- 5
+//     \-- 3
+pub(crate) fn sideways<'a, N>(
+    f: &mut fmt::Formatter<'_>,
+    root: Option<&'a N>,
+    children: impl Fn(&'a N) -> (Option<&'a N>, Option<&'a N>),
+    label: impl Fn(&'a N) -> String,
+) -> fmt::Result {
+    // Visit expands a node into its right subtree, its own line and its left subtree
+    enum Step<'a, N> {
+        Visit(&'a N, String, Side),
+        Print(&'a N, String, Side),
+    }
+
+    let mut stack = Vec::new();
+    if let Some(root) = root {
+        stack.push(Step::Visit(root, String::new(), Side::Root));
+    }
+
+    while let Some(step) = stack.pop() {
+        match step {
+            Step::Visit(node, prefix, side) => {
+                let (left, right) = children(node);
+                // the vertical bar continues towards the parent, which is below a right child
+                // and above a left child
+                if let Some(left) = left {
+                    let bar = if side == Side::Right { "|   " } else { "    " };
+                    stack.push(Step::Visit(left, child_prefix(&prefix, side, bar), Side::Left));
+                }
+                stack.push(Step::Print(node, prefix.clone(), side));
+                if let Some(right) = right {
+                    let bar = if side == Side::Left { "|   " } else { "    " };
+                    stack.push(Step::Visit(right, child_prefix(&prefix, side, bar), Side::Right));
+                }
+            },
+            Step::Print(node, prefix, side) => {
+                let connector = match side {
+                    Side::Root => "",
+                    Side::Right => "/-- ",
+                    Side::Left => "\\-- ",
+                };
+                writeln!(f, "{prefix}{connector}{}", label(node))?;
+            },
+        }
+    }
+    Ok(())
+}
+
+// the children of the root start at the first column, deeper ones get one more bar or blank
+fn child_prefix(prefix: &str, side: Side, bar: &str) -> String {
+    match side {
+        Side::Root => String::new(),
+        _ => format!("{prefix}{bar}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // minimal tree to draw: (label, left, right)
+    struct Node(&'static str, Option<Box<Node>>, Option<Box<Node>>);
+
+    fn children(node: &Node) -> (Option<&Node>, Option<&Node>) {
+        (node.1.as_deref(), node.2.as_deref())
+    }
+
+    fn leaf(label: &'static str) -> Option<Box<Node>> {
+        Some(Box::new(Node(label, None, None)))
+    }
+
+    struct Drawing<'a>(Option<&'a Node>);
+
+    impl fmt::Display for Drawing<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            sideways(f, self.0, children, |node| node.0.to_string())
+        }
+    }
+
+    #[test]
+    fn test_sideways() {
+        let tree = Node("10", Some(Box::new(Node("5", leaf("3"), None))), Some(Box::new(Node("15", leaf("12"), leaf("18")))));
+        let expected = "    /-- 18\n/-- 15\n|   \\-- 12\n10\n\\-- 5\n    \\-- 3\n";
+        assert_eq!(Drawing(Some(&tree)).to_string(), expected);
+        assert_eq!(Drawing(None).to_string(), "");
+    }
+
+    #[test]
+    fn test_dot() {
+        let tree = Node("a\"b", None, leaf("c"));
+        let dot = to_dot("Test", Some(&tree), children, |node| node.0.to_string());
+        assert_eq!(dot, concat!(
+            "digraph Test {\n",
+            "    node [shape=circle];\n",
+            "    n0 [label=\"a\\\"b\"];\n",
+            "    n1 [style=invis];\n",
+            "    n0 -> n1 [style=invis];\n",
+            "    n0 -> n2;\n",
+            "    n2 [label=\"c\"];\n",
+            "}\n",
+        ));
+        assert_eq!(to_dot("Empty", None, children, |node| node.0.to_string()), "digraph Empty {\n    node [shape=circle];\n}\n");
+    }
+}
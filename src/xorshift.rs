@@ -0,0 +1,55 @@
+// xorshift64, a tiny pseudo random generator so the crate needs no dependencies.
+// Good enough for treap priorities, shuffled test values and bench workloads, not for anything
+// that must be hard to guess.
+pub struct Xorshift {
+    state: u64,
+}
+
+impl Xorshift {
+    // the same seed gives the same sequence
+    pub fn new(seed: u64) -> Self {
+        // xorshift is stuck at zero
+        let state = if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed };
+        Xorshift { state }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+}
+
+// deterministic shuffle of 0..n for the tests, so failures can be replayed
+#[cfg(test)]
+pub(crate) fn shuffled(n: u64) -> Vec<u64> {
+    let mut rng = Xorshift::new(0x2545_F491_4F6C_DD1D);
+    let mut values: Vec<u64> = (0..n).collect();
+    for i in (1..values.len()).rev() {
+        values.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_seed() {
+        let mut rng = Xorshift::new(0);
+        assert_ne!(rng.next_u64(), 0);
+    }
+
+    #[test]
+    fn test_shuffled() {
+        let values = shuffled(100);
+        assert_eq!(values, shuffled(100));
+        assert_ne!(values, (0..100).collect::<Vec<_>>());
+
+        let mut sorted = values;
+        sorted.sort();
+        assert_eq!(sorted, (0..100).collect::<Vec<_>>());
+    }
+}